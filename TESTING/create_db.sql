@@ -6,6 +6,35 @@ BEGIN;
     ALTER TYPE public.UserType OWNER TO devcade;
 COMMIT;
 
+BEGIN;
+    CREATE TYPE public.CatalogEventType AS ENUM (
+        'game_created',
+        'game_updated',
+        'game_binary_replaced',
+        'game_deleted',
+        'game_approved',
+        'tag_created',
+        'tag_updated',
+        'tag_deleted'
+    );
+    ALTER TYPE public.CatalogEventType OWNER TO devcade;
+COMMIT;
+
+BEGIN;
+    CREATE TYPE public.ContributorRole AS ENUM ('owner', 'developer', 'artist', 'audio');
+    ALTER TYPE public.ContributorRole OWNER TO devcade;
+COMMIT;
+
+BEGIN;
+    CREATE TYPE public.TagCategory AS ENUM ('genre', 'input', 'player_count', 'status');
+    ALTER TYPE public.TagCategory OWNER TO devcade;
+COMMIT;
+
+BEGIN;
+    CREATE TYPE public.WebhookDeliveryStatus AS ENUM ('pending', 'delivered', 'failed');
+    ALTER TYPE public.WebhookDeliveryStatus OWNER TO devcade;
+COMMIT;
+
 CREATE TABLE public.collection (
     collection_name character varying(100) NOT NULL,
     username character varying(32) NOT NULL
@@ -37,12 +66,167 @@ CREATE TABLE public.game (
     upload_date date NOT NULL,
     name character varying(128) NOT NULL,
     hash character varying(255) NOT NULL,
-    description character varying(1500) NOT NULL
+    description character varying(1500) NOT NULL,
+    created_at timestamp with time zone DEFAULT now() NOT NULL,
+    updated_at timestamp with time zone DEFAULT now() NOT NULL,
+    binary_updated_at timestamp with time zone DEFAULT now() NOT NULL,
+    banner_updated_at timestamp with time zone DEFAULT now() NOT NULL,
+    icon_updated_at timestamp with time zone DEFAULT now() NOT NULL,
+    version character varying(32),
+    entrypoint character varying(255),
+    min_client_version character varying(32),
+    min_players smallint,
+    max_players smallint,
+    controls character varying(32)[] DEFAULT '{}' NOT NULL,
+    session_minutes smallint,
+    credits character varying(128)[] DEFAULT '{}' NOT NULL,
+    repo_url character varying(255),
+    license character varying(64),
+    content_warnings character varying(64)[] DEFAULT '{}' NOT NULL
 );
 
 
 ALTER TABLE public.game OWNER TO devcade;
 
+--
+-- Name: game_changes; Type: TABLE; Schema: devcade; Owner: devcade
+--
+
+CREATE TABLE public.game_changes (
+    id bigserial NOT NULL,
+    game_id character(36) NOT NULL,
+    added boolean DEFAULT false NOT NULL,
+    transaction_id bigint DEFAULT pg_current_xact_id()::text::bigint NOT NULL,
+    changed_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+
+ALTER TABLE public.game_changes OWNER TO devcade;
+
+--
+-- Name: record_game_change; Type: FUNCTION; Schema: devcade; Owner: devcade
+--
+
+CREATE FUNCTION public.record_game_change() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        INSERT INTO public.game_changes (game_id) VALUES (OLD.id);
+    ELSE
+        INSERT INTO public.game_changes (game_id, added) VALUES (NEW.id, TG_OP = 'INSERT');
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+
+ALTER FUNCTION public.record_game_change() OWNER TO devcade;
+
+CREATE TRIGGER game_changes_record AFTER INSERT OR UPDATE OR DELETE ON public.game
+    FOR EACH ROW EXECUTE FUNCTION public.record_game_change();
+
+--
+-- Name: record_game_visibility_change; Type: FUNCTION; Schema: devcade; Owner: devcade
+--
+
+-- Hiding a game removes it from the feed, and unhiding it adds it back
+CREATE FUNCTION public.record_game_visibility_change() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        INSERT INTO public.game_changes (game_id, added) VALUES (OLD.game_id, true);
+    ELSE
+        INSERT INTO public.game_changes (game_id) VALUES (NEW.game_id);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+
+ALTER FUNCTION public.record_game_visibility_change() OWNER TO devcade;
+
+--
+-- Name: catalog_events; Type: TABLE; Schema: devcade; Owner: devcade
+--
+
+CREATE TABLE public.catalog_events (
+    id bigserial NOT NULL,
+    event_type CatalogEventType NOT NULL,
+    game_id character varying(36),
+    tag_name character varying(32),
+    created_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+
+ALTER TABLE public.catalog_events OWNER TO devcade;
+
+--
+-- Name: notify_catalog_event; Type: FUNCTION; Schema: devcade; Owner: devcade
+--
+
+CREATE FUNCTION public.notify_catalog_event() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify('catalog_events', row_to_json(NEW)::text);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+
+ALTER FUNCTION public.notify_catalog_event() OWNER TO devcade;
+
+CREATE TRIGGER catalog_events_notify AFTER INSERT ON public.catalog_events
+    FOR EACH ROW EXECUTE FUNCTION public.notify_catalog_event();
+
+--
+-- Name: publish_catalog_event; Type: FUNCTION; Schema: devcade; Owner: devcade
+--
+
+CREATE FUNCTION public.publish_catalog_event(new_event_type CatalogEventType, new_game_id text, new_tag_name text) RETURNS void AS $$
+BEGIN
+    WITH event AS (
+        INSERT INTO public.catalog_events (event_type, game_id, tag_name)
+        VALUES (new_event_type, new_game_id, new_tag_name)
+        RETURNING id, event_type
+    )
+    INSERT INTO public.webhook_deliveries (webhook_id, event_id)
+    SELECT webhooks.id, event.id FROM public.webhooks, event
+    WHERE webhooks.active AND event.event_type = ANY(webhooks.event_types);
+END;
+$$ LANGUAGE plpgsql;
+
+
+ALTER FUNCTION public.publish_catalog_event(CatalogEventType, text, text) OWNER TO devcade;
+
+--
+-- Name: publish_game_approved; Type: FUNCTION; Schema: devcade; Owner: devcade
+--
+
+CREATE FUNCTION public.publish_game_approved() RETURNS trigger AS $$
+BEGIN
+    -- Checked at commit, so replacing every tag of a game or deleting it is not an approval
+    IF EXISTS (SELECT 1 FROM public.game WHERE id = OLD.game_id)
+        AND NOT EXISTS (
+            SELECT 1 FROM public.game_tags WHERE game_id = OLD.game_id AND tag_name = 'hidden'
+        ) THEN
+        PERFORM public.publish_catalog_event('game_approved', OLD.game_id, NULL);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+
+ALTER FUNCTION public.publish_game_approved() OWNER TO devcade;
+
+--
+-- Name: game_tombstones; Type: TABLE; Schema: devcade; Owner: devcade
+--
+
+CREATE TABLE public.game_tombstones (
+    id character(36) NOT NULL,
+    deleted_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+
+ALTER TABLE public.game_tombstones OWNER TO devcade;
+
 --
 -- Name: game_tags; Type: TABLE; Schema: devcade; Owner: devcade
 --
@@ -55,6 +239,139 @@ CREATE TABLE public.game_tags (
 
 ALTER TABLE public.game_tags OWNER TO devcade;
 
+-- Removing the hidden tag from a game approves it
+CREATE CONSTRAINT TRIGGER game_tags_approved AFTER DELETE ON public.game_tags
+    DEFERRABLE INITIALLY DEFERRED
+    FOR EACH ROW WHEN (OLD.tag_name = 'hidden') EXECUTE FUNCTION public.publish_game_approved();
+
+CREATE TRIGGER game_tags_hidden_record AFTER INSERT ON public.game_tags
+    FOR EACH ROW WHEN (NEW.tag_name = 'hidden') EXECUTE FUNCTION public.record_game_visibility_change();
+
+CREATE TRIGGER game_tags_unhidden_record AFTER DELETE ON public.game_tags
+    FOR EACH ROW WHEN (OLD.tag_name = 'hidden') EXECUTE FUNCTION public.record_game_visibility_change();
+
+--
+-- Name: game_contributors; Type: TABLE; Schema: devcade; Owner: devcade
+--
+
+CREATE TABLE public.game_contributors (
+    game_id character(36) NOT NULL,
+    user_id character varying(32) NOT NULL,
+    role ContributorRole NOT NULL,
+    created_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+
+ALTER TABLE public.game_contributors OWNER TO devcade;
+
+--
+-- Name: tag_aliases; Type: TABLE; Schema: devcade; Owner: devcade
+--
+
+CREATE TABLE public.tag_aliases (
+    alias character varying(32) NOT NULL,
+    tag_name character varying(32) NOT NULL,
+    created_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+
+ALTER TABLE public.tag_aliases OWNER TO devcade;
+
+--
+-- Name: play_sessions; Type: TABLE; Schema: devcade; Owner: devcade
+--
+
+CREATE TABLE public.play_sessions (
+    id character(36) NOT NULL,
+    game_id character(36) NOT NULL,
+    cabinet_id character varying(64) NOT NULL,
+    user_id character varying(32),
+    started_at timestamp with time zone DEFAULT now() NOT NULL,
+    ended_at timestamp with time zone,
+    duration_seconds integer
+);
+
+
+ALTER TABLE public.play_sessions OWNER TO devcade;
+
+--
+-- Name: scores; Type: TABLE; Schema: devcade; Owner: devcade
+--
+
+CREATE TABLE public.scores (
+    id bigserial NOT NULL,
+    game_id character(36) NOT NULL,
+    user_id character varying(32) NOT NULL,
+    board character varying(32) DEFAULT 'default' NOT NULL,
+    score bigint NOT NULL,
+    nonce character varying(64) NOT NULL,
+    created_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+
+ALTER TABLE public.scores OWNER TO devcade;
+
+--
+-- Name: score_secrets; Type: TABLE; Schema: devcade; Owner: devcade
+--
+
+CREATE TABLE public.score_secrets (
+    game_id character(36) NOT NULL,
+    secret character varying(255) NOT NULL,
+    created_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+
+ALTER TABLE public.score_secrets OWNER TO devcade;
+
+--
+-- Name: achievements; Type: TABLE; Schema: devcade; Owner: devcade
+--
+
+CREATE TABLE public.achievements (
+    game_id character(36) NOT NULL,
+    id character varying(64) NOT NULL,
+    title character varying(128) NOT NULL,
+    description character varying(500) NOT NULL,
+    icon character varying(255),
+    hidden boolean DEFAULT false NOT NULL,
+    created_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+
+ALTER TABLE public.achievements OWNER TO devcade;
+
+--
+-- Name: achievement_unlocks; Type: TABLE; Schema: devcade; Owner: devcade
+--
+
+CREATE TABLE public.achievement_unlocks (
+    game_id character(36) NOT NULL,
+    achievement_id character varying(64) NOT NULL,
+    user_id character varying(32) NOT NULL,
+    unlocked_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+
+ALTER TABLE public.achievement_unlocks OWNER TO devcade;
+
+--
+-- Name: reviews; Type: TABLE; Schema: devcade; Owner: devcade
+--
+
+CREATE TABLE public.reviews (
+    game_id character(36) NOT NULL,
+    user_id character varying(32) NOT NULL,
+    rating smallint NOT NULL CHECK (rating BETWEEN 1 AND 5),
+    review character varying(2000),
+    hidden boolean DEFAULT false NOT NULL,
+    created_at timestamp with time zone DEFAULT now() NOT NULL,
+    updated_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+
+ALTER TABLE public.reviews OWNER TO devcade;
+
 --
 -- Name: saves_user; Type: TABLE; Schema: devcade; Owner: devcade
 --
@@ -73,7 +390,13 @@ ALTER TABLE public.saves_user OWNER TO devcade;
 
 CREATE TABLE public.tags (
     name character varying(32) NOT NULL,
-    description text
+    description text,
+    category TagCategory,
+    parent character varying(32),
+    display_order integer DEFAULT 0 NOT NULL,
+    color character varying(7),
+    icon character varying(255),
+    updated_at timestamp with time zone DEFAULT now() NOT NULL
 );
 
 
@@ -90,12 +413,104 @@ CREATE TABLE public.users (
     last_name character varying(32),
     picture character varying(255),
     admin boolean,
-    email character varying(255)
+    email character varying(255),
+    updated_at timestamp with time zone DEFAULT now() NOT NULL
 );
 
 
 ALTER TABLE public.users OWNER TO devcade;
 
+--
+-- Name: GameContributor; Type: TYPE; Schema: devcade; Owner: devcade
+--
+
+CREATE TYPE public.GameContributor AS (
+    contributor public.users,
+    role public.ContributorRole
+);
+
+
+ALTER TYPE public.GameContributor OWNER TO devcade;
+
+--
+-- Name: webhooks; Type: TABLE; Schema: devcade; Owner: devcade
+--
+
+CREATE TABLE public.webhooks (
+    id serial NOT NULL,
+    url character varying(2048) NOT NULL,
+    secret text NOT NULL,
+    event_types CatalogEventType[] NOT NULL,
+    active boolean DEFAULT true NOT NULL,
+    created_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+
+ALTER TABLE public.webhooks OWNER TO devcade;
+
+--
+-- Name: webhook_deliveries; Type: TABLE; Schema: devcade; Owner: devcade
+--
+
+CREATE TABLE public.webhook_deliveries (
+    id bigserial NOT NULL,
+    webhook_id integer NOT NULL,
+    event_id bigint NOT NULL,
+    status WebhookDeliveryStatus DEFAULT 'pending' NOT NULL,
+    attempts integer DEFAULT 0 NOT NULL,
+    next_attempt_at timestamp with time zone DEFAULT now() NOT NULL,
+    last_status_code integer,
+    last_error text,
+    delivered_at timestamp with time zone,
+    created_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+
+ALTER TABLE public.webhook_deliveries OWNER TO devcade;
+
+--
+-- Name: schema_migrations; Type: TABLE; Schema: devcade; Owner: devcade
+--
+
+CREATE TABLE public.schema_migrations (
+    version bigint NOT NULL PRIMARY KEY,
+    description text NOT NULL,
+    applied_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+
+ALTER TABLE public.schema_migrations OWNER TO devcade;
+
+INSERT INTO schema_migrations (version, description) VALUES
+(1, 'Baseline schema'),
+(2, 'Catalog, contributors, play stats, scores, achievements, reviews and webhooks'),
+(3, 'Enum values'),
+(4, 'System tags'),
+(5, 'Game change log');
+
+--
+-- Name: catalog_events catalog_events_pk; Type: CONSTRAINT; Schema: devcade; Owner: devcade
+--
+
+ALTER TABLE ONLY public.catalog_events
+    ADD CONSTRAINT catalog_events_pk PRIMARY KEY (id);
+
+
+--
+-- Name: game_changes game_changes_pk; Type: CONSTRAINT; Schema: devcade; Owner: devcade
+--
+
+ALTER TABLE ONLY public.game_changes
+    ADD CONSTRAINT game_changes_pk PRIMARY KEY (id);
+
+
+--
+-- Name: game_changes game_changes_transaction_idx; Type: INDEX; Schema: devcade; Owner: devcade
+--
+
+CREATE INDEX game_changes_transaction_idx ON public.game_changes (transaction_id);
+
+
 --
 -- Name: collection collection_pk; Type: CONSTRAINT; Schema: devcade; Owner: devcade
 --
@@ -128,6 +543,100 @@ ALTER TABLE ONLY public.game_tags
     ADD CONSTRAINT game_tags_pkey PRIMARY KEY (game_id, tag_name);
 
 
+--
+-- Name: game_tombstones game_tombstones_pk; Type: CONSTRAINT; Schema: devcade; Owner: devcade
+--
+
+ALTER TABLE ONLY public.game_tombstones
+    ADD CONSTRAINT game_tombstones_pk PRIMARY KEY (id);
+
+
+--
+-- Name: play_sessions play_sessions_pk; Type: CONSTRAINT; Schema: devcade; Owner: devcade
+--
+
+ALTER TABLE ONLY public.play_sessions
+    ADD CONSTRAINT play_sessions_pk PRIMARY KEY (id);
+
+
+--
+-- Name: play_sessions play_sessions_game_idx; Type: INDEX; Schema: devcade; Owner: devcade
+--
+
+CREATE INDEX play_sessions_game_idx ON public.play_sessions (game_id, started_at);
+
+
+--
+-- Name: scores scores_pk; Type: CONSTRAINT; Schema: devcade; Owner: devcade
+--
+
+ALTER TABLE ONLY public.scores
+    ADD CONSTRAINT scores_pk PRIMARY KEY (id);
+
+
+--
+-- Name: scores scores_board_idx; Type: INDEX; Schema: devcade; Owner: devcade
+--
+
+CREATE INDEX scores_board_idx ON public.scores (game_id, board, score DESC);
+
+
+--
+-- Name: scores scores_nonce_key; Type: CONSTRAINT; Schema: devcade; Owner: devcade
+--
+
+ALTER TABLE ONLY public.scores
+    ADD CONSTRAINT scores_nonce_key UNIQUE (game_id, nonce);
+
+
+--
+-- Name: score_secrets score_secrets_pk; Type: CONSTRAINT; Schema: devcade; Owner: devcade
+--
+
+ALTER TABLE ONLY public.score_secrets
+    ADD CONSTRAINT score_secrets_pk PRIMARY KEY (game_id);
+
+
+--
+-- Name: achievements achievements_pk; Type: CONSTRAINT; Schema: devcade; Owner: devcade
+--
+
+ALTER TABLE ONLY public.achievements
+    ADD CONSTRAINT achievements_pk PRIMARY KEY (game_id, id);
+
+
+--
+-- Name: achievement_unlocks achievement_unlocks_pk; Type: CONSTRAINT; Schema: devcade; Owner: devcade
+--
+
+ALTER TABLE ONLY public.achievement_unlocks
+    ADD CONSTRAINT achievement_unlocks_pk PRIMARY KEY (game_id, achievement_id, user_id);
+
+
+--
+-- Name: reviews reviews_pk; Type: CONSTRAINT; Schema: devcade; Owner: devcade
+--
+
+ALTER TABLE ONLY public.reviews
+    ADD CONSTRAINT reviews_pk PRIMARY KEY (game_id, user_id);
+
+
+--
+-- Name: game_contributors game_contributors_pk; Type: CONSTRAINT; Schema: devcade; Owner: devcade
+--
+
+ALTER TABLE ONLY public.game_contributors
+    ADD CONSTRAINT game_contributors_pk PRIMARY KEY (game_id, user_id);
+
+
+--
+-- Name: tag_aliases tag_aliases_pk; Type: CONSTRAINT; Schema: devcade; Owner: devcade
+--
+
+ALTER TABLE ONLY public.tag_aliases
+    ADD CONSTRAINT tag_aliases_pk PRIMARY KEY (alias);
+
+
 --
 -- Name: saves_user saves_user_pk; Type: CONSTRAINT; Schema: devcade; Owner: devcade
 --
@@ -152,6 +661,29 @@ ALTER TABLE ONLY public.users
     ADD CONSTRAINT user_pk PRIMARY KEY (id);
 
 
+--
+-- Name: webhooks webhooks_pk; Type: CONSTRAINT; Schema: devcade; Owner: devcade
+--
+
+ALTER TABLE ONLY public.webhooks
+    ADD CONSTRAINT webhooks_pk PRIMARY KEY (id);
+
+
+--
+-- Name: webhook_deliveries webhook_deliveries_pk; Type: CONSTRAINT; Schema: devcade; Owner: devcade
+--
+
+ALTER TABLE ONLY public.webhook_deliveries
+    ADD CONSTRAINT webhook_deliveries_pk PRIMARY KEY (id);
+
+
+--
+-- Name: webhook_deliveries webhook_deliveries_pending_idx; Type: INDEX; Schema: devcade; Owner: devcade
+--
+
+CREATE INDEX webhook_deliveries_pending_idx ON public.webhook_deliveries (next_attempt_at) WHERE status = 'pending';
+
+
 --
 -- Name: collection collection_user_username_fk; Type: FK CONSTRAINT; Schema: devcade; Owner: devcade
 --
@@ -184,6 +716,110 @@ ALTER TABLE ONLY public.game_tags
     ADD CONSTRAINT game_id FOREIGN KEY (game_id) REFERENCES public.game(id) ON UPDATE CASCADE ON DELETE CASCADE;
 
 
+--
+-- Name: play_sessions play_sessions_game_fk; Type: FK CONSTRAINT; Schema: devcade; Owner: devcade
+--
+
+ALTER TABLE ONLY public.play_sessions
+    ADD CONSTRAINT play_sessions_game_fk FOREIGN KEY (game_id) REFERENCES public.game(id) ON UPDATE CASCADE ON DELETE CASCADE;
+
+
+--
+-- Name: play_sessions play_sessions_user_fk; Type: FK CONSTRAINT; Schema: devcade; Owner: devcade
+--
+
+ALTER TABLE ONLY public.play_sessions
+    ADD CONSTRAINT play_sessions_user_fk FOREIGN KEY (user_id) REFERENCES public.users(id) ON UPDATE CASCADE ON DELETE SET NULL;
+
+
+--
+-- Name: scores scores_game_fk; Type: FK CONSTRAINT; Schema: devcade; Owner: devcade
+--
+
+ALTER TABLE ONLY public.scores
+    ADD CONSTRAINT scores_game_fk FOREIGN KEY (game_id) REFERENCES public.game(id) ON UPDATE CASCADE ON DELETE CASCADE;
+
+
+--
+-- Name: scores scores_user_fk; Type: FK CONSTRAINT; Schema: devcade; Owner: devcade
+--
+
+ALTER TABLE ONLY public.scores
+    ADD CONSTRAINT scores_user_fk FOREIGN KEY (user_id) REFERENCES public.users(id) ON UPDATE CASCADE ON DELETE CASCADE;
+
+
+--
+-- Name: score_secrets score_secrets_game_fk; Type: FK CONSTRAINT; Schema: devcade; Owner: devcade
+--
+
+ALTER TABLE ONLY public.score_secrets
+    ADD CONSTRAINT score_secrets_game_fk FOREIGN KEY (game_id) REFERENCES public.game(id) ON UPDATE CASCADE ON DELETE CASCADE;
+
+
+--
+-- Name: achievements achievements_game_fk; Type: FK CONSTRAINT; Schema: devcade; Owner: devcade
+--
+
+ALTER TABLE ONLY public.achievements
+    ADD CONSTRAINT achievements_game_fk FOREIGN KEY (game_id) REFERENCES public.game(id) ON UPDATE CASCADE ON DELETE CASCADE;
+
+
+--
+-- Name: achievement_unlocks achievement_unlocks_achievement_fk; Type: FK CONSTRAINT; Schema: devcade; Owner: devcade
+--
+
+ALTER TABLE ONLY public.achievement_unlocks
+    ADD CONSTRAINT achievement_unlocks_achievement_fk FOREIGN KEY (game_id, achievement_id) REFERENCES public.achievements(game_id, id) ON UPDATE CASCADE ON DELETE CASCADE;
+
+
+--
+-- Name: achievement_unlocks achievement_unlocks_user_fk; Type: FK CONSTRAINT; Schema: devcade; Owner: devcade
+--
+
+ALTER TABLE ONLY public.achievement_unlocks
+    ADD CONSTRAINT achievement_unlocks_user_fk FOREIGN KEY (user_id) REFERENCES public.users(id) ON UPDATE CASCADE ON DELETE CASCADE;
+
+
+--
+-- Name: reviews reviews_game_fk; Type: FK CONSTRAINT; Schema: devcade; Owner: devcade
+--
+
+ALTER TABLE ONLY public.reviews
+    ADD CONSTRAINT reviews_game_fk FOREIGN KEY (game_id) REFERENCES public.game(id) ON UPDATE CASCADE ON DELETE CASCADE;
+
+
+--
+-- Name: reviews reviews_user_fk; Type: FK CONSTRAINT; Schema: devcade; Owner: devcade
+--
+
+ALTER TABLE ONLY public.reviews
+    ADD CONSTRAINT reviews_user_fk FOREIGN KEY (user_id) REFERENCES public.users(id) ON UPDATE CASCADE ON DELETE CASCADE;
+
+
+--
+-- Name: game_contributors game_contributors_game_fk; Type: FK CONSTRAINT; Schema: devcade; Owner: devcade
+--
+
+ALTER TABLE ONLY public.game_contributors
+    ADD CONSTRAINT game_contributors_game_fk FOREIGN KEY (game_id) REFERENCES public.game(id) ON UPDATE CASCADE ON DELETE CASCADE;
+
+
+--
+-- Name: game_contributors game_contributors_user_fk; Type: FK CONSTRAINT; Schema: devcade; Owner: devcade
+--
+
+ALTER TABLE ONLY public.game_contributors
+    ADD CONSTRAINT game_contributors_user_fk FOREIGN KEY (user_id) REFERENCES public.users(id) ON UPDATE CASCADE ON DELETE CASCADE;
+
+
+--
+-- Name: tag_aliases tag_aliases_tag_fk; Type: FK CONSTRAINT; Schema: devcade; Owner: devcade
+--
+
+ALTER TABLE ONLY public.tag_aliases
+    ADD CONSTRAINT tag_aliases_tag_fk FOREIGN KEY (tag_name) REFERENCES public.tags(name) ON UPDATE CASCADE ON DELETE CASCADE;
+
+
 --
 -- Name: saves_user saves_user_game_game_id_fk; Type: FK CONSTRAINT; Schema: devcade; Owner: devcade
 --
@@ -200,6 +836,30 @@ ALTER TABLE ONLY public.saves_user
     ADD CONSTRAINT saves_user_user_username_fk FOREIGN KEY (username) REFERENCES public.users(id) ON UPDATE CASCADE ON DELETE CASCADE;
 
 
+--
+-- Name: webhook_deliveries webhook_deliveries_webhook_fk; Type: FK CONSTRAINT; Schema: devcade; Owner: devcade
+--
+
+ALTER TABLE ONLY public.webhook_deliveries
+    ADD CONSTRAINT webhook_deliveries_webhook_fk FOREIGN KEY (webhook_id) REFERENCES public.webhooks(id) ON UPDATE CASCADE ON DELETE CASCADE;
+
+
+--
+-- Name: webhook_deliveries webhook_deliveries_event_fk; Type: FK CONSTRAINT; Schema: devcade; Owner: devcade
+--
+
+ALTER TABLE ONLY public.webhook_deliveries
+    ADD CONSTRAINT webhook_deliveries_event_fk FOREIGN KEY (event_id) REFERENCES public.catalog_events(id) ON UPDATE CASCADE ON DELETE CASCADE;
+
+
+--
+-- Name: tags tags_parent_fk; Type: FK CONSTRAINT; Schema: devcade; Owner: devcade
+--
+
+ALTER TABLE ONLY public.tags
+    ADD CONSTRAINT tags_parent_fk FOREIGN KEY (parent) REFERENCES public.tags(name) ON UPDATE CASCADE ON DELETE SET NULL;
+
+
 --
 -- Name: game_tags tag_name; Type: FK CONSTRAINT; Schema: devcade; Owner: devcade
 --
@@ -263,7 +923,13 @@ INSERT INTO tags VALUES
 ('TestTag17', 'TestTag17 Description'),
 ('TestTag18', 'TestTag18 Description'),
 ('TestTag19', 'TestTag19 Description'),
-('TestTag20', 'TestTag20 Description');
+('TestTag20', 'TestTag20 Description'),
+('TestTagHierarchy', 'TestTagHierarchy Description');
+
+INSERT INTO tags (name, description, category) VALUES
+('authrequired', 'Required CSH Authentication to Access', 'status'),
+('hidden', 'Hidden from public game listings', 'status'),
+('featured', 'Featured on the cabinet', 'status');
 
 
 INSERT INTO game VALUES
@@ -273,14 +939,14 @@ INSERT INTO game VALUES
 ('DDDDDDDD-DDDD-DDDD-DDDD-DDDDDDDDDDDD', 'atom', '2023-03-23', 'TestGameD', '04d6c7defa5dd48067cb44a473ac8eeb17f529f5', 'TestGameD Description'),
 ('EEEEEEEE-EEEE-EEEE-EEEE-EEEEEEEEEEEE', 'joeneil', '2023-03-23', 'TestGameE', '5d4ac1284877c9262df5808b8ab0e922863f9464', 'TestGameE Description'),
 -- ('FFFFFFFF-FFFF-FFFF-FFFF-FFFFFFFFFFFF', 'mtft', '2023-03-23', 'TestGameF', 'cb838a5177364dacaaeff3724d27202729ad4427', 'TestGameF Description'),
-('GGGGGGGG-GGGG-GGGG-GGGG-GGGGGGGGGGGG', 'skyz', '2023-03-23', 'TestGameG', '3bb390de22dbc674b993e33536bd53c6851a7290', 'TestGameG Description');
--- ('HHHHHHHH-HHHH-HHHH-HHHH-HHHHHHHHHHHH', 'skyz', '2023-03-23', 'TestGameH', '579e03f4fdad803a53602808ce2cfaead7c69344', 'TestGameH Description'),
--- ('IIIIIIII-IIII-IIII-IIII-IIIIIIIIIIII', 'skyz', '2023-03-23', 'TestGameI', '6f6e1f0733bc60463d32436d2c115382ec6a801f', 'TestGameI Description'),
--- ('JJJJJJJJ-JJJJ-JJJJ-JJJJ-JJJJJJJJJJJJ', 'skyz', '2023-03-23', 'TestGameJ', 'a5e8a81726700bc1b408cb60366f232ada0e726b', 'TestGameJ Description'),
--- ('KKKKKKKK-KKKK-KKKK-KKKK-KKKKKKKKKKKK', 'skyz', '2023-03-23', 'TestGameK', '8b4290df8ecdd83dbd215fe745499c0f5e492e28', 'TestGameK Description'),
--- ('LLLLLLLL-LLLL-LLLL-LLLL-LLLLLLLLLLLL', 'skyz', '2023-03-23', 'TestGameL', 'f942b92d813a16ab1ef322e8ad7b1a15d42390b5', 'TestGameL Description'),
--- ('MMMMMMMM-MMMM-MMMM-MMMM-MMMMMMMMMMMM', 'skyz', '2023-03-23', 'TestGameM', '0d54118dcfd7105ac57008a835998f5a08488368', 'TestGameM Description'),
--- ('NNNNNNNN-NNNN-NNNN-NNNN-NNNNNNNNNNNN', 'skyz', '2023-03-23', 'TestGameN', 'e5cc1088ea7326b246364e3fa40a97020f2662b9', 'TestGameN Description'),
+('GGGGGGGG-GGGG-GGGG-GGGG-GGGGGGGGGGGG', 'skyz', '2023-03-23', 'TestGameG', '3bb390de22dbc674b993e33536bd53c6851a7290', 'TestGameG Description'),
+('HHHHHHHH-HHHH-HHHH-HHHH-HHHHHHHHHHHH', 'skyz', '2023-03-23', 'TestGameH', '579e03f4fdad803a53602808ce2cfaead7c69344', 'TestGameH Description'),
+('IIIIIIII-IIII-IIII-IIII-IIIIIIIIIIII', 'skyz', '2023-03-23', 'TestGameI', '6f6e1f0733bc60463d32436d2c115382ec6a801f', 'TestGameI Description'),
+('JJJJJJJJ-JJJJ-JJJJ-JJJJ-JJJJJJJJJJJJ', 'atom', '2023-03-23', 'TestGameJ', 'a5e8a81726700bc1b408cb60366f232ada0e726b', 'TestGameJ Description'),
+('KKKKKKKK-KKKK-KKKK-KKKK-KKKKKKKKKKKK', 'joeneil', '2023-03-23', 'TestGameK', '8b4290df8ecdd83dbd215fe745499c0f5e492e28', 'TestGameK Description'),
+('LLLLLLLL-LLLL-LLLL-LLLL-LLLLLLLLLLLL', 'skyz', '2023-03-23', 'TestGameL', 'f942b92d813a16ab1ef322e8ad7b1a15d42390b5', 'TestGameL Description'),
+('MMMMMMMM-MMMM-MMMM-MMMM-MMMMMMMMMMMM', 'skyz', '2023-03-23', 'TestGameM', '0d54118dcfd7105ac57008a835998f5a08488368', 'TestGameM Description'),
+('NNNNNNNN-NNNN-NNNN-NNNN-NNNNNNNNNNNN', 'skyz', '2023-03-23', 'TestGameN', 'e5cc1088ea7326b246364e3fa40a97020f2662b9', 'TestGameN Description');
 -- ('OOOOOOOO-OOOO-OOOO-OOOO-OOOOOOOOOOOO', 'skyz', '2023-03-23', 'TestGameO', '7f5a092a1adb4d4a62fce40d2cc24b6510b0e30a', 'TestGameO Description'),
 -- ('PPPPPPPP-PPPP-PPPP-PPPP-PPPPPPPPPPPP', 'skyz', '2023-03-23', 'TestGameP', '8d2d4871bb22b6cc79d3e10db94cd565b3fb36f4', 'TestGameP Description'),
 -- ('QQQQQQQQ-QQQQ-QQQQ-QQQQ-QQQQQQQQQQQQ', 'skyz', '2023-03-23', 'TestGameQ', '555d9338b6814340a9370d9a1d6eda87f868de08', 'TestGameQ Description'),
@@ -296,4 +962,9 @@ INSERT INTO game VALUES
 
 INSERT INTO game_tags VALUES
 ('AAAAAAAA-AAAA-AAAA-AAAA-AAAAAAAAAAAA', 'TestTag1'),
-('CCCCCCCC-CCCC-CCCC-CCCC-CCCCCCCCCCCC', 'TestTag4');
+('CCCCCCCC-CCCC-CCCC-CCCC-CCCCCCCCCCCC', 'TestTag4'),
+('MMMMMMMM-MMMM-MMMM-MMMM-MMMMMMMMMMMM', 'TestTagHierarchy'),
+('HHHHHHHH-HHHH-HHHH-HHHH-HHHHHHHHHHHH', 'hidden');
+
+INSERT INTO game_contributors (game_id, user_id, role)
+SELECT id, author, 'owner' FROM game;
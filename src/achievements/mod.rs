@@ -0,0 +1,3 @@
+pub mod routes;
+#[cfg(test)]
+pub mod tests;
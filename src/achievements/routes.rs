@@ -0,0 +1,340 @@
+use crate::{
+    logging::middleware::internal_error,
+    models::{Achievement, AchievementStats, AchievementUnlock, AppState, Game},
+    security::{acting_user, RequireApiKey},
+    telemetry::query::Traced,
+};
+use actix_web::{
+    delete, get, post, put,
+    web::{Data, Json, Path},
+    HttpRequest, HttpResponse, Responder,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{query, query_as, Postgres, Transaction};
+use utoipa::ToSchema;
+
+/// File at the root of a game zip declaring the game's achievements as a JSON array of
+/// `AchievementData`
+pub const ACHIEVEMENTS_MANIFEST: &str = "achievements.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AchievementData {
+    #[schema(example = "first_blood")]
+    pub id: String,
+    #[schema(example = "First Blood")]
+    pub title: String,
+    #[schema(example = "Break your first brick")]
+    pub description: String,
+    #[schema(example = "publish/achievements/first_blood.png")]
+    pub icon: Option<String>,
+    #[serde(default)]
+    #[schema(example = false)]
+    pub hidden: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UnlockData {
+    #[schema(example = "skyz")]
+    pub user_id: String,
+}
+
+/// Title hidden achievements are listed under until the player unlocks them
+const HIDDEN_TITLE: &str = "Hidden Achievement";
+
+/// Replace the achievements of a game with `achievements` as part of `tx`. Achievements that keep
+/// their id keep their unlocks, the unlocks of removed achievements are deleted with them.
+pub async fn replace_achievements(
+    tx: &mut Transaction<'_, Postgres>,
+    game_id: &str,
+    achievements: &[AchievementData],
+) -> Result<Vec<Achievement>, sqlx::Error> {
+    let ids: Vec<String> = achievements.iter().map(|a| a.id.clone()).collect();
+    query("DELETE FROM achievements WHERE game_id = $1 AND NOT (id = ANY($2))")
+        .bind(game_id)
+        .bind(&ids)
+        .execute(Traced(&mut *tx))
+        .await?;
+    let mut declared = vec![];
+    for achievement in achievements {
+        declared.push(
+            query_as::<_, Achievement>(
+                "
+                INSERT INTO achievements (game_id, id, title, description, icon, hidden)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                ON CONFLICT (game_id, id) DO UPDATE
+                SET title = $3, description = $4, icon = $5, hidden = $6
+                RETURNING *
+                ",
+            )
+            .bind(game_id)
+            .bind(&achievement.id)
+            .bind(&achievement.title)
+            .bind(&achievement.description)
+            .bind(&achievement.icon)
+            .bind(achievement.hidden)
+            .fetch_one(Traced(&mut *tx))
+            .await?,
+        );
+    }
+    Ok(declared)
+}
+
+#[utoipa::path(
+    context_path = "/achievements",
+    params(
+        ("game_id", description = "Unique id of game"),
+        ("X-Devcade-User" = Option<String>, Header, description = "Player whose unlocked hidden achievements are revealed, trusted alongside the API key"),
+    ),
+    responses(
+        (status = 200, description = "Achievements declared by the game. Hidden achievements the player has not unlocked have their title, description and icon redacted", body = [Achievement]),
+        (status = 500, description = "Error Created by Query"),
+    )
+)]
+#[get("/{game_id}")]
+pub async fn get_game_achievements(
+    state: Data<AppState>,
+    path: Path<(String,)>,
+    req: HttpRequest,
+) -> impl Responder {
+    let (game_id,) = path.into_inner();
+    match query_as::<_, Achievement>(
+        "
+        SELECT achievements.game_id, achievements.id, achievements.hidden, achievements.created_at,
+            CASE WHEN revealed THEN title ELSE $3 END AS title,
+            CASE WHEN revealed THEN description ELSE '' END AS description,
+            CASE WHEN revealed THEN icon END AS icon
+        FROM achievements, LATERAL (
+            SELECT NOT hidden OR EXISTS (
+                SELECT 1 FROM achievement_unlocks
+                WHERE game_id = achievements.game_id AND achievement_id = achievements.id
+                    AND user_id = $2
+            ) AS revealed
+        ) AS visibility
+        WHERE achievements.game_id = $1
+        ORDER BY achievements.created_at ASC, achievements.id ASC
+        ",
+    )
+    .bind(&game_id)
+    .bind(acting_user(&req))
+    .bind(HIDDEN_TITLE)
+    .fetch_all(Traced(&state.db))
+    .await
+    {
+        Ok(achievements) => HttpResponse::Ok().json(achievements),
+        Err(e) => internal_error("could not fetch achievements", e),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/achievements",
+    request_body(content=[AchievementData], content_type="application/json", description="Complete list of the game's achievements"),
+    responses(
+        (status = 200, description = "Replaced the game's achievements", body = [Achievement]),
+        (status = 400, description = "Missing game"),
+        (status = 401, description = "Invalid/Missing API Key"),
+        (status = 500, description = "Error Created by Query"),
+    ),
+    params(
+        ("game_id", description = "Unique id of game")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[put("/{game_id}", wrap = "RequireApiKey")]
+pub async fn declare_achievements(
+    state: Data<AppState>,
+    path: Path<(String,)>,
+    achievements: Json<Vec<AchievementData>>,
+) -> impl Responder {
+    let (game_id,) = path.into_inner();
+    if query_as::<_, Game>("SELECT * FROM game WHERE id = $1")
+        .bind(&game_id)
+        .fetch_one(Traced(&state.db))
+        .await
+        .is_err()
+    {
+        return HttpResponse::BadRequest().body("Game ID Does Not Exist");
+    }
+    let mut transaction = match state.db.begin().await {
+        Ok(transaction) => transaction,
+        Err(e) => return internal_error("could not begin transaction", e),
+    };
+    let declared = match replace_achievements(&mut transaction, &game_id, &achievements).await {
+        Ok(declared) => declared,
+        Err(e) => {
+            let _ = transaction.rollback().await;
+            return internal_error("could not replace achievements", e);
+        }
+    };
+    match transaction.commit().await {
+        Ok(_) => HttpResponse::Ok().json(declared),
+        Err(e) => internal_error("could not commit achievements", e),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/achievements",
+    responses(
+        (status = 200, description = "Removed achievement and its unlocks"),
+        (status = 400, description = "Missing achievement"),
+        (status = 401, description = "Invalid/Missing API Key"),
+        (status = 500, description = "Error Created by Deletion"),
+    ),
+    params(
+        ("game_id", description = "Unique id of game"),
+        ("achievement_id", description = "Id of the achievement within the game"),
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[delete("/{game_id}/{achievement_id}", wrap = "RequireApiKey")]
+pub async fn delete_achievement(
+    state: Data<AppState>,
+    path: Path<(String, String)>,
+) -> impl Responder {
+    let (game_id, achievement_id) = path.into_inner();
+    match query("DELETE FROM achievements WHERE game_id = $1 AND id = $2")
+        .bind(&game_id)
+        .bind(&achievement_id)
+        .execute(Traced(&state.db))
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => {
+            HttpResponse::BadRequest().body("Achievement Does Not Exist")
+        }
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => internal_error("could not delete achievement", e),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/achievements",
+    request_body(content=UnlockData, content_type="application/json", description="User that unlocked the achievement"),
+    responses(
+        (status = 201, description = "Unlocked achievement", body = AchievementUnlock),
+        (status = 200, description = "User had already unlocked the achievement", body = AchievementUnlock),
+        (status = 400, description = "Missing achievement or user"),
+        (status = 401, description = "Invalid/Missing API Key"),
+        (status = 500, description = "Error Created by Query"),
+    ),
+    params(
+        ("game_id", description = "Unique id of game"),
+        ("achievement_id", description = "Id of the achievement within the game"),
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[post("/{game_id}/{achievement_id}/unlocks", wrap = "RequireApiKey")]
+pub async fn unlock_achievement(
+    state: Data<AppState>,
+    path: Path<(String, String)>,
+    unlock: Json<UnlockData>,
+) -> impl Responder {
+    let (game_id, achievement_id) = path.into_inner();
+    match query_as::<_, AchievementUnlock>(
+        "
+        INSERT INTO achievement_unlocks (game_id, achievement_id, user_id) VALUES ($1, $2, $3)
+        ON CONFLICT DO NOTHING
+        RETURNING *
+        ",
+    )
+    .bind(&game_id)
+    .bind(&achievement_id)
+    .bind(&unlock.user_id)
+    .fetch_optional(Traced(&state.db))
+    .await
+    {
+        Ok(Some(unlock)) => HttpResponse::Created().json(unlock),
+        Ok(None) => match query_as::<_, AchievementUnlock>(
+            "
+            SELECT * FROM achievement_unlocks
+            WHERE game_id = $1 AND achievement_id = $2 AND user_id = $3
+            ",
+        )
+        .bind(&game_id)
+        .bind(&achievement_id)
+        .bind(&unlock.user_id)
+        .fetch_one(Traced(&state.db))
+        .await
+        {
+            Ok(unlock) => HttpResponse::Ok().json(unlock),
+            Err(e) => internal_error("could not fetch achievement unlock", e),
+        },
+        Err(sqlx::Error::Database(e)) if e.code().as_deref() == Some("23503") => {
+            HttpResponse::BadRequest().body("Achievement Or User Does Not Exist")
+        }
+        Err(e) => internal_error("could not unlock achievement", e),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/achievements",
+    params(
+        ("uid", description = "Unique id of user")
+    ),
+    responses(
+        (status = 200, description = "Achievements unlocked by the user across all games, newest first", body = [AchievementUnlock]),
+        (status = 500, description = "Error Created by Query"),
+    )
+)]
+#[get("/users/{uid}")]
+pub async fn get_user_unlocks(state: Data<AppState>, path: Path<(String,)>) -> impl Responder {
+    let (uid,) = path.into_inner();
+    match query_as::<_, AchievementUnlock>(
+        "SELECT * FROM achievement_unlocks WHERE user_id = $1 ORDER BY unlocked_at DESC",
+    )
+    .bind(&uid)
+    .fetch_all(Traced(&state.db))
+    .await
+    {
+        Ok(unlocks) => HttpResponse::Ok().json(unlocks),
+        Err(e) => internal_error("could not fetch achievement unlocks", e),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/achievements",
+    params(
+        ("game_id", description = "Unique id of game")
+    ),
+    responses(
+        (status = 200, description = "Share of the game's players that unlocked each achievement", body = [AchievementStats]),
+        (status = 500, description = "Error Created by Query"),
+    )
+)]
+#[get("/{game_id}/stats")]
+pub async fn get_achievement_stats(state: Data<AppState>, path: Path<(String,)>) -> impl Responder {
+    let (game_id,) = path.into_inner();
+    match query_as::<_, AchievementStats>(
+        "
+        WITH players AS (
+            SELECT user_id FROM play_sessions WHERE game_id = $1 AND user_id IS NOT NULL
+            UNION
+            SELECT user_id FROM achievement_unlocks WHERE game_id = $1
+        )
+        SELECT achievements.id AS achievement_id,
+            COUNT(achievement_unlocks.user_id) AS unlocks,
+            COALESCE(
+                100.0 * COUNT(achievement_unlocks.user_id)
+                    / NULLIF((SELECT COUNT(*) FROM players), 0),
+                0
+            )::float8 AS percentage
+        FROM achievements
+        LEFT JOIN achievement_unlocks ON achievement_unlocks.game_id = achievements.game_id
+            AND achievement_unlocks.achievement_id = achievements.id
+        WHERE achievements.game_id = $1
+        GROUP BY achievements.id, achievements.created_at
+        ORDER BY achievements.created_at ASC, achievements.id ASC
+        ",
+    )
+    .bind(&game_id)
+    .fetch_all(Traced(&state.db))
+    .await
+    {
+        Ok(stats) => HttpResponse::Ok().json(stats),
+        Err(e) => internal_error("could not fetch achievement stats", e),
+    }
+}
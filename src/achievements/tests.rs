@@ -0,0 +1,144 @@
+#[cfg(test)]
+use crate::tests::{get_test_server, TEST_GAME_C};
+use crate::{
+    achievements::routes::{AchievementData, UnlockData},
+    models::{Achievement, AchievementStats, AchievementUnlock},
+    security::USER_HEADER,
+};
+
+async fn unlock(
+    srv: &actix_test::TestServer,
+    achievement_id: &str,
+    user_id: &str,
+) -> awc::ClientResponse<actix_web::dev::Decompress<actix_web::dev::Payload>> {
+    let req = srv
+        .post(format!(
+            "/api/achievements/{}/{}/unlocks",
+            TEST_GAME_C.id, achievement_id
+        ))
+        .insert_header(("frontend_api_key", "TESTING"));
+    req.send_json(&UnlockData {
+        user_id: user_id.to_string(),
+    })
+    .await
+    .unwrap()
+}
+
+#[actix_web::test]
+async fn test_declare_achievements_unauthorized() {
+    let srv = get_test_server().await;
+    let req = srv.put(format!("/api/achievements/{}", TEST_GAME_C.id));
+    let res = req.send_json(&Vec::<AchievementData>::new()).await.unwrap();
+    assert_eq!(res.status().as_u16(), 401);
+}
+
+#[actix_web::test]
+async fn test_achievements() {
+    let srv = get_test_server().await;
+    let declared = vec![
+        AchievementData {
+            id: "first_blood".to_string(),
+            title: "First Blood".to_string(),
+            description: "Break your first brick".to_string(),
+            icon: None,
+            hidden: false,
+        },
+        AchievementData {
+            id: "secret_level".to_string(),
+            title: "Secret Level".to_string(),
+            description: "Find the secret level".to_string(),
+            icon: None,
+            hidden: true,
+        },
+    ];
+    let req = srv
+        .put(format!("/api/achievements/{}", TEST_GAME_C.id))
+        .insert_header(("frontend_api_key", "TESTING"));
+    let res = req.send_json(&declared).await.unwrap();
+    assert!(res.status().is_success());
+
+    let req = srv.get(format!("/api/achievements/{}", TEST_GAME_C.id));
+    let mut res = req.send().await.unwrap();
+    assert!(res.status().is_success());
+    let achievements = res.json::<Vec<Achievement>>().await.unwrap();
+    assert_eq!(achievements.len(), 2);
+    let secret_level = achievements
+        .iter()
+        .find(|achievement| achievement.id == "secret_level")
+        .unwrap();
+    assert!(secret_level.hidden);
+    assert_ne!(secret_level.title, "Secret Level");
+    assert!(secret_level.description.is_empty());
+
+    assert_eq!(
+        unlock(&srv, "first_blood", "qel").await.status().as_u16(),
+        201
+    );
+    assert_eq!(
+        unlock(&srv, "first_blood", "qel").await.status().as_u16(),
+        200
+    );
+    assert_eq!(
+        unlock(&srv, "first_blood", "ella").await.status().as_u16(),
+        201
+    );
+    assert_eq!(
+        unlock(&srv, "secret_level", "qel").await.status().as_u16(),
+        201
+    );
+    assert_eq!(
+        unlock(&srv, "not_declared", "qel").await.status().as_u16(),
+        400
+    );
+
+    // Hidden achievements are revealed to the players that unlocked them only
+    for (user_id, revealed) in [("qel", true), ("ella", false)] {
+        let req = srv
+            .get(format!("/api/achievements/{}", TEST_GAME_C.id))
+            .insert_header(("frontend_api_key", "TESTING"))
+            .insert_header((USER_HEADER, user_id));
+        let mut res = req.send().await.unwrap();
+        assert!(res.status().is_success());
+        let achievements = res.json::<Vec<Achievement>>().await.unwrap();
+        let secret_level = achievements
+            .iter()
+            .find(|achievement| achievement.id == "secret_level")
+            .unwrap();
+        assert_eq!(secret_level.title == "Secret Level", revealed);
+    }
+
+    let req = srv.get(format!("/api/achievements/{}/stats", TEST_GAME_C.id));
+    let mut res = req.send().await.unwrap();
+    assert!(res.status().is_success());
+    let stats = res.json::<Vec<AchievementStats>>().await.unwrap();
+    let first_blood = stats
+        .iter()
+        .find(|stat| stat.achievement_id == "first_blood")
+        .unwrap();
+    let secret_level = stats
+        .iter()
+        .find(|stat| stat.achievement_id == "secret_level")
+        .unwrap();
+    assert_eq!(first_blood.unlocks, 2);
+    assert!(first_blood.percentage > secret_level.percentage);
+
+    let req = srv.get("/api/achievements/users/qel");
+    let mut res = req.send().await.unwrap();
+    assert!(res.status().is_success());
+    let unlocks = res.json::<Vec<AchievementUnlock>>().await.unwrap();
+    assert!(unlocks
+        .iter()
+        .any(|unlock| unlock.achievement_id == "secret_level"));
+
+    let req = srv
+        .delete(format!("/api/achievements/{}/secret_level", TEST_GAME_C.id))
+        .insert_header(("frontend_api_key", "TESTING"));
+    let res = req.send().await.unwrap();
+    assert!(res.status().is_success());
+    let req = srv.get("/api/achievements/users/qel");
+    let mut res = req.send().await.unwrap();
+    let unlocks = res.json::<Vec<AchievementUnlock>>().await.unwrap();
+    assert!(!unlocks
+        .iter()
+        .any(|unlock| unlock.achievement_id == "secret_level"));
+}
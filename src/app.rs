@@ -1,29 +1,65 @@
 use crate::{
-    games::routes::{self as games, FileUploadDoc, GameData, GameUploadDoc},
-    models::{AppState, Game, GameWithTags, Tag, User, UserType},
-    tags::routes as tags,
-    users::routes as users,
+    achievements::routes::{self as achievements, AchievementData, UnlockData},
+    config::Config,
+    contributors::routes::{self as contributors, ContributorData, OwnershipTransfer},
+    events::{notify, routes as events},
+    games::{
+        manifest::{GameManifest, PlayerCount},
+        routes::{self as games, FileUploadDoc, GameData, GameSort, GameTagsPatch, GameUploadDoc},
+    },
+    health::routes::{self as health, BuildInfo, Check, Readiness},
+    metrics::{registry::Metrics, routes as metrics},
+    migrations::runner as migrations,
+    models::{
+        Achievement, AchievementStats, AchievementUnlock, AppState, CatalogEvent, CatalogEventType,
+        Contributor, ContributorRole, DailyPlays, Game, GameChange, GameChanges, GamePlayStats,
+        GameTombstone, GameWithTags, LeaderboardEntry, PlaySession, PlayStats, Review, ReviewPage,
+        Score, ScoreSecret, SyncedUser, Tag, TagAssignment, TagCategory, TagWithUsage, User,
+        UserPage, UserType, Webhook, WebhookDelivery, WebhookDeliveryStatus,
+    },
+    reviews::routes::{self as reviews, ReviewData, ReviewVisibility},
+    scores::routes::{self as scores, ScoreSubmission, ScoreWindow},
+    sessions::routes::{self as sessions, SessionEnd, SessionStart},
+    tags::routes::{self as tags, GameIds, TagMerge},
+    telemetry::export,
+    users::routes::{self as users, LoginClaims},
+    validate::ValidationErrors,
+    webhooks::{
+        delivery,
+        routes::{self as webhooks, WebhookData},
+    },
 };
 
-use actix_web::web::{self, scope, Data};
+use actix_web::{
+    rt::{self, time::sleep},
+    web::{self, scope, Data},
+};
 use aws_sdk_s3 as s3;
 use aws_sdk_s3::Endpoint;
 
-use sqlx::postgres::PgPoolOptions;
-use std::env;
+use sqlx::{postgres::PgPoolOptions, Pool, Postgres};
+use std::time::Duration;
+use tokio::sync::broadcast;
 use utoipa::{
     openapi::security::{ApiKey, ApiKeyValue, SecurityScheme},
     Modify, OpenApi,
 };
 use utoipa_swagger_ui::SwaggerUi;
 
+/// Longest wait between attempts to connect to the database at startup
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
 pub fn configure_app(cfg: &mut web::ServiceConfig) {
     #[derive(OpenApi)]
     #[openapi(
         paths(
             games::get_all_games,
+            games::get_featured_games,
+            games::get_game_changes,
             games::get_game,
             games::edit_game,
+            games::patch_game,
+            games::patch_game_tags,
             games::delete_game,
             games::add_game,
             games::get_binary,
@@ -32,18 +68,63 @@ pub fn configure_app(cfg: &mut web::ServiceConfig) {
             games::update_banner,
             games::get_icon,
             games::update_icon,
+            contributors::get_contributors,
+            contributors::get_contributor,
+            contributors::put_contributor,
+            contributors::delete_contributor,
+            contributors::transfer_ownership,
             tags::get_all_tags,
             tags::get_tag,
             tags::edit_tag,
+            tags::patch_tag,
             tags::delete_tag,
             tags::add_tag,
             tags::get_tag_games,
+            tags::merge_tag,
+            tags::add_tag_games,
+            tags::delete_tag_games,
             users::get_user,
             users::add_user,
             users::edit_user,
+            users::patch_user,
+            users::get_all_users,
+            users::get_user_games,
+            users::delete_user,
+            users::sync_user,
+            events::get_events,
+            webhooks::get_all_webhooks,
+            webhooks::add_webhook,
+            webhooks::get_webhook,
+            webhooks::edit_webhook,
+            webhooks::delete_webhook,
+            webhooks::get_webhook_deliveries,
+            sessions::start_session,
+            sessions::end_session,
+            sessions::get_all_stats,
+            sessions::get_game_stats,
+            scores::submit_score,
+            scores::rotate_score_secret,
+            scores::get_leaderboard,
+            scores::get_leaderboard_around,
+            scores::get_user_bests,
+            scores::delete_score,
+            achievements::get_game_achievements,
+            achievements::declare_achievements,
+            achievements::delete_achievement,
+            achievements::unlock_achievement,
+            achievements::get_user_unlocks,
+            achievements::get_achievement_stats,
+            reviews::get_game_reviews,
+            reviews::put_review,
+            reviews::set_review_visibility,
+            reviews::delete_review,
+            health::get_health,
+            health::get_ready,
+            health::get_version,
+            metrics::get_metrics,
         ),
         components(
-            schemas(GameData, Game, GameUploadDoc, FileUploadDoc, GameWithTags, Tag, User, UserType)
+            schemas(GameData, Game, GameUploadDoc, GameManifest, PlayerCount, FileUploadDoc, GameWithTags, GameChange, GameChanges, GameTombstone, Tag, TagCategory, TagWithUsage, TagMerge, TagAssignment, GameIds, GameTagsPatch, User, UserType, UserPage, LoginClaims, SyncedUser, Contributor, ContributorRole, ContributorData, OwnershipTransfer, CatalogEvent, CatalogEventType, Webhook, WebhookData, WebhookDelivery, WebhookDeliveryStatus, GameSort, PlaySession, SessionStart, SessionEnd, PlayStats, DailyPlays, GamePlayStats, Score, ScoreSubmission, ScoreWindow, ScoreSecret, LeaderboardEntry, Achievement, AchievementData, AchievementUnlock, AchievementStats, UnlockData, Review, ReviewData, ReviewVisibility, ReviewPage, ValidationErrors, Check, Readiness, BuildInfo)
         ),
         tags(
             (name = "DevcadeAPI", description = "")
@@ -65,13 +146,21 @@ pub fn configure_app(cfg: &mut web::ServiceConfig) {
     }
 
     let openapi = ApiDoc::openapi();
+    cfg.service(health::get_health)
+        .service(health::get_ready)
+        .service(health::get_version)
+        .service(metrics::get_metrics);
     cfg.service(
         scope("/api")
             .service(
                 scope("/games")
                     .service(games::get_all_games)
+                    .service(games::get_featured_games)
+                    .service(games::get_game_changes)
                     .service(games::get_game)
                     .service(games::edit_game)
+                    .service(games::patch_game)
+                    .service(games::patch_game_tags)
                     .service(games::delete_game)
                     .service(games::add_game)
                     .service(games::get_binary)
@@ -79,48 +168,139 @@ pub fn configure_app(cfg: &mut web::ServiceConfig) {
                     .service(games::get_banner)
                     .service(games::update_banner)
                     .service(games::get_icon)
-                    .service(games::update_icon),
+                    .service(games::update_icon)
+                    .service(contributors::get_contributors)
+                    .service(contributors::get_contributor)
+                    .service(contributors::put_contributor)
+                    .service(contributors::delete_contributor)
+                    .service(contributors::transfer_ownership),
             )
             .service(
                 scope("/tags")
                     .service(tags::get_all_tags)
                     .service(tags::get_tag)
                     .service(tags::edit_tag)
+                    .service(tags::patch_tag)
                     .service(tags::delete_tag)
                     .service(tags::add_tag)
-                    .service(tags::get_tag_games),
+                    .service(tags::get_tag_games)
+                    .service(tags::merge_tag)
+                    .service(tags::add_tag_games)
+                    .service(tags::delete_tag_games),
             )
             .service(
                 scope("/users")
+                    .service(users::get_all_users)
+                    .service(users::sync_user)
                     .service(users::get_user)
+                    .service(users::get_user_games)
                     .service(users::add_user)
-                    .service(users::edit_user),
+                    .service(users::edit_user)
+                    .service(users::patch_user)
+                    .service(users::delete_user),
+            )
+            .service(scope("/events").service(events::get_events))
+            .service(
+                scope("/webhooks")
+                    .service(webhooks::get_all_webhooks)
+                    .service(webhooks::add_webhook)
+                    .service(webhooks::get_webhook)
+                    .service(webhooks::edit_webhook)
+                    .service(webhooks::delete_webhook)
+                    .service(webhooks::get_webhook_deliveries),
+            )
+            .service(
+                scope("/sessions")
+                    .service(sessions::start_session)
+                    .service(sessions::end_session)
+                    .service(sessions::get_all_stats)
+                    .service(sessions::get_game_stats),
+            )
+            .service(
+                scope("/scores")
+                    .service(scores::get_user_bests)
+                    .service(scores::submit_score)
+                    .service(scores::rotate_score_secret)
+                    .service(scores::get_leaderboard)
+                    .service(scores::get_leaderboard_around)
+                    .service(scores::delete_score),
+            )
+            .service(
+                scope("/achievements")
+                    .service(achievements::get_user_unlocks)
+                    .service(achievements::get_game_achievements)
+                    .service(achievements::declare_achievements)
+                    .service(achievements::delete_achievement)
+                    .service(achievements::unlock_achievement)
+                    .service(achievements::get_achievement_stats),
+            )
+            .service(
+                scope("/reviews")
+                    .service(reviews::get_game_reviews)
+                    .service(reviews::put_review)
+                    .service(reviews::set_review_visibility)
+                    .service(reviews::delete_review),
             )
             .service(SwaggerUi::new("/docs/{_:.*}").url("/api/api-doc/openapi.json", openapi)),
     );
 }
 
-pub async fn get_app_data() -> Data<AppState> {
+/// Connect to Postgres, retrying with exponential backoff so the API can start before the
+/// database does
+async fn connect(config: &Config) -> Result<Pool<Postgres>, sqlx::Error> {
+    let mut backoff = Duration::from_millis(500);
+    let mut attempt = 1;
+    loop {
+        match PgPoolOptions::new()
+            .max_connections(config.db_max_connections)
+            .min_connections(config.db_min_connections)
+            .connect(&config.sql_uri)
+            .await
+        {
+            Ok(pool) => return Ok(pool),
+            Err(e) if attempt < config.db_connect_attempts => {
+                log::warn!(
+                    "Could not connect to the database (attempt {} of {}), retrying in {:?}: {}",
+                    attempt,
+                    config.db_connect_attempts,
+                    backoff,
+                    e
+                );
+                sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+pub async fn get_app_data(config: Config) -> Result<Data<AppState>, sqlx::Error> {
     let shared_config = aws_config::load_from_env().await;
 
     // Create an S3 config from the shared config and override the endpoint resolver.
     let s3_config = s3::config::Builder::from(&shared_config)
-        .endpoint_resolver(Endpoint::immutable(
-            env::var("S3_ENDPOINT")
-                .unwrap_or("https://s3.csh.rit.edu".to_string())
-                .parse()
-                .unwrap(),
-        ))
-        //.endpoint_resolver(s3::Endpoint::immutable("https://s3.csh.rit.edu".parse().unwrap()))
+        .endpoint_resolver(Endpoint::immutable(config.s3_endpoint.clone()))
         .build();
     let s3_conn = s3::Client::from_conf(s3_config);
 
-    let pool = PgPoolOptions::new()
-        .connect(&env::var("SQL_URI").unwrap())
-        .await
-        .unwrap();
-    Data::new(AppState {
+    let pool = connect(&config).await?;
+    migrations::run(&pool).await?;
+    if let Some(telemetry) = &config.telemetry {
+        export::start(telemetry);
+    }
+    let (events, _) = broadcast::channel(256);
+    rt::spawn(notify::listen(pool.clone(), events.clone()));
+    rt::spawn(delivery::run(
+        pool.clone(),
+        config.webhook_secret_key.clone(),
+        events.subscribe(),
+    ));
+    Ok(Data::new(AppState {
         db: pool,
         s3: s3_conn.clone(),
-    })
+        events,
+        config,
+        metrics: Metrics::default(),
+    }))
 }
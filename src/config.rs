@@ -0,0 +1,258 @@
+use crate::{
+    cors::CorsConfig, security::SecretKey, telemetry::export::TelemetryConfig,
+    users::ldap::LdapConfig,
+};
+use actix_web::http::Uri;
+use std::{env, error::Error, fmt, fs, net::SocketAddr, str::FromStr};
+use url::Url;
+
+/// Settings of the API, checked once at startup
+#[derive(Clone)]
+pub struct Config {
+    /// Address the server listens on
+    pub listen_address: SocketAddr,
+    pub sql_uri: String,
+    pub db_max_connections: u32,
+    pub db_min_connections: u32,
+    /// Times to try connecting to the database at startup before giving up
+    pub db_connect_attempts: u32,
+    /// Key frontends send in `frontend_api_key` to reach protected routes
+    pub frontend_api_key: String,
+    pub s3_endpoint: Uri,
+    pub s3_games_bucket: String,
+    /// Largest JSON body accepted, in bytes
+    pub json_limit: usize,
+    /// Largest multipart upload accepted, in bytes
+    pub upload_limit: usize,
+    /// Origins browsers may call the API from, and what they may send and read
+    pub cors: CorsConfig,
+    /// Key webhook secrets are encrypted with, webhooks can only be managed and delivered
+    /// when `WEBHOOK_SECRET_KEY` is set
+    pub webhook_secret_key: Option<SecretKey>,
+    /// Key score secrets are encrypted with, scores can only be submitted and score secrets
+    /// rotated when `SCORE_SECRET_KEY` is set
+    pub score_secret_key: Option<SecretKey>,
+    /// Directory used to enrich CSH logins, only configured when `LDAP_URI` is set
+    pub ldap: Option<LdapConfig>,
+    /// Collector traces are exported to, only configured when `OTEL_EXPORTER_OTLP_ENDPOINT`
+    /// is set
+    pub telemetry: Option<TelemetryConfig>,
+}
+
+/// Stands in for secrets when the config is logged
+const REDACTED: &str = "<redacted>";
+
+impl fmt::Debug for Config {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Config")
+            .field("listen_address", &self.listen_address)
+            .field("sql_uri", &REDACTED)
+            .field("db_max_connections", &self.db_max_connections)
+            .field("db_min_connections", &self.db_min_connections)
+            .field("db_connect_attempts", &self.db_connect_attempts)
+            .field("frontend_api_key", &REDACTED)
+            .field("s3_endpoint", &self.s3_endpoint)
+            .field("s3_games_bucket", &self.s3_games_bucket)
+            .field("json_limit", &self.json_limit)
+            .field("upload_limit", &self.upload_limit)
+            .field("cors", &self.cors)
+            .field("webhook_secret_key", &self.webhook_secret_key)
+            .field("score_secret_key", &self.score_secret_key)
+            .field(
+                "ldap",
+                &self.ldap.as_ref().map(|ldap| LdapConfig {
+                    bind_password: REDACTED.to_string(),
+                    ..ldap.clone()
+                }),
+            )
+            .field("telemetry", &self.telemetry)
+            .finish()
+    }
+}
+
+/// Every missing or invalid setting found while loading the config
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError(pub Vec<String>);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid configuration:")?;
+        for problem in &self.0 {
+            write!(f, "\n  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ConfigError {}
+
+/// Raw settings by key, with environment variables taking precedence over the config file
+struct Source {
+    file: toml::Table,
+    problems: Vec<String>,
+}
+
+impl Source {
+    fn get(&self, key: &str) -> Option<String> {
+        // An empty variable counts as unset, so it does not hide the file
+        env::var(key.to_uppercase())
+            .ok()
+            .filter(|value| !value.is_empty())
+            .or_else(|| match self.file.get(key)? {
+                toml::Value::String(value) => Some(value.clone()),
+                toml::Value::Array(values) => Some(
+                    values
+                        .iter()
+                        .map(|value| value.as_str().map_or(value.to_string(), str::to_string))
+                        .collect::<Vec<_>>()
+                        .join(","),
+                ),
+                value => Some(value.to_string()),
+            })
+            .filter(|value| !value.is_empty())
+    }
+
+    fn required(&mut self, key: &str) -> String {
+        self.get(key).unwrap_or_else(|| {
+            self.problems
+                .push(format!("{} is required", key.to_uppercase()));
+            String::new()
+        })
+    }
+
+    fn optional<T: FromStr>(&mut self, key: &str, default: T) -> T
+    where
+        T::Err: fmt::Display,
+    {
+        self.parsed(key).unwrap_or(default)
+    }
+
+    fn parsed<T: FromStr>(&mut self, key: &str) -> Option<T>
+    where
+        T::Err: fmt::Display,
+    {
+        match self.get(key)?.parse::<T>() {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.problems
+                    .push(format!("{} is invalid: {}", key.to_uppercase(), e));
+                None
+            }
+        }
+    }
+
+    fn list_or(&self, key: &str, default: &[&str]) -> Vec<String> {
+        self.get(key).map_or(
+            default.iter().map(|item| item.to_string()).collect(),
+            |value| Source::list(&value),
+        )
+    }
+
+    fn list(value: &str) -> Vec<String> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+impl Config {
+    /// Load the config from environment variables, over the TOML file at `CONFIG_FILE` if
+    /// one is given. Keys in the file are the lowercase names of the variables.
+    pub fn load() -> Result<Config, ConfigError> {
+        let file = match env::var("CONFIG_FILE") {
+            Ok(path) => fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|contents| toml::from_str(&contents).map_err(|e| e.to_string()))
+                .map_err(|e| {
+                    ConfigError(vec![format!("CONFIG_FILE {} is invalid: {}", path, e)])
+                })?,
+            Err(_) => toml::Table::new(),
+        };
+        let mut source = Source {
+            file,
+            problems: vec![],
+        };
+        let sql_uri = source.required("sql_uri");
+        let frontend_api_key = source.required("frontend_api_key");
+        let s3_games_bucket = source.required("s3_games_bucket");
+        // DOMAIN is the older name for a single allowed origin
+        let cors_origins = match source.get("cors_origins").or_else(|| source.get("domain")) {
+            Some(origins) => Source::list(&origins),
+            None => {
+                source.required("cors_origins");
+                vec![]
+            }
+        };
+        let config = Config {
+            listen_address: source.optional("listen_address", ([0, 0, 0, 0], 8080).into()),
+            sql_uri,
+            db_max_connections: source.optional("db_max_connections", 10),
+            db_min_connections: source.optional("db_min_connections", 0),
+            db_connect_attempts: source.optional("db_connect_attempts", 10),
+            frontend_api_key,
+            s3_endpoint: source.optional("s3_endpoint", Uri::from_static("https://s3.csh.rit.edu")),
+            s3_games_bucket,
+            json_limit: source.optional("json_limit", 2 * 1024 * 1024),
+            upload_limit: source.optional("upload_limit", 50 * 1024 * 1024),
+            cors: CorsConfig {
+                origins: cors_origins,
+                public_origins: source.list_or("cors_public_origins", &[]),
+                allowed_headers: source.list_or("cors_allowed_headers", &[]),
+                exposed_headers: source.list_or(
+                    "cors_exposed_headers",
+                    &["ETag", "Content-Disposition", "Location"],
+                ),
+                allow_credentials: source.optional("cors_allow_credentials", false),
+                max_age: source.optional("cors_max_age", 3600),
+            },
+            webhook_secret_key: source.parsed("webhook_secret_key"),
+            score_secret_key: source.parsed("score_secret_key"),
+            ldap: source.get("ldap_uri").map(|uri| LdapConfig {
+                uri,
+                bind_dn: source.get("ldap_bind_dn"),
+                bind_password: source.get("ldap_bind_password").unwrap_or_default(),
+                user_base: source
+                    .get("ldap_user_base")
+                    .unwrap_or("cn=users,cn=accounts,dc=csh,dc=rit,dc=edu".to_string()),
+                group_base: source
+                    .get("ldap_group_base")
+                    .unwrap_or("cn=groups,cn=accounts,dc=csh,dc=rit,dc=edu".to_string()),
+                admin_groups: Source::list(
+                    &source
+                        .get("ldap_admin_groups")
+                        .unwrap_or("devcade".to_string()),
+                ),
+            }),
+            telemetry: source
+                .get("otel_exporter_otlp_endpoint")
+                .map(|endpoint| TelemetryConfig {
+                    endpoint,
+                    service_name: source
+                        .get("otel_service_name")
+                        .unwrap_or("devcade-api".to_string()),
+                }),
+        };
+        source.problems.extend(config.cors.problems());
+        if let Some(telemetry) = &config.telemetry {
+            match Url::parse(&telemetry.endpoint) {
+                Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {}
+                _ => source.problems.push(format!(
+                    "OTEL_EXPORTER_OTLP_ENDPOINT {} is not an http or https URL",
+                    telemetry.endpoint
+                )),
+            }
+        }
+        if config.db_min_connections > config.db_max_connections {
+            source
+                .problems
+                .push("DB_MIN_CONNECTIONS is above DB_MAX_CONNECTIONS".to_string());
+        }
+        match source.problems.is_empty() {
+            true => Ok(config),
+            false => Err(ConfigError(source.problems)),
+        }
+    }
+}
@@ -0,0 +1,365 @@
+use crate::{
+    logging::middleware::internal_error,
+    models::{AppState, Contributor, ContributorRole},
+    security::{acting_user, RequireApiKey},
+    telemetry::query::Traced,
+};
+use actix_web::{
+    delete, get, post, put,
+    web::{Data, Json, Path},
+    HttpRequest, HttpResponse, Responder,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{query, query_as, query_scalar, Pool, Postgres, Transaction};
+use utoipa::ToSchema;
+
+const CONTRIBUTORS: &str = "
+    SELECT users.*, game_contributors.role FROM game_contributors
+    JOIN users ON users.id = game_contributors.user_id
+    WHERE game_contributors.game_id = $1
+";
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ContributorData {
+    #[schema(example = "developer")]
+    pub role: ContributorRole,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct OwnershipTransfer {
+    /// Current owner giving up ownership, kept on as a developer
+    #[schema(example = "skyz")]
+    pub from: String,
+    #[schema(example = "ella")]
+    pub to: String,
+}
+
+/// Whether `uid` is one of the owners of the game
+pub async fn is_owner(db: &Pool<Postgres>, game_id: &str, uid: &str) -> Result<bool, sqlx::Error> {
+    query_scalar::<_, bool>(
+        "
+        SELECT EXISTS (
+            SELECT 1 FROM game_contributors WHERE game_id = $1 AND user_id = $2 AND role = 'owner'
+        )
+        ",
+    )
+    .bind(game_id)
+    .bind(uid)
+    .fetch_one(Traced(db))
+    .await
+}
+
+/// Turn away changes to the contributors of a game unless they are made for one of its owners
+async fn require_owner(
+    db: &Pool<Postgres>,
+    game_id: &str,
+    req: &HttpRequest,
+) -> Result<(), HttpResponse> {
+    let user = match acting_user(req) {
+        Some(user) => user,
+        None => return Err(HttpResponse::Forbidden().body("Only An Owner May Change Contributors")),
+    };
+    match is_owner(db, game_id, user).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(HttpResponse::Forbidden().body("Only An Owner May Change Contributors")),
+        Err(e) => Err(internal_error("could not check ownership", e)),
+    }
+}
+
+/// Make sure the game is left with an owner, and that its author is one of them
+async fn finish_ownership_change(
+    mut transaction: Transaction<'_, Postgres>,
+    game_id: &str,
+) -> Result<Option<Vec<Contributor>>, sqlx::Error> {
+    let owner = query_scalar::<_, String>(
+        "
+        SELECT user_id FROM game_contributors WHERE game_id = $1 AND role = 'owner'
+        ORDER BY (user_id = (SELECT author FROM game WHERE id = $1)) DESC, created_at ASC
+        LIMIT 1
+        ",
+    )
+    .bind(game_id)
+    .fetch_optional(Traced(&mut transaction))
+    .await?;
+    let owner = match owner {
+        Some(owner) => owner,
+        None => {
+            transaction.rollback().await?;
+            return Ok(None);
+        }
+    };
+    query("UPDATE game SET author = $2, updated_at = now() WHERE id = $1 AND author <> $2")
+        .bind(game_id)
+        .bind(&owner)
+        .execute(Traced(&mut transaction))
+        .await?;
+    let contributors = query_as::<_, Contributor>(&format!(
+        "{} ORDER BY game_contributors.role ASC, game_contributors.created_at ASC",
+        CONTRIBUTORS
+    ))
+    .bind(game_id)
+    .fetch_all(Traced(&mut transaction))
+    .await?;
+    transaction.commit().await?;
+    Ok(Some(contributors))
+}
+
+#[utoipa::path(
+    context_path = "/games",
+    params(
+        ("id", description = "Unique id of game")
+    ),
+    responses(
+        (status = 200, description = "Contributors of the game with their roles, owners first", body = [Contributor]),
+        (status = 500, description = "Error Created by Query"),
+    )
+)]
+#[get("/{id}/contributors")]
+pub async fn get_contributors(state: Data<AppState>, path: Path<(String,)>) -> impl Responder {
+    let (id,) = path.into_inner();
+    match query_as::<_, Contributor>(&format!(
+        "{} ORDER BY game_contributors.role ASC, game_contributors.created_at ASC",
+        CONTRIBUTORS
+    ))
+    .bind(&id)
+    .fetch_all(Traced(&state.db))
+    .await
+    {
+        Ok(contributors) => HttpResponse::Ok().json(contributors),
+        Err(e) => internal_error("could not fetch contributors", e),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/games",
+    params(
+        ("id", description = "Unique id of game"),
+        ("uid", description = "Unique id of user"),
+    ),
+    responses(
+        (status = 200, description = "Role of the user on the game, used for ownership checks", body = Contributor),
+        (status = 400, description = "User is not a contributor"),
+        (status = 500, description = "Error Created by Query"),
+    )
+)]
+#[get("/{id}/contributors/{uid}")]
+pub async fn get_contributor(
+    state: Data<AppState>,
+    path: Path<(String, String)>,
+) -> impl Responder {
+    let (id, uid) = path.into_inner();
+    match query_as::<_, Contributor>(&format!("{} AND users.id = $2", CONTRIBUTORS))
+        .bind(&id)
+        .bind(&uid)
+        .fetch_optional(Traced(&state.db))
+        .await
+    {
+        Ok(Some(contributor)) => HttpResponse::Ok().json(contributor),
+        Ok(None) => HttpResponse::BadRequest().body("User Is Not A Contributor"),
+        Err(e) => internal_error("could not fetch contributor", e),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/games",
+    request_body(content=ContributorData, content_type="application/json", description="Role of the contributor"),
+    responses(
+        (status = 200, description = "Added contributor or changed their role", body = [Contributor]),
+        (status = 400, description = "Missing game or user, or the game would be left without an owner"),
+        (status = 401, description = "Invalid/Missing API Key"),
+        (status = 403, description = "Request not made for an owner of the game"),
+        (status = 500, description = "Error Created by Query"),
+    ),
+    params(
+        ("id", description = "Unique id of game"),
+        ("uid", description = "Unique id of user"),
+        ("X-Devcade-User" = String, Header, description = "Owner of the game making the change, trusted alongside the API key"),
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[put("/{id}/contributors/{uid}", wrap = "RequireApiKey")]
+pub async fn put_contributor(
+    state: Data<AppState>,
+    path: Path<(String, String)>,
+    contributor: Json<ContributorData>,
+    req: HttpRequest,
+) -> impl Responder {
+    let (id, uid) = path.into_inner();
+    if let Err(res) = require_owner(&state.db, &id, &req).await {
+        return res;
+    }
+    let mut transaction = match state.db.begin().await {
+        Ok(transaction) => transaction,
+        Err(e) => return internal_error("could not begin transaction", e),
+    };
+    if let Err(e) = query(
+        "
+        INSERT INTO game_contributors (game_id, user_id, role) VALUES ($1, $2, $3)
+        ON CONFLICT (game_id, user_id) DO UPDATE SET role = $3
+        ",
+    )
+    .bind(&id)
+    .bind(&uid)
+    .bind(contributor.role)
+    .execute(Traced(&mut transaction))
+    .await
+    {
+        let _ = transaction.rollback().await;
+        return match e {
+            sqlx::Error::Database(e) if e.code().as_deref() == Some("23503") => {
+                HttpResponse::BadRequest().body("Game Or User Does Not Exist")
+            }
+            e => internal_error("could not save contributor", e),
+        };
+    }
+    match finish_ownership_change(transaction, &id).await {
+        Ok(Some(contributors)) => HttpResponse::Ok().json(contributors),
+        Ok(None) => HttpResponse::BadRequest().body("Game Must Keep An Owner"),
+        Err(e) => internal_error("could not finish ownership change", e),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/games",
+    responses(
+        (status = 200, description = "Removed contributor", body = [Contributor]),
+        (status = 400, description = "Missing contributor, or the game would be left without an owner"),
+        (status = 401, description = "Invalid/Missing API Key"),
+        (status = 403, description = "Request not made for an owner of the game"),
+        (status = 500, description = "Error Created by Deletion"),
+    ),
+    params(
+        ("id", description = "Unique id of game"),
+        ("uid", description = "Unique id of user"),
+        ("X-Devcade-User" = String, Header, description = "Owner of the game making the change, trusted alongside the API key"),
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[delete("/{id}/contributors/{uid}", wrap = "RequireApiKey")]
+pub async fn delete_contributor(
+    state: Data<AppState>,
+    path: Path<(String, String)>,
+    req: HttpRequest,
+) -> impl Responder {
+    let (id, uid) = path.into_inner();
+    if let Err(res) = require_owner(&state.db, &id, &req).await {
+        return res;
+    }
+    let mut transaction = match state.db.begin().await {
+        Ok(transaction) => transaction,
+        Err(e) => return internal_error("could not begin transaction", e),
+    };
+    match query("DELETE FROM game_contributors WHERE game_id = $1 AND user_id = $2")
+        .bind(&id)
+        .bind(&uid)
+        .execute(Traced(&mut transaction))
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => {
+            let _ = transaction.rollback().await;
+            return HttpResponse::BadRequest().body("User Is Not A Contributor");
+        }
+        Ok(_) => {}
+        Err(e) => {
+            let _ = transaction.rollback().await;
+            return internal_error("could not delete contributor", e);
+        }
+    }
+    match finish_ownership_change(transaction, &id).await {
+        Ok(Some(contributors)) => HttpResponse::Ok().json(contributors),
+        Ok(None) => HttpResponse::BadRequest().body("Game Must Keep An Owner"),
+        Err(e) => internal_error("could not finish ownership change", e),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/games",
+    request_body(content=OwnershipTransfer, content_type="application/json", description="Owner giving up the game and the user receiving it"),
+    responses(
+        (status = 200, description = "Transferred ownership", body = [Contributor]),
+        (status = 400, description = "Current user is not an owner, or missing game or user"),
+        (status = 401, description = "Invalid/Missing API Key"),
+        (status = 403, description = "Request not made for an owner of the game"),
+        (status = 500, description = "Error Created by Query"),
+    ),
+    params(
+        ("id", description = "Unique id of game"),
+        ("X-Devcade-User" = String, Header, description = "Owner of the game making the change, trusted alongside the API key"),
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[post("/{id}/owner", wrap = "RequireApiKey")]
+pub async fn transfer_ownership(
+    state: Data<AppState>,
+    path: Path<(String,)>,
+    transfer: Json<OwnershipTransfer>,
+    req: HttpRequest,
+) -> impl Responder {
+    let (id,) = path.into_inner();
+    if let Err(res) = require_owner(&state.db, &id, &req).await {
+        return res;
+    }
+    match is_owner(&state.db, &id, &transfer.from).await {
+        Ok(true) => {}
+        Ok(false) => return HttpResponse::BadRequest().body("User Is Not An Owner"),
+        Err(e) => return internal_error("could not check ownership", e),
+    }
+    let mut transaction = match state.db.begin().await {
+        Ok(transaction) => transaction,
+        Err(e) => return internal_error("could not begin transaction", e),
+    };
+    if let Err(e) = query(
+        "
+        INSERT INTO game_contributors (game_id, user_id, role) VALUES ($1, $2, 'owner')
+        ON CONFLICT (game_id, user_id) DO UPDATE SET role = 'owner'
+        ",
+    )
+    .bind(&id)
+    .bind(&transfer.to)
+    .execute(Traced(&mut transaction))
+    .await
+    {
+        let _ = transaction.rollback().await;
+        return match e {
+            sqlx::Error::Database(e) if e.code().as_deref() == Some("23503") => {
+                HttpResponse::BadRequest().body("User Does Not Exist")
+            }
+            e => internal_error("could not add new owner", e),
+        };
+    }
+    if transfer.from != transfer.to {
+        if let Err(e) = query(
+            "UPDATE game_contributors SET role = 'developer' WHERE game_id = $1 AND user_id = $2",
+        )
+        .bind(&id)
+        .bind(&transfer.from)
+        .execute(Traced(&mut transaction))
+        .await
+        {
+            let _ = transaction.rollback().await;
+            return internal_error("could not demote previous owner", e);
+        }
+        if let Err(e) =
+            query("UPDATE game SET author = $2, updated_at = now() WHERE id = $1 AND author = $3")
+                .bind(&id)
+                .bind(&transfer.to)
+                .bind(&transfer.from)
+                .execute(Traced(&mut transaction))
+                .await
+        {
+            let _ = transaction.rollback().await;
+            return internal_error("could not update game author", e);
+        }
+    }
+    match finish_ownership_change(transaction, &id).await {
+        Ok(Some(contributors)) => HttpResponse::Ok().json(contributors),
+        Ok(None) => HttpResponse::BadRequest().body("Game Must Keep An Owner"),
+        Err(e) => internal_error("could not finish ownership change", e),
+    }
+}
@@ -0,0 +1,133 @@
+#[cfg(test)]
+use crate::tests::{get_test_server, TEST_GAME_J};
+use crate::{
+    contributors::routes::{ContributorData, OwnershipTransfer},
+    models::{Contributor, ContributorRole, GameWithTags},
+    security::USER_HEADER,
+};
+
+async fn put_contributor(
+    srv: &actix_test::TestServer,
+    acting_user: &str,
+    uid: &str,
+    role: ContributorRole,
+) -> awc::ClientResponse<actix_web::dev::Decompress<actix_web::dev::Payload>> {
+    let req = srv
+        .put(format!(
+            "/api/games/{}/contributors/{}",
+            TEST_GAME_J.id, uid
+        ))
+        .insert_header(("frontend_api_key", "TESTING"))
+        .insert_header((USER_HEADER, acting_user));
+    req.send_json(&ContributorData { role }).await.unwrap()
+}
+
+async fn transfer(srv: &actix_test::TestServer, from: &str, to: &str) -> Vec<Contributor> {
+    let req = srv
+        .post(format!("/api/games/{}/owner", TEST_GAME_J.id))
+        .insert_header(("frontend_api_key", "TESTING"))
+        .insert_header((USER_HEADER, from));
+    let mut res = req
+        .send_json(&OwnershipTransfer {
+            from: from.to_string(),
+            to: to.to_string(),
+        })
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    res.json::<Vec<Contributor>>().await.unwrap()
+}
+
+#[actix_web::test]
+async fn test_put_contributor_unauthorized() {
+    let srv = get_test_server().await;
+    let req = srv.put(format!("/api/games/{}/contributors/ella", TEST_GAME_J.id));
+    let res = req
+        .send_json(&ContributorData {
+            role: ContributorRole::Artist,
+        })
+        .await
+        .unwrap();
+    assert_eq!(res.status().as_u16(), 401);
+}
+
+#[actix_web::test]
+async fn test_contributors() {
+    let srv = get_test_server().await;
+    let author = TEST_GAME_J.author.as_str();
+    assert!(
+        put_contributor(&srv, author, "ella", ContributorRole::Artist)
+            .await
+            .status()
+            .is_success()
+    );
+    assert_eq!(
+        put_contributor(&srv, "ella", "ella", ContributorRole::Owner)
+            .await
+            .status()
+            .as_u16(),
+        403
+    );
+    assert_eq!(
+        put_contributor(&srv, author, author, ContributorRole::Developer)
+            .await
+            .status()
+            .as_u16(),
+        400
+    );
+
+    let req = srv.get(format!("/api/games/{}", TEST_GAME_J.id));
+    let mut res = req.send().await.unwrap();
+    let game = res.json::<GameWithTags>().await.unwrap();
+    let contributors: Vec<(&str, ContributorRole)> = game
+        .contributors
+        .iter()
+        .map(|contributor| (contributor.user.id.as_str(), contributor.role))
+        .collect();
+    assert_eq!(
+        contributors,
+        vec![
+            (author, ContributorRole::Owner),
+            ("ella", ContributorRole::Artist)
+        ]
+    );
+
+    let contributors = transfer(&srv, author, "ella").await;
+    assert!(contributors
+        .iter()
+        .any(|c| c.user.id == "ella" && c.role == ContributorRole::Owner));
+    let req = srv.get(format!(
+        "/api/games/{}/contributors/{}",
+        TEST_GAME_J.id, author
+    ));
+    let mut res = req.send().await.unwrap();
+    assert_eq!(
+        res.json::<Contributor>().await.unwrap().role,
+        ContributorRole::Developer
+    );
+    let req = srv.get(format!("/api/games/{}", TEST_GAME_J.id));
+    let mut res = req.send().await.unwrap();
+    assert_eq!(res.json::<GameWithTags>().await.unwrap().author, "ella");
+
+    transfer(&srv, "ella", author).await;
+    let req = srv
+        .delete(format!("/api/games/{}/contributors/ella", TEST_GAME_J.id))
+        .insert_header(("frontend_api_key", "TESTING"));
+    let res = req.send().await.unwrap();
+    assert_eq!(res.status().as_u16(), 403);
+    let req = srv
+        .delete(format!("/api/games/{}/contributors/ella", TEST_GAME_J.id))
+        .insert_header(("frontend_api_key", "TESTING"))
+        .insert_header((USER_HEADER, author));
+    let res = req.send().await.unwrap();
+    assert!(res.status().is_success());
+    let req = srv
+        .delete(format!(
+            "/api/games/{}/contributors/{}",
+            TEST_GAME_J.id, author
+        ))
+        .insert_header(("frontend_api_key", "TESTING"))
+        .insert_header((USER_HEADER, author));
+    let res = req.send().await.unwrap();
+    assert_eq!(res.status().as_u16(), 400);
+}
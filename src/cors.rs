@@ -0,0 +1,165 @@
+use crate::security::{API_KEY_NAME, SIGNATURE_HEADER, USER_HEADER};
+use actix_cors::Cors;
+use actix_web::{
+    dev::RequestHead,
+    http::{
+        header::{self, HeaderName, HeaderValue},
+        Method,
+    },
+};
+use url::Url;
+
+/// Headers frontends need to send for the API to work at all
+const ALLOWED_HEADERS: [&str; 7] = [
+    "authorization",
+    "accept",
+    "content-type",
+    "if-match",
+    API_KEY_NAME,
+    USER_HEADER,
+    SIGNATURE_HEADER,
+];
+
+/// Which browsers may call the API and what they may see of its responses
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorsConfig {
+    /// Origins allowed to make any request, as `https://devcade.csh.rit.edu`,
+    /// `https://*.csh.rit.edu` for any subdomain, or `*` for any origin
+    pub origins: Vec<String>,
+    /// Further origins allowed to read (`GET` and `HEAD`) but not to make edits
+    pub public_origins: Vec<String>,
+    /// Request headers allowed on top of the ones the API itself reads
+    pub allowed_headers: Vec<String>,
+    /// Response headers scripts may read
+    pub exposed_headers: Vec<String>,
+    /// Whether browsers may send cookies and HTTP authentication along
+    pub allow_credentials: bool,
+    /// Seconds browsers may cache a preflight response for
+    pub max_age: usize,
+}
+
+impl CorsConfig {
+    /// Settings that are not valid, by config key
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = vec![];
+        for (key, origins) in [
+            ("CORS_ORIGINS", &self.origins),
+            ("CORS_PUBLIC_ORIGINS", &self.public_origins),
+        ] {
+            for origin in origins.iter().filter(|origin| !is_origin_pattern(origin)) {
+                problems.push(format!("{} entry {} is not an origin", key, origin));
+            }
+            if self.allow_credentials && origins.iter().any(|origin| origin == "*") {
+                problems.push(format!(
+                    "{} may not allow every origin while CORS_ALLOW_CREDENTIALS is set",
+                    key
+                ));
+            }
+        }
+        for (key, headers) in [
+            ("CORS_ALLOWED_HEADERS", &self.allowed_headers),
+            ("CORS_EXPOSED_HEADERS", &self.exposed_headers),
+        ] {
+            for name in headers
+                .iter()
+                .filter(|name| HeaderName::try_from(name.as_str()).is_err())
+            {
+                problems.push(format!("{} entry {} is not a header name", key, name));
+            }
+        }
+        problems
+    }
+
+    fn allows(&self, origin: &str, req: &RequestHead) -> bool {
+        self.origins
+            .iter()
+            .any(|pattern| origin_matches(pattern, origin))
+            || (is_read(req)
+                && self
+                    .public_origins
+                    .iter()
+                    .any(|pattern| origin_matches(pattern, origin)))
+    }
+}
+
+fn is_origin_pattern(pattern: &str) -> bool {
+    if pattern == "*" {
+        return true;
+    }
+    match Url::parse(&pattern.replacen("://*.", "://wildcard.", 1)) {
+        Ok(url) => {
+            (url.scheme() == "http" || url.scheme() == "https")
+                && url.host_str().is_some()
+                && url.path() == "/"
+                && !pattern.ends_with('/')
+                && url.query().is_none()
+                && url.fragment().is_none()
+        }
+        Err(_) => false,
+    }
+}
+
+/// Whether `origin` is the origin `pattern` names, or one of the subdomains it covers
+pub fn origin_matches(pattern: &str, origin: &str) -> bool {
+    if pattern == "*" {
+        return true;
+    }
+    match pattern.split_once("://*.") {
+        Some((scheme, domain)) => {
+            let subdomain = origin
+                .strip_prefix(scheme)
+                .and_then(|origin| origin.strip_prefix("://"))
+                .and_then(|host| host.strip_suffix(domain))
+                .and_then(|host| host.strip_suffix('.'));
+            matches!(subdomain, Some(subdomain) if !subdomain.is_empty()
+                && subdomain
+                    .split('.')
+                    .all(|label| !label.is_empty() && label.chars().all(is_label_char)))
+        }
+        None => pattern.eq_ignore_ascii_case(origin),
+    }
+}
+
+fn is_label_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-'
+}
+
+/// Whether a request, or the request a preflight is asking about, only reads
+fn is_read(req: &RequestHead) -> bool {
+    let method = match req.method {
+        Method::OPTIONS => req
+            .headers()
+            .get(header::ACCESS_CONTROL_REQUEST_METHOD)
+            .and_then(|method| Method::from_bytes(method.as_bytes()).ok()),
+        ref method => Some(method.clone()),
+    };
+    matches!(method, Some(Method::GET | Method::HEAD))
+}
+
+/// CORS middleware enforcing `config`, wrapped around the whole app
+pub fn cors(config: &CorsConfig) -> Cors {
+    let policy = config.clone();
+    let cors = Cors::default()
+        .allowed_origin_fn(move |origin: &HeaderValue, req: &RequestHead| {
+            matches!(origin.to_str(), Ok(origin) if policy.allows(origin, req))
+        })
+        .allowed_methods(vec!["GET", "HEAD", "POST", "PUT", "PATCH", "DELETE"])
+        .allowed_headers(
+            ALLOWED_HEADERS
+                .iter()
+                .copied()
+                .chain(config.allowed_headers.iter().map(String::as_str))
+                .filter_map(|name| HeaderName::try_from(name).ok()),
+        )
+        .expose_headers(
+            config
+                .exposed_headers
+                .iter()
+                .filter_map(|name| HeaderName::try_from(name.as_str()).ok()),
+        )
+        .max_age(config.max_age);
+    match config.allow_credentials {
+        true => cors.supports_credentials(),
+        false => cors,
+    }
+}
@@ -0,0 +1,71 @@
+use actix_web::{
+    http::header::{self, EntityTag, Header, IfMatch},
+    HttpRequest, HttpResponse,
+};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use sqlx::FromRow;
+
+/// A row along with the time it last changed, which versions it for `ETag`s
+#[derive(Debug, FromRow)]
+pub struct Versioned<T> {
+    #[sqlx(flatten)]
+    pub resource: T,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Strong entity tag of a resource last changed at `updated_at`
+pub fn etag(updated_at: &DateTime<Utc>) -> header::ETag {
+    header::ETag(EntityTag::new_strong(
+        updated_at.timestamp_micros().to_string(),
+    ))
+}
+
+/// Versions of a resource an edit may be applied to, taken from the `If-Match` header
+#[derive(Debug, Clone, PartialEq)]
+pub enum Precondition {
+    Any,
+    Versions(Vec<DateTime<Utc>>),
+}
+
+impl Precondition {
+    /// Edits must say which version they were based on, so requests without `If-Match`
+    /// are turned away with `428 Precondition Required`
+    pub fn from_request(req: &HttpRequest) -> Result<Precondition, HttpResponse> {
+        if !req.headers().contains_key(header::IF_MATCH) {
+            return Err(HttpResponse::PreconditionRequired().body("If-Match Header Required"));
+        }
+        match IfMatch::parse(req) {
+            Ok(IfMatch::Any) => Ok(Precondition::Any),
+            Ok(IfMatch::Items(tags)) => Ok(Precondition::Versions(
+                tags.iter()
+                    .filter(|tag| !tag.weak)
+                    .filter_map(|tag| tag.tag().parse::<i64>().ok())
+                    .filter_map(NaiveDateTime::from_timestamp_micros)
+                    .map(|updated_at| DateTime::<Utc>::from_utc(updated_at, Utc))
+                    .collect(),
+            )),
+            Err(_) => Err(HttpResponse::BadRequest().body("Invalid If-Match Header")),
+        }
+    }
+
+    pub fn matches(&self, updated_at: &DateTime<Utc>) -> bool {
+        match self {
+            Precondition::Any => true,
+            Precondition::Versions(versions) => versions.contains(updated_at),
+        }
+    }
+
+    /// Versions to guard an `UPDATE` with, as in `($n::timestamptz[] IS NULL OR updated_at =
+    /// ANY($n))`, `None` when any version may be replaced
+    pub fn versions(&self) -> Option<Vec<DateTime<Utc>>> {
+        match self {
+            Precondition::Any => None,
+            Precondition::Versions(versions) => Some(versions.clone()),
+        }
+    }
+}
+
+/// Response for an edit based on an outdated version of the resource
+pub fn precondition_failed() -> HttpResponse {
+    HttpResponse::PreconditionFailed().body("Resource Was Modified Since It Was Read")
+}
@@ -0,0 +1,4 @@
+pub mod notify;
+pub mod routes;
+#[cfg(test)]
+pub mod tests;
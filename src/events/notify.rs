@@ -0,0 +1,125 @@
+use crate::{
+    models::{CatalogEvent, CatalogEventType},
+    telemetry::query::Traced,
+};
+use actix_web::rt::time::sleep;
+use sqlx::{postgres::PgListener, query, query_as, query_scalar, Pool, Postgres, Transaction};
+use std::time::Duration;
+use tokio::sync::broadcast::Sender;
+
+pub const EVENTS_CHANNEL: &str = "catalog_events";
+
+/// Record a catalog event in the transaction making the change it describes, so the event is
+/// published exactly when the change commits. The insert trigger on `catalog_events` fans it
+/// out to every replica through `NOTIFY`, which Postgres sends on commit, and the stored row
+/// lets clients resume from their last seen event id. `publish_catalog_event` also queues a
+/// delivery for every active webhook subscribed to the event, and is what the trigger
+/// publishing `game_approved` events calls as well.
+pub async fn publish(
+    transaction: &mut Transaction<'_, Postgres>,
+    event_type: CatalogEventType,
+    game_id: Option<&str>,
+    tag_name: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    query("SELECT publish_catalog_event($1, $2, $3)")
+        .bind(event_type)
+        .bind(game_id)
+        .bind(tag_name)
+        .execute(Traced(transaction))
+        .await
+        .map(|_| ())
+}
+
+/// Forward `NOTIFY` payloads from Postgres to the in-process broadcast channel,
+/// reconnecting if the listener connection is lost. Notifications sent while disconnected are
+/// dropped by Postgres, so events stored since the last one forwarded are replayed on reconnect.
+pub async fn listen(db: Pool<Postgres>, sender: Sender<CatalogEvent>) {
+    let mut last_seen = None;
+    loop {
+        let mut listener = match PgListener::connect_with(&db).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                log::warn!("Could not connect the catalog event listener: {}", e);
+                sleep(Duration::from_secs(5)).await;
+                continue;
+            }
+        };
+        if let Err(e) = listener.listen(EVENTS_CHANNEL).await {
+            log::warn!("Could not listen for catalog events: {}", e);
+            sleep(Duration::from_secs(5)).await;
+            continue;
+        }
+        loop {
+            last_seen = match catch_up(&db, &sender, last_seen).await {
+                Ok(last_seen) => last_seen,
+                Err(e) => {
+                    log::warn!("Could not replay missed catalog events: {}", e);
+                    sleep(Duration::from_secs(5)).await;
+                    continue;
+                }
+            };
+            match forward(&mut listener, &sender, &mut last_seen).await {
+                // The listener reconnects on its next receive, after replaying what was missed
+                Ok(()) => log::warn!("Catalog event listener lost its connection"),
+                Err(e) => {
+                    log::warn!("Catalog event listener failed: {}", e);
+                    sleep(Duration::from_secs(5)).await;
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Send events stored after `last_seen`, returning the id of the newest one. Events from
+/// before the listener first started are left to clients resuming with `Last-Event-ID`.
+async fn catch_up(
+    db: &Pool<Postgres>,
+    sender: &Sender<CatalogEvent>,
+    last_seen: Option<i64>,
+) -> Result<Option<i64>, sqlx::Error> {
+    let last_seen = match last_seen {
+        Some(last_seen) => last_seen,
+        None => {
+            return query_scalar::<_, Option<i64>>("SELECT max(id) FROM catalog_events")
+                .fetch_one(db)
+                .await
+                .map(|newest| Some(newest.unwrap_or(0)))
+        }
+    };
+    let missed =
+        query_as::<_, CatalogEvent>("SELECT * FROM catalog_events WHERE id > $1 ORDER BY id ASC")
+            .bind(last_seen)
+            .fetch_all(db)
+            .await?;
+    let newest = missed.last().map_or(last_seen, |event| event.id);
+    for event in missed {
+        let _ = sender.send(event);
+    }
+    Ok(Some(newest))
+}
+
+/// Forward notifications until the connection is lost
+async fn forward(
+    listener: &mut PgListener,
+    sender: &Sender<CatalogEvent>,
+    last_seen: &mut Option<i64>,
+) -> Result<(), sqlx::Error> {
+    while let Some(notification) = listener.try_recv().await? {
+        let event = match serde_json::from_str::<CatalogEvent>(notification.payload()) {
+            Ok(event) => event,
+            Err(e) => {
+                log::warn!("Could not parse catalog event notification: {}", e);
+                continue;
+            }
+        };
+        // Already sent while catching up
+        if matches!(*last_seen, Some(last_seen) if event.id <= last_seen) {
+            continue;
+        }
+        *last_seen = Some(event.id);
+        // No subscribers is not an error, the event is still stored for resuming
+        let _ = sender.send(event);
+    }
+    Ok(())
+}
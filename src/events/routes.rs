@@ -0,0 +1,127 @@
+use crate::{
+    logging::middleware::internal_error,
+    models::{AppState, CatalogEvent},
+    telemetry::query::Traced,
+};
+use actix_web::{
+    get,
+    rt::time::timeout,
+    web::{Bytes, Data, Query},
+    HttpRequest, HttpResponse, Responder,
+};
+use futures::stream::{self, StreamExt};
+use serde::Deserialize;
+use sqlx::query_as;
+use std::time::Duration;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use utoipa::IntoParams;
+
+const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+/// Most stored events replayed on one connection
+const BACKLOG_LIMIT: i64 = 1000;
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct EventsQuery {
+    /// Resume after this event id. The `Last-Event-ID` header takes precedence
+    last_event_id: Option<i64>,
+}
+
+fn sse_frame(event: &CatalogEvent) -> Bytes {
+    let data = serde_json::to_string(event).unwrap_or_default();
+    Bytes::from(format!(
+        "id: {}\nevent: {}\ndata: {}\n\n",
+        event.id,
+        event.event_type.name(),
+        data
+    ))
+}
+
+async fn next_frame(
+    mut receiver: Receiver<CatalogEvent>,
+    last_sent: i64,
+) -> Option<(
+    Result<Bytes, actix_web::Error>,
+    (Receiver<CatalogEvent>, i64),
+)> {
+    loop {
+        match timeout(KEEPALIVE_INTERVAL, receiver.recv()).await {
+            // Already delivered from the backlog
+            Ok(Ok(event)) if event.id <= last_sent => continue,
+            Ok(Ok(event)) => return Some((Ok(sse_frame(&event)), (receiver, event.id))),
+            // End the stream so the client reconnects with Last-Event-ID and replays what it missed
+            Ok(Err(RecvError::Lagged(_))) | Ok(Err(RecvError::Closed)) => return None,
+            Err(_) => {
+                return Some((
+                    Ok(Bytes::from_static(b": keepalive\n\n")),
+                    (receiver, last_sent),
+                ))
+            }
+        }
+    }
+}
+
+#[utoipa::path(
+    context_path = "/events",
+    params(
+        EventsQuery,
+        ("Last-Event-ID" = Option<i64>, Header, description = "Resume after this event id")
+    ),
+    responses(
+        (status = 200, description = "Server-Sent Events stream of catalog changes. At most 1000 missed events are replayed, after which the stream ends so the client resumes from the last one", body = CatalogEvent, content_type = "text/event-stream"),
+        (status = 500, description = "Error Created by Query"),
+    )
+)]
+#[get("/")]
+pub async fn get_events(
+    req: HttpRequest,
+    state: Data<AppState>,
+    params: Query<EventsQuery>,
+) -> impl Responder {
+    let last_event_id = req
+        .headers()
+        .get("Last-Event-ID")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<i64>().ok())
+        .or(params.last_event_id);
+    // Subscribe before reading the backlog so nothing is lost in between
+    let receiver = state.events.subscribe();
+    let backlog = match last_event_id {
+        Some(last_event_id) => {
+            match query_as::<_, CatalogEvent>(
+                "SELECT * FROM catalog_events WHERE id > $1 ORDER BY id ASC LIMIT $2",
+            )
+            .bind(last_event_id)
+            .bind(BACKLOG_LIMIT)
+            .fetch_all(Traced(&state.db))
+            .await
+            {
+                Ok(backlog) => backlog,
+                Err(e) => return internal_error("could not fetch event backlog", e),
+            }
+        }
+        None => vec![],
+    };
+    // The client resumes from the end of a full backlog when the stream ends, instead of being
+    // sent live events that would leave a gap after it
+    let truncated = backlog.len() as i64 == BACKLOG_LIMIT;
+    let last_sent = backlog
+        .last()
+        .map(|event| event.id)
+        .or(last_event_id)
+        .unwrap_or(0);
+    let backlog = stream::iter(
+        backlog
+            .into_iter()
+            .map(|event| Ok::<_, actix_web::Error>(sse_frame(&event))),
+    );
+    let live = stream::unfold((receiver, last_sent), |(receiver, last_sent)| {
+        next_frame(receiver, last_sent)
+    });
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(match truncated {
+            true => backlog.left_stream(),
+            false => backlog.chain(live).right_stream(),
+        })
+}
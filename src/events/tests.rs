@@ -0,0 +1,78 @@
+use crate::models::Tag;
+#[cfg(test)]
+use crate::tests::get_test_server;
+use actix_web::rt::time::timeout;
+use futures::{Stream, StreamExt};
+use std::time::Duration;
+
+/// Everything sent on an event stream until `needle` shows up, failing if it takes too long
+async fn read_until<S, E>(stream: &mut S, needle: &str) -> String
+where
+    S: Stream<Item = Result<actix_web::web::Bytes, E>> + Unpin,
+    E: std::fmt::Debug,
+{
+    let mut received = String::new();
+    timeout(Duration::from_secs(10), async {
+        while !received.contains(needle) {
+            let chunk = stream.next().await.unwrap().unwrap();
+            received.push_str(std::str::from_utf8(&chunk).unwrap());
+        }
+    })
+    .await
+    .unwrap_or_else(|_| panic!("{} was not sent, got {:?}", needle, received));
+    received
+}
+
+#[actix_web::test]
+async fn test_get_events_stream() {
+    let srv = get_test_server().await;
+    let req = srv.get("/api/events/");
+    let res = req.send().await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(
+        res.headers().get("Content-Type").unwrap(),
+        "text/event-stream"
+    );
+}
+
+#[actix_web::test]
+async fn test_get_events_resume() {
+    let srv = get_test_server().await;
+    let new_tag = Tag {
+        name: "EVENT_RESUME_TAG".to_string(),
+        description: "Tag created before subscribing".to_string(),
+        ..Default::default()
+    };
+    let req = srv
+        .post("/api/tags/")
+        .insert_header(("frontend_api_key", "TESTING"));
+    let res = req.send_json(&new_tag).await.unwrap();
+    assert!(res.status().is_success());
+    let req = srv
+        .get("/api/events/")
+        .insert_header(("Last-Event-ID", "0"));
+    let mut res = req.send().await.unwrap();
+    assert!(res.status().is_success());
+    let received = read_until(&mut res, "EVENT_RESUME_TAG").await;
+    assert!(received.contains("event: tag_created"));
+}
+
+#[actix_web::test]
+async fn test_get_events_live() {
+    let srv = get_test_server().await;
+    let req = srv.get("/api/events/");
+    let mut res = req.send().await.unwrap();
+    assert!(res.status().is_success());
+    let new_tag = Tag {
+        name: "EVENT_LIVE_TAG".to_string(),
+        description: "Tag created while subscribed".to_string(),
+        ..Default::default()
+    };
+    let req = srv
+        .post("/api/tags/")
+        .insert_header(("frontend_api_key", "TESTING"));
+    let res_tag = req.send_json(&new_tag).await.unwrap();
+    assert!(res_tag.status().is_success());
+    let received = read_until(&mut res, "EVENT_LIVE_TAG").await;
+    assert!(received.contains("event: tag_created"));
+}
@@ -0,0 +1,200 @@
+use crate::{
+    achievements::routes::{AchievementData, ACHIEVEMENTS_MANIFEST},
+    tags::routes::SYSTEM_TAGS,
+    telemetry::query::Traced,
+    validate::ValidationErrors,
+};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use sqlx::{query_scalar, Pool, Postgres};
+use std::{error::Error, io::Read};
+use utoipa::ToSchema;
+use zip::read::ZipArchive;
+
+pub const JSON_MANIFEST: &str = "devcade.json";
+pub const TOML_MANIFEST: &str = "devcade.toml";
+
+/// Controls a game can require from the cabinet
+pub const CONTROLS: [&str; 3] = ["joystick", "buttons", "trackball"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PlayerCount {
+    #[schema(example = 1)]
+    pub min: i16,
+    #[schema(example = 2)]
+    pub max: i16,
+}
+
+/// Metadata a game declares about itself in a `devcade.json` or `devcade.toml` at the root of
+/// its zip. Declared values take precedence over the fields of the upload form.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct GameManifest {
+    #[schema(example = "BrickBreaker")]
+    pub name: Option<String>,
+    /// Semantic version of the game, must increase with every binary update
+    #[schema(example = "1.2.0")]
+    pub version: Option<String>,
+    #[schema(example = "Break bricks, get points")]
+    pub description: Option<String>,
+    /// Executable to launch, relative to the zip root
+    #[schema(example = "publish/BrickBreaker")]
+    pub entrypoint: Option<String>,
+    pub players: Option<PlayerCount>,
+    #[schema(example = json!(["joystick", "buttons"]))]
+    pub controls: Option<Vec<String>>,
+    #[schema(example = json!(["arcade"]))]
+    pub tags: Option<Vec<String>>,
+    /// Oldest cabinet client able to run the game
+    #[schema(example = "2.0.0")]
+    pub min_client_version: Option<String>,
+    /// Expected length of a play session in minutes
+    #[schema(example = 5)]
+    pub session_minutes: Option<i16>,
+    #[schema(example = json!(["Ella - Art", "Skyz - Music"]))]
+    pub credits: Option<Vec<String>>,
+    #[schema(example = "https://github.com/ComputerScienceHouse/BrickBreaker")]
+    pub repo_url: Option<String>,
+    #[schema(example = "MIT")]
+    pub license: Option<String>,
+    #[schema(example = json!(["flashing lights"]))]
+    pub content_warnings: Option<Vec<String>>,
+    pub achievements: Option<Vec<AchievementData>>,
+}
+
+impl GameManifest {
+    /// Read and check the manifest of a game zip, if it has one. Achievements not declared in the
+    /// manifest are read from `achievements.json`.
+    pub fn read<R: Read + std::io::Seek>(
+        zip_archive: &mut ZipArchive<R>,
+    ) -> Result<GameManifest, Box<dyn Error>> {
+        let json = read_file(zip_archive, JSON_MANIFEST)?;
+        let toml = read_file(zip_archive, TOML_MANIFEST)?;
+        let mut manifest = match (json, toml) {
+            (Some(_), Some(_)) => {
+                return Err(format!(
+                    "only one of {} and {} may be provided",
+                    JSON_MANIFEST, TOML_MANIFEST
+                )
+                .into())
+            }
+            (Some(json), None) => serde_json::from_str::<GameManifest>(&json)
+                .map_err(|e| format!("invalid {}: {}", JSON_MANIFEST, e))?,
+            (None, Some(toml)) => toml::from_str::<GameManifest>(&toml)
+                .map_err(|e| format!("invalid {}: {}", TOML_MANIFEST, e))?,
+            (None, None) => GameManifest::default(),
+        };
+        if manifest.achievements.is_none() {
+            if let Some(achievements) = read_file(zip_archive, ACHIEVEMENTS_MANIFEST)? {
+                manifest.achievements = Some(
+                    serde_json::from_str::<Vec<AchievementData>>(&achievements)
+                        .map_err(|e| format!("invalid {}: {}", ACHIEVEMENTS_MANIFEST, e))?,
+                );
+            }
+        }
+        manifest.check(zip_archive)?;
+        Ok(manifest)
+    }
+
+    fn check<R: Read + std::io::Seek>(
+        &self,
+        zip_archive: &mut ZipArchive<R>,
+    ) -> Result<(), Box<dyn Error>> {
+        if let Some(version) = &self.version {
+            Version::parse(version).map_err(|e| format!("invalid version {}: {}", version, e))?;
+        }
+        if let Some(version) = &self.min_client_version {
+            Version::parse(version)
+                .map_err(|e| format!("invalid min_client_version {}: {}", version, e))?;
+        }
+        if let Some(entrypoint) = &self.entrypoint {
+            match zip_archive.by_name(entrypoint) {
+                Ok(file) if file.is_file() => {}
+                _ => return Err(format!("entrypoint {} not found", entrypoint).into()),
+            }
+        }
+        if let Some(players) = &self.players {
+            check_players(players.min, players.max)?;
+        }
+        check_controls(self.controls.iter().flatten())?;
+        if matches!(self.session_minutes, Some(minutes) if minutes < 1) {
+            return Err("session_minutes must be positive".into());
+        }
+        Ok(())
+    }
+
+    /// Check the manifest against the catalog: declared tags must exist and not be system tags,
+    /// and an update of `game_id` must declare a newer version than the one already published.
+    pub async fn validate(
+        &self,
+        db: &Pool<Postgres>,
+        game_id: Option<&str>,
+        errors: &mut ValidationErrors,
+    ) -> Result<(), sqlx::Error> {
+        if let Some(tags) = &self.tags {
+            for tag in tags
+                .iter()
+                .filter(|tag| SYSTEM_TAGS.contains(&tag.as_str()))
+            {
+                errors.add("tags", format!("{} is a system tag", tag));
+            }
+            errors.tags_exist(db, "tags", tags).await?;
+        }
+        if let (Some(version), Some(game_id)) = (&self.version, game_id) {
+            let published =
+                query_scalar::<_, Option<String>>("SELECT version FROM game WHERE id = $1")
+                    .bind(game_id)
+                    .fetch_optional(Traced(db))
+                    .await?
+                    .flatten();
+            // The declared version was checked when the manifest was read, and the published one
+            // when it was declared
+            if let (Ok(declared), Some(Ok(current))) = (
+                Version::parse(version),
+                published.as_deref().map(Version::parse),
+            ) {
+                if declared <= current {
+                    errors.add(
+                        "version",
+                        format!(
+                            "{} is not newer than published version {}",
+                            declared, current
+                        ),
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn check_players(min: i16, max: i16) -> Result<(), Box<dyn Error>> {
+    if min < 1 || max < min {
+        return Err("players must have 1 <= min <= max".into());
+    }
+    Ok(())
+}
+
+pub fn check_controls<'a>(
+    controls: impl IntoIterator<Item = &'a String>,
+) -> Result<(), Box<dyn Error>> {
+    if let Some(control) = controls
+        .into_iter()
+        .find(|control| !CONTROLS.contains(&control.as_str()))
+    {
+        return Err(format!("unknown control {}", control).into());
+    }
+    Ok(())
+}
+
+fn read_file<R: Read + std::io::Seek>(
+    zip_archive: &mut ZipArchive<R>,
+    name: &str,
+) -> Result<Option<String>, Box<dyn Error>> {
+    let mut file = match zip_archive.by_name(name) {
+        Ok(file) => file,
+        Err(_) => return Ok(None),
+    };
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)?;
+    Ok(Some(contents))
+}
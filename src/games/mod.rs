@@ -1,3 +1,4 @@
+pub mod manifest;
 pub mod routes;
 #[cfg(test)]
 pub mod tests;
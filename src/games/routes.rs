@@ -1,34 +1,48 @@
 use crate::{
-    models::{AppState, Game, GameWithTags},
-    security::RequireApiKey,
+    achievements::routes::replace_achievements,
+    etag::{self, Precondition, Versioned},
+    events::notify,
+    games::manifest::{check_controls, check_players, GameManifest},
+    logging::{json, middleware::internal_error},
+    models::{
+        AppState, CatalogEventType, Game, GameChange, GameChanges, GameTombstone, GameWithTags,
+        TagAssignment,
+    },
+    patch,
+    security::{has_api_key, RequireApiKey, USER_HEADER},
+    tags::routes::{
+        has_tag, tag_and_descendants, AUTH_REQUIRED_TAG, FEATURED_TAG, HIDDEN_TAG, SYSTEM_TAGS,
+    },
+    telemetry::{
+        query::Traced,
+        span::{traced, Span, SpanKind},
+    },
+    validate::{Validate, ValidationErrors},
 };
 use actix_multipart::form::{tempfile::TempFile, text::Text, MultipartForm};
 use actix_web::{
-    delete, get, post, put,
-    web::{Data, Json, Path},
-    HttpResponse, Responder,
+    delete, get, patch, post, put,
+    web::{Data, Json, Path, Query},
+    HttpRequest, HttpResponse, Responder,
 };
-use aws_sdk_s3::{types::ByteStream, Client};
+use aws_sdk_s3::types::ByteStream;
+use bytes::Bytes;
 use chrono::prelude::*;
-use lazy_static::lazy_static;
+use log::Level;
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use sha1::{Digest, Sha1};
-use sqlx::{query, query_as};
+use sqlx::{query, query_as, query_scalar, Pool, Postgres, Transaction};
 use std::{
-    env,
     error::Error,
     fmt,
     fs::File,
     io::{BufReader, Read},
 };
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 use zip::read::ZipArchive;
 
-lazy_static! {
-    static ref GAMES_BUCKET: String = env::var("S3_GAMES_BUCKET").unwrap();
-}
-
 #[derive(Debug, Clone)]
 struct GameError {
     reason: String,
@@ -48,6 +62,7 @@ impl fmt::Display for GameError {
     }
 }
 
+/// A full edit of a game. Metadata fields that are left out keep their stored values
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct GameData {
     #[schema(example = "BrickBreaker")]
@@ -57,6 +72,214 @@ pub struct GameData {
     #[schema(example = "ella")]
     author: String,
     tags: Vec<String>,
+    #[schema(example = 1)]
+    min_players: Option<i16>,
+    #[schema(example = 2)]
+    max_players: Option<i16>,
+    #[schema(example = json!(["joystick", "buttons"]))]
+    controls: Option<Vec<String>>,
+    #[schema(example = 5)]
+    session_minutes: Option<i16>,
+    #[schema(example = json!(["Ella - Art", "Skyz - Music"]))]
+    credits: Option<Vec<String>>,
+    #[schema(example = "https://github.com/ComputerScienceHouse/BrickBreaker")]
+    repo_url: Option<String>,
+    #[schema(example = "MIT")]
+    license: Option<String>,
+    #[schema(example = json!(["flashing lights"]))]
+    content_warnings: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct GameTagsPatch {
+    #[serde(default)]
+    #[schema(example = json!(["featured"]))]
+    pub add: Vec<String>,
+    #[serde(default)]
+    #[schema(example = json!(["TestTag1"]))]
+    pub remove: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GameSort {
+    Name,
+    Popularity,
+    Rating,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct GameListQuery {
+    /// Order of the listing, alphabetical by default
+    sort: Option<GameSort>,
+    /// Only games playable by this many players. A bound a game does not declare is not checked
+    players: Option<i16>,
+    /// Comma separated controls available on the cabinet. Only games requiring a subset of them
+    controls: Option<String>,
+    /// Only games with an expected session of at most this many minutes
+    max_session_minutes: Option<i16>,
+    /// Only games under this license
+    license: Option<String>,
+    /// Comma separated content warnings. Excludes games carrying any of them
+    exclude_warnings: Option<String>,
+    /// Only games carrying this tag or any tag below it
+    tag: Option<String>,
+    /// List games tagged `hidden` as well, requires the API key
+    include_hidden: Option<bool>,
+}
+
+/// Fields of a game a JSON Merge Patch may change
+const GAME_FIELDS: [&str; 10] = [
+    "name",
+    "description",
+    "min_players",
+    "max_players",
+    "controls",
+    "session_minutes",
+    "credits",
+    "repo_url",
+    "license",
+    "content_warnings",
+];
+
+/// Descriptive fields shared by stored games and edits to them
+struct Details<'a> {
+    name: &'a str,
+    description: &'a str,
+    min_players: Option<i16>,
+    max_players: Option<i16>,
+    controls: &'a [String],
+    session_minutes: Option<i16>,
+    credits: &'a [String],
+    repo_url: Option<&'a str>,
+    license: Option<&'a str>,
+    content_warnings: &'a [String],
+}
+
+impl Validate for Details<'_> {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.length("name", self.name, 1, 128);
+        errors.length("description", self.description, 1, 1500);
+        match (self.min_players, self.max_players) {
+            (Some(min), Some(max)) => {
+                if let Err(e) = check_players(min, max) {
+                    errors.add("players", e.to_string());
+                }
+            }
+            (Some(bound), None) | (None, Some(bound)) if bound < 1 => {
+                errors.add("players", "must be at least 1");
+            }
+            _ => {}
+        }
+        if let Err(e) = check_controls(self.controls) {
+            errors.add("controls", e.to_string());
+        }
+        if matches!(self.session_minutes, Some(minutes) if minutes < 1) {
+            errors.add("session_minutes", "must be positive");
+        }
+        errors.each_length("credits", self.credits, 1, 128);
+        if let Some(repo_url) = self.repo_url {
+            errors.length("repo_url", repo_url, 1, 255);
+            errors.url("repo_url", repo_url);
+        }
+        if let Some(license) = self.license {
+            errors.length("license", license, 1, 64);
+        }
+        errors.each_length("content_warnings", self.content_warnings, 1, 64);
+    }
+}
+
+impl Validate for Game {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        Details {
+            name: &self.name,
+            description: &self.description,
+            min_players: self.min_players,
+            max_players: self.max_players,
+            controls: &self.controls,
+            session_minutes: self.session_minutes,
+            credits: &self.credits,
+            repo_url: self.repo_url.as_deref(),
+            license: self.license.as_deref(),
+            content_warnings: &self.content_warnings,
+        }
+        .validate(errors);
+    }
+}
+
+impl GameData {
+    /// `game` with the edit applied
+    fn apply(&self, game: Game) -> Game {
+        Game {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            min_players: self.min_players.or(game.min_players),
+            max_players: self.max_players.or(game.max_players),
+            controls: self.controls.clone().unwrap_or(game.controls),
+            session_minutes: self.session_minutes.or(game.session_minutes),
+            credits: self.credits.clone().unwrap_or(game.credits),
+            repo_url: self.repo_url.clone().or(game.repo_url),
+            license: self.license.clone().or(game.license),
+            content_warnings: self
+                .content_warnings
+                .clone()
+                .unwrap_or(game.content_warnings),
+            ..game
+        }
+    }
+
+    /// Every problem with the edited game, including an author or tags that do not exist
+    async fn check(
+        &self,
+        db: &Pool<Postgres>,
+        edited: &Game,
+    ) -> Result<ValidationErrors, sqlx::Error> {
+        let mut errors = ValidationErrors::of(edited);
+        errors.length("author", &self.author, 1, 32);
+        errors.each_length("tags", &self.tags, 1, 32);
+        errors.user_exists(db, "author", &self.author).await?;
+        errors.tags_exist(db, "tags", &self.tags).await?;
+        Ok(errors)
+    }
+}
+
+/// Tag the game `id` with each of `tags`, resolving aliases to the tags they stand for
+async fn insert_tags(
+    transaction: &mut Transaction<'_, Postgres>,
+    id: &str,
+    tags: &[String],
+) -> Result<(), sqlx::Error> {
+    query(
+        "
+        INSERT INTO game_tags (game_id, tag_name)
+        SELECT DISTINCT $1, COALESCE(tags.name, tag_aliases.tag_name)
+        FROM unnest($2::text[]) names(name)
+        LEFT JOIN tags ON tags.name = names.name
+        LEFT JOIN tag_aliases ON tag_aliases.alias = names.name
+        ON CONFLICT DO NOTHING
+        ",
+    )
+    .bind(id)
+    .bind(tags)
+    .execute(Traced(&mut *transaction))
+    .await?;
+    Ok(())
+}
+
+fn split_list(list: &Option<String>) -> Option<Vec<String>> {
+    list.as_ref().map(|list| {
+        list.split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(str::to_string)
+            .collect()
+    })
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ChangesQuery {
+    /// `until` sync token of the previous sync. Omit for a full sync
+    since: Option<i64>,
 }
 
 #[derive(Debug, MultipartForm)]
@@ -64,10 +287,32 @@ pub struct GameUpload {
     pub game: TempFile,
     pub banner: TempFile,
     pub icon: TempFile,
-    pub title: Text<String>,
-    pub description: Text<String>,
+    pub title: Option<Text<String>>,
+    pub description: Option<Text<String>>,
     pub author: Text<String>,
-    pub tags: Text<String>,
+    pub tags: Option<Text<String>>,
+}
+
+impl GameUpload {
+    /// Tags from the comma separated `tags` field
+    fn tag_list(&self) -> Vec<String> {
+        split_list(&self.tags.as_ref().map(|tags| tags.to_string())).unwrap_or_default()
+    }
+
+    /// Every problem with the form fields, before the files are looked at
+    async fn check(&self, db: &Pool<Postgres>) -> Result<ValidationErrors, sqlx::Error> {
+        let mut errors = ValidationErrors::default();
+        if let Some(title) = &self.title {
+            errors.length("title", title, 1, 128);
+        }
+        if let Some(description) = &self.description {
+            errors.length("description", description, 1, 1500);
+        }
+        errors.length("author", &self.author, 1, 32);
+        errors.user_exists(db, "author", &self.author).await?;
+        errors.tags_exist(db, "tags", &self.tag_list()).await?;
+        Ok(errors)
+    }
 }
 
 #[allow(dead_code)]
@@ -79,10 +324,13 @@ pub struct GameUploadDoc {
     banner: String,
     #[schema(format = Binary)]
     icon: String,
-    title: String,
-    description: String,
+    /// Required unless declared by the game's manifest
+    title: Option<String>,
+    /// Required unless declared by the game's manifest
+    description: Option<String>,
     author: String,
-    tags: Vec<String>,
+    /// Comma separated, replaced by the tags of the game's manifest
+    tags: Option<String>,
 }
 
 #[derive(Debug, MultipartForm)]
@@ -109,113 +357,455 @@ impl ImageComponent {
     }
 }
 
+/// Query of every `GameWithTags` matching the `filter` condition, sorted by `order`
+pub fn games_with_tags(filter: &str, order: &str) -> String {
+    format!(
+        "
+        SELECT game.*,
+            ROW(users.*)::users AS \"user\",
+            array_remove(ARRAY_AGG(tags.*), NULL) AS \"tags\",
+            ARRAY(
+                SELECT ROW(contributor, game_contributors.role)::GameContributor FROM game_contributors
+                JOIN users contributor ON contributor.id = game_contributors.user_id
+                WHERE game_contributors.game_id = game.id
+                ORDER BY game_contributors.role ASC, game_contributors.created_at ASC
+            ) AS contributors,
+            (SELECT AVG(rating)::float8 FROM reviews
+                WHERE reviews.game_id = game.id AND NOT reviews.hidden) AS rating_average,
+            (SELECT COUNT(*) FROM reviews
+                WHERE reviews.game_id = game.id AND NOT reviews.hidden) AS rating_count
+        FROM game
+        LEFT JOIN game_tags ON game_tags.game_id = game.id
+        LEFT JOIN tags ON tags.name = game_tags.tag_name
+        LEFT JOIN users ON users.id = game.author
+        WHERE {}
+        GROUP BY game.id, users.id ORDER BY {}
+        ",
+        filter, order
+    )
+}
+
 #[utoipa::path(
     context_path = "/games",
+    params(GameListQuery),
     responses(
         (status = 200, description = "List all games", body = [GameWithTags]),
         (status = 500, description = "Error Created by Query"),
     )
 )]
 #[get("/")]
-pub async fn get_all_games(state: Data<AppState>) -> impl Responder {
-    match query_as::<_, GameWithTags>(
+pub async fn get_all_games(
+    state: Data<AppState>,
+    params: Query<GameListQuery>,
+    req: HttpRequest,
+) -> impl Responder {
+    if params.include_hidden == Some(true) && !has_api_key(&req) {
+        return HttpResponse::Unauthorized().body("Listing Hidden Games Requires An API Key");
+    }
+    let order = match params.sort {
+        Some(GameSort::Popularity) => {
+            "(SELECT COUNT(*) FROM play_sessions WHERE play_sessions.game_id = game.id) DESC, name ASC"
+        }
+        Some(GameSort::Rating) => "rating_average DESC NULLS LAST, rating_count DESC, name ASC",
+        Some(GameSort::Name) | None => "name ASC",
+    };
+    let filter = format!(
         "
-        SELECT game.*,
-            ROW(users.*)::users AS \"user\",
-            array_remove(ARRAY_AGG(tags.*), NULL) AS \"tags\"
-        FROM game
-        LEFT JOIN game_tags ON game_tags.game_id = game.id
-        LEFT JOIN tags ON tags.name = game_tags.tag_name
-        LEFT JOIN users ON game.author = users.id
-        GROUP BY game.id, users.id ORDER BY name ASC
+        ($1::smallint IS NULL OR (
+            $1 >= COALESCE(game.min_players, $1) AND $1 <= COALESCE(game.max_players, $1)
+        ))
+        AND ($2::varchar[] IS NULL OR game.controls <@ $2::varchar[])
+        AND ($3::smallint IS NULL OR game.session_minutes <= $3)
+        AND ($4::varchar IS NULL OR game.license = $4)
+        AND ($5::varchar[] IS NULL OR NOT game.content_warnings && $5::varchar[])
+        AND ($6::varchar IS NULL OR game.id IN (
+            SELECT game_id FROM game_tags WHERE tag_name IN ({})
+        ))
+        AND ($7 OR NOT {})
         ",
+        tag_and_descendants("$6"),
+        has_tag(HIDDEN_TAG)
+    );
+    match query_as::<_, GameWithTags>(&games_with_tags(&filter, order))
+        .bind(params.players)
+        .bind(split_list(&params.controls))
+        .bind(params.max_session_minutes)
+        .bind(&params.license)
+        .bind(split_list(&params.exclude_warnings))
+        .bind(&params.tag)
+        .bind(params.include_hidden.unwrap_or(false))
+        .fetch_all(Traced(&state.db))
+        .await
+    {
+        Ok(games) => HttpResponse::Ok().json(games),
+        Err(e) => internal_error("could not fetch games", e),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/games",
+    responses(
+        (status = 200, description = "List games tagged featured, most recently updated first", body = [GameWithTags]),
+        (status = 500, description = "Error Created by Query"),
     )
-    .fetch_all(&state.db)
+)]
+#[get("/featured")]
+pub async fn get_featured_games(state: Data<AppState>) -> impl Responder {
+    match query_as::<_, GameWithTags>(&games_with_tags(
+        &format!("{} AND NOT {}", has_tag(FEATURED_TAG), has_tag(HIDDEN_TAG)),
+        "game.updated_at DESC, name ASC",
+    ))
+    .fetch_all(Traced(&state.db))
     .await
     {
         Ok(games) => HttpResponse::Ok().json(games),
-        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(e) => internal_error("could not fetch featured games", e),
     }
 }
 
-async fn verify_and_upload_game(
-    game: TempFile,
-    s3: &Client,
-    uuid: Option<String>,
-) -> Result<(String, String), Box<dyn std::error::Error>> {
-    let game_content_type = game
-        .content_type
-        .as_ref()
-        .ok_or("Could not determine file type")?
-        .clone();
-    if game_content_type != "application/zip" {
-        return Err(Box::new(GameError::new("Game provided is not a Zip")));
-    }
-    let uuid = uuid.unwrap_or(Uuid::new_v4().to_string());
-    {
-        let zip_file = File::open(game.file.path())?;
-        let mut zip_archive = ZipArchive::new(zip_file)?;
-        let publish = zip_archive
-            .by_name("publish/")
-            .map_err(|_| GameError::new("publish directory not found"))?;
+/// Latest change to each game made by a transaction in `[$1, $2)`, every transaction when `$1`
+/// is null. A game counts as added when any of those changes added it or unhid it.
+const CHANGED_GAMES: &str = "
+    WITH changed AS (
+        SELECT game_id, bool_or(added) AS added, max(changed_at) AS changed_at
+        FROM game_changes
+        WHERE ($1::bigint IS NULL OR transaction_id >= $1) AND transaction_id < $2
+        GROUP BY game_id
+    )
+";
+
+#[utoipa::path(
+    context_path = "/games",
+    params(ChangesQuery),
+    responses(
+        (status = 200, description = "Games added, updated, and removed since the given sync token, games hidden since then count as removed", body = GameChanges),
+        (status = 500, description = "Error Created by Query"),
+    )
+)]
+#[get("/changes")]
+pub async fn get_game_changes(
+    state: Data<AppState>,
+    params: Query<ChangesQuery>,
+) -> impl Responder {
+    let since = params.since;
+    // One snapshot for every read, so a game can't be both added and removed
+    let mut transaction = match state.db.begin().await {
+        Ok(transaction) => transaction,
+        Err(e) => return internal_error("could not begin transaction", e),
+    };
+    if let Err(e) = query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+        .execute(Traced(&mut transaction))
+        .await
+    {
+        let _ = transaction.rollback().await;
+        return internal_error("could not begin transaction", e);
+    }
+    // Transactions from the oldest one still in flight on have not all committed yet, so their
+    // changes are left for the next sync
+    let until = match query_scalar::<_, i64>(
+        "SELECT pg_snapshot_xmin(pg_current_snapshot())::text::bigint",
+    )
+    .fetch_one(Traced(&mut transaction))
+    .await
+    {
+        Ok(until) => until,
+        Err(e) => {
+            let _ = transaction.rollback().await;
+            return internal_error("could not read sync token", e);
+        }
+    };
+    let added = match query_as::<_, GameChange>(&format!(
+        "
+        {}
+        SELECT game.* FROM game JOIN changed ON changed.game_id = game.id
+        WHERE ($1::bigint IS NULL OR changed.added) AND NOT {}
+        ORDER BY game.created_at ASC
+        ",
+        CHANGED_GAMES,
+        has_tag(HIDDEN_TAG)
+    ))
+    .bind(since)
+    .bind(until)
+    .fetch_all(Traced(&mut transaction))
+    .await
+    {
+        Ok(added) => added,
+        Err(e) => {
+            let _ = transaction.rollback().await;
+            return internal_error("could not fetch added games", e);
+        }
+    };
+    let updated = match query_as::<_, GameChange>(&format!(
+        "
+        {}
+        SELECT game.* FROM game JOIN changed ON changed.game_id = game.id
+        WHERE $1::bigint IS NOT NULL AND NOT changed.added AND NOT {}
+        ORDER BY game.updated_at ASC
+        ",
+        CHANGED_GAMES,
+        has_tag(HIDDEN_TAG)
+    ))
+    .bind(since)
+    .bind(until)
+    .fetch_all(Traced(&mut transaction))
+    .await
+    {
+        Ok(updated) => updated,
+        Err(e) => {
+            let _ = transaction.rollback().await;
+            return internal_error("could not fetch updated games", e);
+        }
+    };
+    let removed = match query_as::<_, GameTombstone>(&format!(
+        "
+        {}
+        SELECT changed.game_id AS id, changed.changed_at AS deleted_at
+        FROM changed LEFT JOIN game ON game.id = changed.game_id
+        WHERE game.id IS NULL OR {}
+        ORDER BY deleted_at ASC
+        ",
+        CHANGED_GAMES,
+        has_tag(HIDDEN_TAG)
+    ))
+    .bind(since)
+    .bind(until)
+    .fetch_all(Traced(&mut transaction))
+    .await
+    {
+        Ok(removed) => removed,
+        Err(e) => {
+            let _ = transaction.rollback().await;
+            return internal_error("could not fetch removed games", e);
+        }
+    };
+    if let Err(e) = transaction.commit().await {
+        return internal_error("could not commit transaction", e);
+    }
+    HttpResponse::Ok().json(GameChanges {
+        since,
+        until,
+        added,
+        updated,
+        removed,
+    })
+}
+
+/// Why an upload was not stored, along with the reason reported in `game_upload_failures_total`
+#[derive(Debug)]
+enum UploadError {
+    /// The file is not a game zip or an image
+    Invalid(&'static str, Box<dyn Error>),
+    /// The game's manifest is malformed or disagrees with the catalog
+    Manifest(&'static str, ValidationErrors),
+    /// Checking or storing the upload failed
+    Failed(&'static str, Box<dyn Error>),
+}
+
+impl UploadError {
+    fn reason(&self) -> &'static str {
+        match self {
+            UploadError::Invalid(reason, _)
+            | UploadError::Manifest(reason, _)
+            | UploadError::Failed(reason, _) => reason,
+        }
+    }
+
+    fn response(&self) -> HttpResponse {
+        match self {
+            UploadError::Invalid(_, e) => HttpResponse::BadRequest().body(e.to_string()),
+            UploadError::Manifest(_, errors) => errors.response(),
+            UploadError::Failed(_, e) => internal_error("could not store upload", e),
+        }
+    }
+}
+
+impl fmt::Display for UploadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UploadError::Invalid(_, e) | UploadError::Failed(_, e) => write!(f, "{}", e),
+            UploadError::Manifest(_, errors) => write!(f, "invalid manifest: {:?}", errors.errors),
+        }
+    }
+}
+
+/// Check an uploaded game before it is stored
+async fn verify_game(
+    game: &TempFile,
+    db: &Pool<Postgres>,
+    uuid: Option<&str>,
+) -> Result<GameManifest, UploadError> {
+    match game.content_type.as_ref() {
+        None => {
+            return Err(UploadError::Invalid(
+                "not_zip",
+                "Could not determine file type".into(),
+            ))
+        }
+        Some(content_type) if *content_type != "application/zip" => {
+            return Err(UploadError::Invalid(
+                "not_zip",
+                Box::new(GameError::new("Game provided is not a Zip")),
+            ))
+        }
+        Some(_) => {}
+    }
+    let mut zip_archive = File::open(game.file.path())
+        .map_err(|e| UploadError::Failed("storage", e.into()))
+        .and_then(|zip_file| {
+            ZipArchive::new(zip_file).map_err(|e| UploadError::Invalid("invalid_zip", e.into()))
+        })?;
+    {
+        let publish = zip_archive.by_name("publish/").map_err(|_| {
+            UploadError::Invalid(
+                "missing_publish",
+                GameError::new("publish directory not found").into(),
+            )
+        })?;
         if !publish.is_dir() {
-            return Err(Box::new(GameError::new("publish is not a directory")));
+            return Err(UploadError::Invalid(
+                "missing_publish",
+                Box::new(GameError::new("publish is not a directory")),
+            ));
         }
     }
-    let hash = sha1sum(game.file.path().display().to_string())?;
-    let _ = s3
-        .put_object()
-        .key(format!("{}/{}.zip", uuid, uuid))
-        .body(ByteStream::from_path(game.file.path()).await?)
-        .bucket(&GAMES_BUCKET.to_string())
-        .send()
-        .await?;
-    Ok((uuid, hash))
+    let manifest = GameManifest::read(&mut zip_archive).map_err(|e| {
+        let mut errors = ValidationErrors::default();
+        errors.add("manifest", e.to_string());
+        UploadError::Manifest("invalid_manifest", errors)
+    })?;
+    let mut errors = ValidationErrors::default();
+    manifest
+        .validate(db, uuid, &mut errors)
+        .await
+        .map_err(|e| UploadError::Failed("database", e.into()))?;
+    if !errors.is_empty() {
+        return Err(UploadError::Manifest("manifest_rejected", errors));
+    }
+    Ok(manifest)
 }
 
-async fn verify_and_upload_image(
-    image: TempFile,
-    s3: &Client,
-    image_type: ImageComponent,
-    uuid: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
+/// Check an uploaded game, recording the check in a span and any rejection in the metrics
+async fn traced_verify_game(
+    game: &TempFile,
+    state: &AppState,
+    uuid: Option<&str>,
+) -> Result<GameManifest, UploadError> {
+    let mut span = Span::child("game.verify", SpanKind::Internal);
+    let verified = span.scope(verify_game(game, &state.db, uuid)).await;
+    if let Err(e) = &verified {
+        span.attribute("game.rejected_reason", e.reason());
+        span.fail(e);
+        state.metrics.record_upload_rejected(e.reason());
+    }
+    span.end();
+    verified
+}
+
+async fn hash_game(game: &TempFile) -> Result<String, UploadError> {
+    traced("game.hash", SpanKind::Internal, &[], async {
+        sha1sum(game.file.path().display().to_string())
+    })
+    .await
+    .map_err(|e| UploadError::Failed("storage", e))
+}
+
+/// Store a verified game binary under `uuid`
+async fn upload_game(game: &TempFile, state: &AppState, uuid: &str) -> Result<(), UploadError> {
+    let stored = async {
+        let body = ByteStream::from_path(game.file.path()).await?;
+        let uploaded = traced(
+            "s3.put_object",
+            SpanKind::Client,
+            &[("s3.bucket", &state.config.s3_games_bucket)],
+            state
+                .s3
+                .put_object()
+                .key(binary_key(uuid))
+                .body(body)
+                .bucket(state.config.s3_games_bucket.as_str())
+                .send(),
+        )
+        .await;
+        state
+            .metrics
+            .record_s3("put_object", uploaded.is_ok(), game.size as u64);
+        uploaded?;
+        Ok::<_, Box<dyn Error>>(())
+    }
+    .await;
+    match stored {
+        Ok(()) => {
+            state.metrics.record_upload_accepted(game.size);
+            Ok(())
+        }
+        Err(e) => {
+            state.metrics.record_upload_rejected("storage");
+            Err(UploadError::Failed("storage", e))
+        }
+    }
+}
+
+/// Check that `image` is an image before anything is stored
+fn verify_image(image: &TempFile, image_type: &ImageComponent) -> Result<(), UploadError> {
     let image_content_type = image
         .content_type
         .as_ref()
-        .ok_or("Could not determine file type")?
-        .clone();
+        .ok_or_else(|| UploadError::Invalid("not_image", "Could not determine file type".into()))?;
     if image_content_type.type_() != "image" {
-        return Err(Box::new(GameError::new(&format!(
-            "{:?} provided is not an image",
-            image_type
-        ))));
-    }
-    let _ = s3
-        .put_object()
-        .key(format!(
-            "{}/{}",
-            uuid,
-            image_type.filename(),
-            //image_content_type.subtype()
-        ))
-        .body(ByteStream::from_path(image.file.path()).await?)
-        .bucket(&GAMES_BUCKET.to_string())
-        .send()
-        .await?;
+        return Err(UploadError::Invalid(
+            "not_image",
+            Box::new(GameError::new(&format!(
+                "{:?} provided is not an image",
+                image_type
+            ))),
+        ));
+    }
     Ok(())
 }
 
-async fn verify_and_upload(
-    game: TempFile,
-    banner: TempFile,
-    icon: TempFile,
-    s3: &Client,
-    uuid: Option<String>,
-) -> Result<(String, String), Box<dyn std::error::Error>> {
-    let (uuid, hash) = verify_and_upload_game(game, s3, uuid).await?;
-    verify_and_upload_image(banner, s3, ImageComponent::Banner, &uuid).await?;
-    verify_and_upload_image(icon, s3, ImageComponent::Icon, &uuid).await?;
-    Ok((uuid, hash))
+/// Store a verified image of the game `uuid`
+async fn upload_image(
+    image: &TempFile,
+    state: &AppState,
+    image_type: ImageComponent,
+    uuid: &str,
+) -> Result<(), UploadError> {
+    let body = ByteStream::from_path(image.file.path())
+        .await
+        .map_err(|e| UploadError::Failed("storage", e.into()))?;
+    let uploaded = traced(
+        "s3.put_object",
+        SpanKind::Client,
+        &[("s3.bucket", &state.config.s3_games_bucket)],
+        state
+            .s3
+            .put_object()
+            .key(format!(
+                "{}/{}",
+                uuid,
+                image_type.filename(),
+                //image_content_type.subtype()
+            ))
+            .body(body)
+            .bucket(state.config.s3_games_bucket.as_str())
+            .send(),
+    )
+    .await;
+    state
+        .metrics
+        .record_s3("put_object", uploaded.is_ok(), image.size as u64);
+    uploaded.map_err(|e| UploadError::Failed("storage", e.into()))?;
+    Ok(())
+}
+
+async fn verify_and_upload_image(
+    image: TempFile,
+    state: &AppState,
+    image_type: ImageComponent,
+    uuid: &str,
+) -> Result<(), UploadError> {
+    verify_image(&image, &image_type)?;
+    upload_image(&image, state, image_type, uuid).await
 }
 
 #[utoipa::path(
@@ -225,6 +815,7 @@ async fn verify_and_upload(
         (status = 201, description = "Created new game"),
         (status = 400, description = "Invalid format of file upload"),
         (status = 401, description = "Invalid/Missing API Key"),
+        (status = 422, description = "Invalid or missing form fields or manifest, by field", body = ValidationErrors),
         (status = 500, description = "Error Created by Query"),
     ),
     security(
@@ -236,91 +827,218 @@ pub async fn add_game(
     state: Data<AppState>,
     MultipartForm(form): MultipartForm<GameUpload>,
 ) -> impl Responder {
-    match verify_and_upload(form.game, form.banner, form.icon, &state.s3, None).await {
-        Ok((uuid, hash)) => {
-            let date = Local::now().date_naive();
-            match query("INSERT INTO game VALUES ($1, $2, $3, $4, $5, $6)")
-                .bind(&uuid)
-                .bind(form.author.clone())
-                .bind(date)
-                .bind(form.title.clone())
-                .bind(&hash)
-                .bind(form.description.clone())
-                .execute(&state.db)
-                .await
-            {
-                Ok(_) => {
-                    for tag_name in form.tags.split(',') {
-                        if tag_name.is_empty() {
-                            continue;
-                        }
-                        if let Err(e) = query("INSERT INTO game_tags VALUES ($1, $2)")
-                            .bind(&uuid)
-                            .bind(tag_name)
-                            .execute(&state.db)
-                            .await
-                        {
-                            return HttpResponse::BadRequest().body(e.to_string());
-                        }
-                    }
-                    HttpResponse::Created().json(Game {
-                        id: uuid,
-                        author: form.author.clone(),
-                        upload_date: date,
-                        name: form.title.clone(),
-                        hash,
-                        description: form.description.clone(),
-                    })
-                }
-                Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
-            }
+    match form.check(&state.db).await {
+        Ok(errors) if errors.is_empty() => {}
+        Ok(errors) => return errors.response(),
+        Err(e) => return internal_error("could not check game form", e),
+    }
+    // Everything is checked before anything is stored
+    let manifest = match traced_verify_game(&form.game, &state, None).await {
+        Ok(manifest) => manifest,
+        Err(e) => return e.response(),
+    };
+    for (image, image_type) in [
+        (&form.banner, ImageComponent::Banner),
+        (&form.icon, ImageComponent::Icon),
+    ] {
+        if let Err(e) = verify_image(image, &image_type) {
+            return e.response();
+        }
+    }
+    let form_tags = form.tag_list();
+    let name = manifest
+        .name
+        .clone()
+        .or_else(|| form.title.as_ref().map(|title| title.to_string()));
+    let description = manifest
+        .description
+        .clone()
+        .or_else(|| form.description.as_ref().map(|desc| desc.to_string()));
+    let tags = manifest.tags.clone().unwrap_or(form_tags);
+    let players = manifest.players.as_ref();
+    let controls = manifest.controls.clone().unwrap_or_default();
+    let credits = manifest.credits.clone().unwrap_or_default();
+    let content_warnings = manifest.content_warnings.clone().unwrap_or_default();
+    let mut errors = ValidationErrors::of(&Details {
+        name: name.as_deref().unwrap_or_default(),
+        description: description.as_deref().unwrap_or_default(),
+        min_players: players.map(|players| players.min),
+        max_players: players.map(|players| players.max),
+        controls: &controls,
+        session_minutes: manifest.session_minutes,
+        credits: &credits,
+        repo_url: manifest.repo_url.as_deref(),
+        license: manifest.license.as_deref(),
+        content_warnings: &content_warnings,
+    });
+    for (field, value) in [("name", &name), ("description", &description)] {
+        if value.is_none() {
+            errors.errors.insert(
+                field.to_string(),
+                vec!["must be provided by the form or manifest".to_string()],
+            );
+        }
+    }
+    errors.each_length("tags", &tags, 1, 32);
+    if !errors.is_empty() {
+        return errors.response();
+    }
+    let (name, description) = (name.unwrap_or_default(), description.unwrap_or_default());
+    let uuid = Uuid::new_v4().to_string();
+    let hash = match hash_game(&form.game).await {
+        Ok(hash) => hash,
+        Err(e) => return e.response(),
+    };
+    let mut transaction = match state.db.begin().await {
+        Ok(transaction) => transaction,
+        Err(e) => return internal_error("could not begin transaction", e),
+    };
+    let game = match query_as::<_, Game>(
+        "
+        INSERT INTO game (
+            id, author, upload_date, name, hash, description, version, entrypoint,
+            min_client_version, min_players, max_players, controls, session_minutes,
+            credits, repo_url, license, content_warnings
+        )
+        VALUES (
+            $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17
+        )
+        RETURNING *
+        ",
+    )
+    .bind(&uuid)
+    .bind(form.author.clone())
+    .bind(Local::now().date_naive())
+    .bind(&name)
+    .bind(&hash)
+    .bind(&description)
+    .bind(&manifest.version)
+    .bind(&manifest.entrypoint)
+    .bind(&manifest.min_client_version)
+    .bind(players.map(|players| players.min))
+    .bind(players.map(|players| players.max))
+    .bind(&controls)
+    .bind(manifest.session_minutes)
+    .bind(&credits)
+    .bind(&manifest.repo_url)
+    .bind(&manifest.license)
+    .bind(&content_warnings)
+    .fetch_one(Traced(&mut transaction))
+    .await
+    {
+        Ok(game) => game,
+        Err(e) => {
+            let _ = transaction.rollback().await;
+            return internal_error("could not add game", e);
         }
-        Err(e) => HttpResponse::NotAcceptable().body(e.to_string()),
+    };
+    if let Err(e) =
+        query("INSERT INTO game_contributors (game_id, user_id, role) VALUES ($1, $2, 'owner')")
+            .bind(&uuid)
+            .bind(&game.author)
+            .execute(Traced(&mut transaction))
+            .await
+    {
+        let _ = transaction.rollback().await;
+        return internal_error("could not add game owner", e);
+    }
+    if let Err(e) = insert_tags(&mut transaction, &uuid, &tags).await {
+        let _ = transaction.rollback().await;
+        return internal_error("could not add game tags", e);
     }
+    if let Some(achievements) = &manifest.achievements {
+        if let Err(e) = replace_achievements(&mut transaction, &uuid, achievements).await {
+            let _ = transaction.rollback().await;
+            return internal_error("could not add game achievements", e);
+        }
+    }
+    // The files are stored once the rows are in place, and removed again if any of them fails
+    let uploaded = async {
+        upload_game(&form.game, &state, &uuid).await?;
+        upload_image(&form.banner, &state, ImageComponent::Banner, &uuid).await?;
+        upload_image(&form.icon, &state, ImageComponent::Icon, &uuid).await
+    }
+    .await;
+    if let Err(e) = uploaded {
+        let _ = transaction.rollback().await;
+        json::event(
+            Level::Error,
+            "could not store new game",
+            json!({
+                "game_id": uuid,
+                "reason": e.reason(),
+                "error": e.to_string(),
+            }),
+        );
+        remove_unstored(&state, &uuid).await;
+        return e.response();
+    }
+    if let Err(e) = notify::publish(
+        &mut transaction,
+        CatalogEventType::GameCreated,
+        Some(&uuid),
+        None,
+    )
+    .await
+    {
+        let _ = transaction.rollback().await;
+        remove_unstored(&state, &uuid).await;
+        return internal_error("could not publish catalog event", e);
+    }
+    if let Err(e) = transaction.commit().await {
+        remove_unstored(&state, &uuid).await;
+        return internal_error("could not commit new game", e);
+    }
+    HttpResponse::Created().json(game)
 }
 
 #[utoipa::path(
     context_path = "/games",
     responses(
-        (status = 200, description = "Get specified game", body = GameWithTags),
-        (status = 400, description = "Missing game"),
+        (status = 200, description = "Get specified game, with its version in the ETag header", body = GameWithTags),
+        (status = 400, description = "Missing game, or a hidden game without the API key"),
         (status = 500, description = "Error Created by Query"),
     )
 )]
 #[get("/{id}")]
-pub async fn get_game(state: Data<AppState>, path: Path<(String,)>) -> impl Responder {
+pub async fn get_game(
+    state: Data<AppState>,
+    path: Path<(String,)>,
+    req: HttpRequest,
+) -> impl Responder {
     let (id,) = path.into_inner();
-    match query_as::<_, GameWithTags>(
-        "
-        SELECT game.*,
-            ROW(users.*)::users AS \"user\",
-            array_remove(ARRAY_AGG(tags.*), NULL) AS \"tags\"
-        FROM game
-        LEFT JOIN game_tags ON game_tags.game_id = game.id
-        LEFT JOIN tags ON tags.name = game_tags.tag_name
-        LEFT JOIN users ON users.id = game.author
-        WHERE game.id = $1
-        GROUP BY game.id, users.id
-        ",
-    )
+    match query_as::<_, Versioned<GameWithTags>>(&games_with_tags(
+        &format!("game.id = $1 AND ($2 OR NOT {})", has_tag(HIDDEN_TAG)),
+        "name ASC",
+    ))
     .bind(id)
-    .fetch_one(&state.db)
+    .bind(has_api_key(&req))
+    .fetch_one(Traced(&state.db))
     .await
     {
-        Ok(game) => HttpResponse::Ok().json(game),
+        Ok(game) => HttpResponse::Ok()
+            .insert_header(etag::etag(&game.updated_at))
+            .json(game.resource),
         Err(e) => HttpResponse::BadRequest().body(e.to_string()),
     }
 }
 
 #[utoipa::path(
     context_path = "/games",
-    request_body(content=GameData, content_type="application/json", description="JSON with name, desc, and author"),
+    request_body(content=GameData, content_type="application/json", description="JSON with name, desc, author, tags, and extended metadata"),
     responses(
-        (status = 200, description = "Updated game"),
+        (status = 200, description = "Updated game, with its new version in the ETag header"),
         (status = 400, description = "Missing game"),
         (status = 401, description = "Invalid/Missing API Key"),
+        (status = 412, description = "Game changed since the version in If-Match"),
+        (status = 422, description = "Invalid fields, by field", body = ValidationErrors),
+        (status = 428, description = "Missing If-Match header"),
         (status = 500, description = "Error Created by Query"),
     ),
+    params(
+        ("id", description = "Unique id of game"),
+        ("If-Match" = String, Header, description = "ETag of the version being edited, or * for any version"),
+    ),
     security(
         ("api_key" = [])
     )
@@ -330,83 +1048,504 @@ pub async fn edit_game(
     state: Data<AppState>,
     path: Path<(String,)>,
     game_data: Json<GameData>,
+    req: HttpRequest,
 ) -> impl Responder {
     let (id,) = path.into_inner();
-    let mut transaction = state.db.begin().await.unwrap();
-    match query_as::<_, Game>("SELECT * FROM game WHERE id = $1")
+    let precondition = match Precondition::from_request(&req) {
+        Ok(precondition) => precondition,
+        Err(res) => return res,
+    };
+    let mut transaction = match state.db.begin().await {
+        Ok(transaction) => transaction,
+        Err(e) => return internal_error("could not begin transaction", e),
+    };
+    let current =
+        match query_as::<_, Versioned<Game>>("SELECT * FROM game WHERE id = $1 FOR UPDATE")
+            .bind(&id)
+            .fetch_optional(Traced(&mut transaction))
+            .await
+        {
+            Ok(Some(current)) => current,
+            Ok(None) => {
+                let _ = transaction.rollback().await;
+                return HttpResponse::BadRequest().body("Game ID Does Not Exist");
+            }
+            Err(e) => {
+                let _ = transaction.rollback().await;
+                return internal_error("could not lock game", e);
+            }
+        };
+    if !precondition.matches(&current.updated_at) {
+        let _ = transaction.rollback().await;
+        return etag::precondition_failed();
+    }
+    // Validate the game as it will be stored, so a bound left out is checked against the other
+    let game = game_data.apply(current.resource);
+    match game_data.check(&state.db, &game).await {
+        Ok(errors) if errors.is_empty() => {}
+        Ok(errors) => {
+            let _ = transaction.rollback().await;
+            return errors.response();
+        }
+        Err(e) => {
+            let _ = transaction.rollback().await;
+            return internal_error("could not check game data", e);
+        }
+    }
+    let game = match query_as::<_, Versioned<Game>>(
+        "
+        UPDATE game SET name = $1, description = $2, min_players = $3, max_players = $4,
+            controls = $5, session_minutes = $6, credits = $7, repo_url = $8,
+            license = $9, content_warnings = $10, updated_at = now()
+        WHERE id = $11
+        RETURNING *
+        ",
+    )
+    .bind(&game.name)
+    .bind(&game.description)
+    .bind(game.min_players)
+    .bind(game.max_players)
+    .bind(&game.controls)
+    .bind(game.session_minutes)
+    .bind(&game.credits)
+    .bind(&game.repo_url)
+    .bind(&game.license)
+    .bind(&game.content_warnings)
+    .bind(&id)
+    .fetch_one(Traced(&mut transaction))
+    .await
+    {
+        Ok(game) => game,
+        Err(e) => {
+            let _ = transaction.rollback().await;
+            return internal_error("could not update game", e);
+        }
+    };
+    if let Err(e) = query("DELETE FROM game_tags WHERE game_id =  $1")
         .bind(&id)
-        .fetch_one(&mut transaction)
+        .execute(Traced(&mut transaction))
         .await
     {
-        Ok(game) => {
-            match query("UPDATE game SET name = $1, description = $2 WHERE id = $3")
-                .bind(game_data.name.clone())
-                .bind(game_data.description.clone())
-                .bind(&id)
-                .execute(&mut transaction)
-                .await
-            {
-                Ok(_) => {
-                    if let Err(e) = query("DELETE FROM game_tags WHERE game_id =  $1")
-                            .bind(&id)
-                            .execute(&mut transaction)
-                            .await {
-                                let _ = transaction.rollback().await;
-                                return HttpResponse::InternalServerError().body(e.to_string());
-                            };
-                    for tag_name in game_data.tags.clone() {
-                        if let Err(e) = query("INSERT INTO game_tags VALUES ($1, $2)")
-                            .bind(&id)
-                            .bind(tag_name)
-                            .execute(&mut transaction)
-                            .await
-                        {
-                            let _ = transaction.rollback().await;
-                            return HttpResponse::BadRequest().body(e.to_string());
-                        }
-                    }
-                    let _ = transaction.commit().await;
-                    HttpResponse::Ok().json(Game {
-                        id,
-                        author: game.author,
-                        upload_date: game.upload_date,
-                        name: game_data.name.clone(),
-                        hash: game.hash,
-                        description: game_data.description.clone(),
-                    })
-                },
-                Err(e) => {
-                    let _ = transaction.rollback().await;
-                    HttpResponse::InternalServerError().body(e.to_string())
-                },
+        let _ = transaction.rollback().await;
+        return internal_error("could not clear game tags", e);
+    };
+    if let Err(e) = insert_tags(&mut transaction, &id, &game_data.tags).await {
+        let _ = transaction.rollback().await;
+        return internal_error("could not add game tags", e);
+    }
+    if let Err(e) = notify::publish(
+        &mut transaction,
+        CatalogEventType::GameUpdated,
+        Some(&id),
+        None,
+    )
+    .await
+    {
+        let _ = transaction.rollback().await;
+        return internal_error("could not publish catalog event", e);
+    }
+    if let Err(e) = transaction.commit().await {
+        return internal_error("could not commit game", e);
+    }
+    HttpResponse::Ok()
+        .insert_header(etag::etag(&game.updated_at))
+        .json(game.resource)
+}
+
+#[utoipa::path(
+    context_path = "/games",
+    request_body(content=Object, content_type="application/merge-patch+json", description="JSON Merge Patch of the game fields to change"),
+    responses(
+        (status = 200, description = "Updated game, with its new version in the ETag header", body = GameWithTags),
+        (status = 400, description = "Missing game, or invalid patch or field"),
+        (status = 401, description = "Invalid/Missing API Key"),
+        (status = 412, description = "Game changed since the version in If-Match"),
+        (status = 422, description = "Invalid fields, by field", body = ValidationErrors),
+        (status = 428, description = "Missing If-Match header"),
+        (status = 500, description = "Error Created by Query"),
+    ),
+    params(
+        ("id", description = "Unique id of game"),
+        ("If-Match" = String, Header, description = "ETag of the version being edited, or * for any version"),
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[patch("/{id}", wrap = "RequireApiKey")]
+pub async fn patch_game(
+    state: Data<AppState>,
+    path: Path<(String,)>,
+    game_patch: Json<Value>,
+    req: HttpRequest,
+) -> impl Responder {
+    let (id,) = path.into_inner();
+    let precondition = match Precondition::from_request(&req) {
+        Ok(precondition) => precondition,
+        Err(res) => return res,
+    };
+    let mut transaction = match state.db.begin().await {
+        Ok(transaction) => transaction,
+        Err(e) => return internal_error("could not begin transaction", e),
+    };
+    let current =
+        match query_as::<_, Versioned<Game>>("SELECT * FROM game WHERE id = $1 FOR UPDATE")
+            .bind(&id)
+            .fetch_optional(Traced(&mut transaction))
+            .await
+        {
+            Ok(Some(current)) => current,
+            Ok(None) => {
+                let _ = transaction.rollback().await;
+                return HttpResponse::BadRequest().body("Game ID Does Not Exist");
+            }
+            Err(e) => {
+                let _ = transaction.rollback().await;
+                return internal_error("could not lock game", e);
+            }
+        };
+    if !precondition.matches(&current.updated_at) {
+        let _ = transaction.rollback().await;
+        return etag::precondition_failed();
+    }
+    let game = match patch::apply(&current.resource, &game_patch, &GAME_FIELDS) {
+        Ok(game) => game,
+        Err(e) => {
+            let _ = transaction.rollback().await;
+            return HttpResponse::BadRequest().body(e);
+        }
+    };
+    let errors = ValidationErrors::of(&game);
+    if !errors.is_empty() {
+        let _ = transaction.rollback().await;
+        return errors.response();
+    }
+    if let Err(e) = query(
+        "
+        UPDATE game SET name = $1, description = $2, min_players = $3, max_players = $4,
+            controls = $5, session_minutes = $6, credits = $7, repo_url = $8,
+            license = $9, content_warnings = $10, updated_at = now()
+        WHERE id = $11
+        ",
+    )
+    .bind(&game.name)
+    .bind(&game.description)
+    .bind(game.min_players)
+    .bind(game.max_players)
+    .bind(&game.controls)
+    .bind(game.session_minutes)
+    .bind(&game.credits)
+    .bind(&game.repo_url)
+    .bind(&game.license)
+    .bind(&game.content_warnings)
+    .bind(&id)
+    .execute(Traced(&mut transaction))
+    .await
+    {
+        let _ = transaction.rollback().await;
+        return internal_error("could not update game", e);
+    }
+    if let Err(e) = notify::publish(
+        &mut transaction,
+        CatalogEventType::GameUpdated,
+        Some(&id),
+        None,
+    )
+    .await
+    {
+        let _ = transaction.rollback().await;
+        return internal_error("could not publish catalog event", e);
+    }
+    if let Err(e) = transaction.commit().await {
+        return internal_error("could not commit game", e);
+    }
+    match query_as::<_, Versioned<GameWithTags>>(&games_with_tags("game.id = $1", "name ASC"))
+        .bind(&id)
+        .fetch_one(Traced(&state.db))
+        .await
+    {
+        Ok(game) => HttpResponse::Ok()
+            .insert_header(etag::etag(&game.updated_at))
+            .json(game.resource),
+        Err(e) => internal_error("could not fetch patched game", e),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/games",
+    request_body(content=GameTagsPatch, content_type="application/json", description="Tags to add and remove"),
+    responses(
+        (status = 200, description = "Changed the tags of the game, lists the tags that were added or removed", body = TagAssignment),
+        (status = 400, description = "Missing game, or missing tags which are listed and nothing is changed", body = TagAssignment),
+        (status = 401, description = "Invalid/Missing API Key"),
+        (status = 500, description = "Error Created by Query"),
+    ),
+    params(
+        ("id", description = "Unique id of game")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[patch("/{id}/tags", wrap = "RequireApiKey")]
+pub async fn patch_game_tags(
+    state: Data<AppState>,
+    path: Path<(String,)>,
+    patch: Json<GameTagsPatch>,
+) -> impl Responder {
+    let (id,) = path.into_inner();
+    let mut transaction = match state.db.begin().await {
+        Ok(transaction) => transaction,
+        Err(e) => return internal_error("could not begin transaction", e),
+    };
+    match query_scalar::<_, bool>("SELECT EXISTS (SELECT 1 FROM game WHERE id = $1)")
+        .bind(&id)
+        .fetch_one(Traced(&mut transaction))
+        .await
+    {
+        Ok(true) => {}
+        Ok(false) => {
+            let _ = transaction.rollback().await;
+            return HttpResponse::BadRequest().body("Game ID Does Not Exist");
+        }
+        Err(e) => {
+            let _ = transaction.rollback().await;
+            return internal_error("could not check game exists", e);
+        }
+    }
+    // Resolve aliases so renamed and merged tags keep working
+    let names: Vec<String> = patch.add.iter().chain(&patch.remove).cloned().collect();
+    let resolved = match query_as::<_, (String, Option<String>)>(
+        "
+        SELECT names.name, COALESCE(tags.name, tag_aliases.tag_name)
+        FROM unnest($1::text[]) WITH ORDINALITY names(name, position)
+        LEFT JOIN tags ON tags.name = names.name
+        LEFT JOIN tag_aliases ON tag_aliases.alias = names.name
+        ORDER BY names.position
+        ",
+    )
+    .bind(&names)
+    .fetch_all(Traced(&mut transaction))
+    .await
+    {
+        Ok(resolved) => resolved,
+        Err(e) => {
+            let _ = transaction.rollback().await;
+            return internal_error("could not resolve tags", e);
+        }
+    };
+    let mut missing: Vec<String> = resolved
+        .iter()
+        .filter(|(_, tag)| tag.is_none())
+        .map(|(name, _)| name.clone())
+        .collect();
+    missing.sort();
+    missing.dedup();
+    if !missing.is_empty() {
+        let _ = transaction.rollback().await;
+        return HttpResponse::BadRequest().json(TagAssignment {
+            added: vec![],
+            removed: vec![],
+            missing,
+        });
+    }
+    let (add, remove): (Vec<String>, Vec<String>) = {
+        let mut tags = resolved.into_iter().filter_map(|(_, tag)| tag);
+        (
+            tags.by_ref().take(patch.add.len()).collect(),
+            tags.collect(),
+        )
+    };
+    let (mut added, mut removed) = (vec![], vec![]);
+    for (statement, tags, changed) in [
+        (
+            "DELETE FROM game_tags WHERE game_id = $1 AND tag_name = ANY($2::text[]) RETURNING tag_name",
+            &remove,
+            &mut removed,
+        ),
+        (
+            "
+            INSERT INTO game_tags (game_id, tag_name) SELECT DISTINCT $1, unnest($2::text[])
+            ON CONFLICT DO NOTHING RETURNING tag_name
+            ",
+            &add,
+            &mut added,
+        ),
+    ] {
+        match query_scalar::<_, String>(statement)
+            .bind(&id)
+            .bind(tags)
+            .fetch_all(Traced(&mut transaction))
+            .await
+        {
+            Ok(tags) => *changed = tags,
+            Err(e) => {
+                let _ = transaction.rollback().await;
+                return internal_error("could not change game tags", e);
             }
         }
-        Err(_) => {
+    }
+    let changed = !added.is_empty() || !removed.is_empty();
+    if changed {
+        if let Err(e) = query("UPDATE game SET updated_at = now() WHERE id = $1")
+            .bind(&id)
+            .execute(Traced(&mut transaction))
+            .await
+        {
+            let _ = transaction.rollback().await;
+            return internal_error("could not touch game", e);
+        }
+        if let Err(e) = notify::publish(
+            &mut transaction,
+            CatalogEventType::GameUpdated,
+            Some(&id),
+            None,
+        )
+        .await
+        {
             let _ = transaction.rollback().await;
-            HttpResponse::BadRequest().body("Game ID Does Not Exist")
-        },
-    }
-}
-
-async fn delete_recursively(s3: &Client, id: &str) -> Result<(), Box<dyn std::error::Error>> {
-    s3.delete_object()
-        .bucket(&GAMES_BUCKET.to_string())
-        .key(format!("{}/{}.zip", id, id))
-        .send()
-        .await?;
-    s3.delete_object()
-        .bucket(&GAMES_BUCKET.to_string())
-        .key(format!("{}/icon", id))
-        .send()
-        .await?;
-    s3.delete_object()
-        .bucket(&GAMES_BUCKET.to_string())
-        .key(format!("{}/banner", id))
-        .send()
-        .await?;
+            return internal_error("could not publish catalog event", e);
+        }
+    }
+    if let Err(e) = transaction.commit().await {
+        return internal_error("could not commit game tags", e);
+    }
+    HttpResponse::Ok().json(TagAssignment {
+        added,
+        removed,
+        missing: vec![],
+    })
+}
+
+pub async fn delete_recursively(
+    state: &AppState,
+    id: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for key in [
+        binary_key(id),
+        previous_binary_key(id),
+        format!("{}/icon", id),
+        format!("{}/banner", id),
+    ] {
+        delete_object(state, key).await?;
+    }
     Ok(())
 }
 
+fn binary_key(id: &str) -> String {
+    format!("{}/{}.zip", id, id)
+}
+
+/// Where the binary being replaced is kept until the new one is committed
+fn previous_binary_key(id: &str) -> String {
+    format!("{}/{}.zip.previous", id, id)
+}
+
+async fn delete_object(state: &AppState, key: String) -> Result<(), Box<dyn std::error::Error>> {
+    let deleted = traced(
+        "s3.delete_object",
+        SpanKind::Client,
+        &[("s3.bucket", &state.config.s3_games_bucket)],
+        state
+            .s3
+            .delete_object()
+            .bucket(state.config.s3_games_bucket.as_str())
+            .key(key)
+            .send(),
+    )
+    .await;
+    state.metrics.record_s3("delete_object", deleted.is_ok(), 0);
+    deleted?;
+    Ok(())
+}
+
+async fn copy_object(
+    state: &AppState,
+    from: String,
+    to: String,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let copied = traced(
+        "s3.copy_object",
+        SpanKind::Client,
+        &[("s3.bucket", &state.config.s3_games_bucket)],
+        state
+            .s3
+            .copy_object()
+            .bucket(state.config.s3_games_bucket.as_str())
+            .copy_source(format!("{}/{}", state.config.s3_games_bucket, from))
+            .key(to)
+            .send(),
+    )
+    .await;
+    state.metrics.record_s3("copy_object", copied.is_ok(), 0);
+    copied?;
+    Ok(())
+}
+
+/// Read `key` from the games bucket. The span covers reading the body as well as the request,
+/// since the object is streamed in after the response headers arrive.
+async fn get_object(state: &AppState, key: String) -> Result<Bytes, HttpResponse> {
+    let mut span = Span::child("s3.get_object", SpanKind::Client);
+    span.attribute("s3.bucket", &state.config.s3_games_bucket);
+    let fetched = span
+        .scope(async {
+            let object = state
+                .s3
+                .get_object()
+                .bucket(state.config.s3_games_bucket.as_str())
+                .key(key)
+                .send()
+                .await
+                .map_err(|e| ("could not get object", e.to_string()))?;
+            object
+                .body
+                .collect()
+                .await
+                .map(|bytes| bytes.into_bytes())
+                .map_err(|e| ("could not read object body", e.to_string()))
+        })
+        .await;
+    state.metrics.record_s3(
+        "get_object",
+        fetched.is_ok(),
+        fetched.as_ref().map_or(0, |bytes| bytes.len() as u64),
+    );
+    if let Err((_, error)) = &fetched {
+        span.fail(error);
+    }
+    span.end();
+    fetched.map_err(|(context, error)| internal_error(context, error))
+}
+
+/// Remove the files of a game that was not added. The game was never listed, so a failure only
+/// leaves files behind, and is logged so they can be cleaned up.
+async fn remove_unstored(state: &AppState, uuid: &str) {
+    if let Err(e) = delete_recursively(state, uuid).await {
+        json::event(
+            Level::Error,
+            "could not remove files of a game that was not added",
+            json!({
+                "game_id": uuid,
+                "error": e.to_string(),
+            }),
+        );
+    }
+}
+
+/// Drop the copy of the binary replaced by `update_binary`, which is no longer needed
+async fn remove_previous_binary(state: &AppState, id: &str) {
+    if let Err(e) = delete_object(state, previous_binary_key(id)).await {
+        json::event(
+            Level::Error,
+            "could not remove previous game binary",
+            json!({
+                "game_id": id,
+                "error": e.to_string(),
+            }),
+        );
+    }
+}
+
 #[utoipa::path(
     context_path = "/games",
     responses(
@@ -427,73 +1566,114 @@ pub async fn delete_game(state: Data<AppState>, path: Path<(String,)>) -> impl R
     let (id,) = path.into_inner();
     if query_as::<_, Game>("SELECT * FROM game WHERE id = $1")
         .bind(&id)
-        .fetch_one(&state.db)
+        .fetch_one(Traced(&state.db))
         .await
         .is_err()
     {
         return HttpResponse::BadRequest().body("Game ID Does Not Exist");
     }
-    match delete_recursively(&state.s3, &id).await {
-        Ok(_) => {
-            match query("DELETE FROM game WHERE id = $1")
-                .bind(&id)
-                .execute(&state.db)
-                .await
-            {
-                Ok(_) => match query("DELETE FROM game_tags WHERE game_id = $1")
-                    .bind(&id)
-                    .execute(&state.db)
-                    .await
-                {
-                    Ok(_) => HttpResponse::Ok().finish(),
-                    Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
-                },
-                Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
-            }
+    let mut transaction = match state.db.begin().await {
+        Ok(transaction) => transaction,
+        Err(e) => return internal_error("could not begin transaction", e),
+    };
+    for statement in [
+        "DELETE FROM game WHERE id = $1",
+        "DELETE FROM game_tags WHERE game_id = $1",
+        "INSERT INTO game_tombstones VALUES ($1) ON CONFLICT (id) DO UPDATE SET deleted_at = now()",
+    ] {
+        if let Err(e) = query(statement)
+            .bind(&id)
+            .execute(Traced(&mut transaction))
+            .await
+        {
+            let _ = transaction.rollback().await;
+            return internal_error("could not delete game", e);
         }
-        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
     }
+    if let Err(e) = notify::publish(
+        &mut transaction,
+        CatalogEventType::GameDeleted,
+        Some(&id),
+        None,
+    )
+    .await
+    {
+        let _ = transaction.rollback().await;
+        return internal_error("could not publish catalog event", e);
+    }
+    if let Err(e) = transaction.commit().await {
+        return internal_error("could not commit game deletion", e);
+    }
+    state.metrics.forget_game(&id);
+    // Objects are only removed once the game is gone, so a failed delete leaves it playable
+    if let Err(e) = delete_recursively(&state, &id).await {
+        return internal_error("could not delete game files", e);
+    }
+    HttpResponse::Ok().finish()
 }
 
 #[utoipa::path(
     context_path = "/games",
     responses(
-        (status = 200, description = "Provide game source zip", content_type="application/zip"),
+        (status = 200, description = "Provide game source zip, with its version in the ETag header", content_type="application/zip"),
         (status = 400, description = "Missing game"),
+        (status = 401, description = "Game is tagged authrequired and the request is not from an authenticated CSH user"),
         (status = 500, description = "Error Created by Query"),
     ),
     params(
-        ("id", description = "Unique id of game")
+        ("id", description = "Unique id of game"),
+        ("X-Devcade-User" = Option<String>, Header, description = "CSH user downloading the game, required with the API key for authrequired games"),
     ),
 )]
 #[get("/{id}/game")]
-pub async fn get_binary(state: Data<AppState>, path: Path<(String,)>) -> impl Responder {
+pub async fn get_binary(
+    state: Data<AppState>,
+    path: Path<(String,)>,
+    req: HttpRequest,
+) -> impl Responder {
     let (id,) = path.into_inner();
-    if query_as::<_, Game>("SELECT * FROM game WHERE id = $1")
-        .bind(&id)
-        .fetch_one(&state.db)
-        .await
-        .is_err()
+    let (auth_required, binary_updated_at) = match query_as::<_, (bool, DateTime<Utc>)>(&format!(
+        "SELECT {}, binary_updated_at FROM game WHERE id = $1",
+        has_tag(AUTH_REQUIRED_TAG)
+    ))
+    .bind(&id)
+    .fetch_optional(Traced(&state.db))
+    .await
     {
-        return HttpResponse::BadRequest().body("Game ID Does Not Exist");
+        Ok(Some(game)) => game,
+        Ok(None) => return HttpResponse::BadRequest().body("Game ID Does Not Exist"),
+        Err(e) => return internal_error("could not fetch game", e),
+    };
+    if auth_required {
+        let user = req
+            .headers()
+            .get(USER_HEADER)
+            .and_then(|user| user.to_str().ok());
+        let authenticated = match user {
+            Some(user) if has_api_key(&req) => match query_scalar::<_, bool>(
+                "SELECT EXISTS (SELECT 1 FROM users WHERE id = $1 AND user_type = 'CSH')",
+            )
+            .bind(user)
+            .fetch_one(Traced(&state.db))
+            .await
+            {
+                Ok(authenticated) => authenticated,
+                Err(e) => return internal_error("could not check CSH membership", e),
+            },
+            _ => false,
+        };
+        if !authenticated {
+            return HttpResponse::Unauthorized().body("CSH Authentication Required");
+        }
     }
-    match state
-        .s3
-        .get_object()
-        .bucket(&GAMES_BUCKET.to_string())
-        .key(format!("{}/{}.zip", id, id))
-        .send()
-        .await
-    {
-        Ok(objout) => {
-            let bytestream = objout.body.collect().await;
-            match bytestream {
-                Ok(bytes) => HttpResponse::Ok().body(bytes.into_bytes()),
-                Err(e) => HttpResponse::InternalServerError()
-                    .body(format!("Error getting object body: {}", e)),
-            }
+    match get_object(&state, binary_key(&id)).await {
+        Ok(bytes) => {
+            state.metrics.record_download(&id);
+            HttpResponse::Ok()
+                .insert_header(etag::etag(&binary_updated_at))
+                .body(bytes)
         }
-        Err(e) => HttpResponse::InternalServerError().body(format!("Error getting object: {}", e)),
+        Err(res) => res,
     }
 }
 
@@ -501,13 +1681,17 @@ pub async fn get_binary(state: Data<AppState>, path: Path<(String,)>) -> impl Re
     context_path = "/games",
     request_body(content=FileUploadDoc, content_type="multipart/form-data", description="Zip of game publish folder"),
     responses(
-        (status = 200, description = "Updated Game Binary"),
-        (status = 400, description = "Missing game"),
+        (status = 200, description = "Updated Game Binary, with its new version in the ETag header"),
+        (status = 400, description = "Missing game, or file is not a game zip"),
         (status = 401, description = "Invalid/Missing API Key"),
+        (status = 412, description = "Binary replaced since the version in If-Match"),
+        (status = 422, description = "Invalid manifest, by field", body = ValidationErrors),
+        (status = 428, description = "Missing If-Match header"),
         (status = 500, description = "Error Created by Query"),
     ),
     params(
-        ("id", description = "Unique id of game")
+        ("id", description = "Unique id of game"),
+        ("If-Match" = String, Header, description = "ETag of the binary being replaced, or * for any version"),
     ),
     security(
         ("api_key" = [])
@@ -518,26 +1702,163 @@ pub async fn update_binary(
     state: Data<AppState>,
     path: Path<(String,)>,
     MultipartForm(form): MultipartForm<FileUpload>,
+    req: HttpRequest,
 ) -> impl Responder {
     let (id,) = path.into_inner();
-    match query_as::<_, Game>("SELECT * FROM game WHERE id = $1")
-        .bind(&id)
-        .fetch_one(&state.db)
-        .await
+    let precondition = match Precondition::from_request(&req) {
+        Ok(precondition) => precondition,
+        Err(res) => return res,
+    };
+    // Hold the game until the new binary is recorded so concurrent replacements cannot interleave
+    let mut transaction = match state.db.begin().await {
+        Ok(transaction) => transaction,
+        Err(e) => return internal_error("could not begin transaction", e),
+    };
+    match query_scalar::<_, DateTime<Utc>>(
+        "SELECT binary_updated_at FROM game WHERE id = $1 FOR UPDATE",
+    )
+    .bind(&id)
+    .fetch_optional(Traced(&mut transaction))
+    .await
     {
-        Ok(game) => match verify_and_upload_game(form.file, &state.s3, Some(id.clone())).await {
-            Ok((_, hash)) => HttpResponse::Ok().json(Game {
-                id,
-                author: game.author,
-                upload_date: game.upload_date,
-                name: game.name,
-                hash,
-                description: game.description,
-            }),
-            Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
-        },
-        Err(_) => HttpResponse::BadRequest().body("Game ID Does Not Exist"),
+        Ok(Some(binary_updated_at)) if precondition.matches(&binary_updated_at) => {}
+        Ok(Some(_)) => {
+            let _ = transaction.rollback().await;
+            return etag::precondition_failed();
+        }
+        Ok(None) => {
+            let _ = transaction.rollback().await;
+            return HttpResponse::BadRequest().body("Game ID Does Not Exist");
+        }
+        Err(e) => {
+            let _ = transaction.rollback().await;
+            return internal_error("could not lock game", e);
+        }
+    }
+    let manifest = match traced_verify_game(&form.file, &state, Some(&id)).await {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            let _ = transaction.rollback().await;
+            return e.response();
+        }
+    };
+    let hash = match hash_game(&form.file).await {
+        Ok(hash) => hash,
+        Err(e) => {
+            let _ = transaction.rollback().await;
+            return e.response();
+        }
+    };
+    let players = manifest.players.as_ref();
+    let game = match query_as::<_, Versioned<Game>>(
+        "
+        UPDATE game SET hash = $2,
+            name = COALESCE($3, name),
+            description = COALESCE($4, description),
+            version = COALESCE($5, version),
+            entrypoint = COALESCE($6, entrypoint),
+            min_client_version = COALESCE($7, min_client_version),
+            min_players = COALESCE($8, min_players),
+            max_players = COALESCE($9, max_players),
+            controls = COALESCE($10, controls),
+            session_minutes = COALESCE($11, session_minutes),
+            credits = COALESCE($12, credits),
+            repo_url = COALESCE($13, repo_url),
+            license = COALESCE($14, license),
+            content_warnings = COALESCE($15, content_warnings),
+            binary_updated_at = now(), updated_at = now()
+        WHERE id = $1
+        RETURNING *
+        ",
+    )
+    .bind(&id)
+    .bind(&hash)
+    .bind(&manifest.name)
+    .bind(&manifest.description)
+    .bind(&manifest.version)
+    .bind(&manifest.entrypoint)
+    .bind(&manifest.min_client_version)
+    .bind(players.map(|players| players.min))
+    .bind(players.map(|players| players.max))
+    .bind(&manifest.controls)
+    .bind(manifest.session_minutes)
+    .bind(&manifest.credits)
+    .bind(&manifest.repo_url)
+    .bind(&manifest.license)
+    .bind(&manifest.content_warnings)
+    .fetch_one(Traced(&mut transaction))
+    .await
+    {
+        Ok(game) => game,
+        Err(e) => {
+            let _ = transaction.rollback().await;
+            return internal_error("could not update game", e);
+        }
+    };
+    if let Some(tags) = &manifest.tags {
+        // System tags are the catalog's to set, so the manifest's tags replace only the others
+        if let Err(e) =
+            query("DELETE FROM game_tags WHERE game_id = $1 AND NOT (tag_name = ANY($2))")
+                .bind(&id)
+                .bind(&SYSTEM_TAGS[..])
+                .execute(Traced(&mut transaction))
+                .await
+        {
+            let _ = transaction.rollback().await;
+            return internal_error("could not clear game tags", e);
+        }
+        if let Err(e) = insert_tags(&mut transaction, &id, tags).await {
+            let _ = transaction.rollback().await;
+            return internal_error("could not add game tags", e);
+        }
     }
+    if let Some(achievements) = manifest.achievements {
+        if let Err(e) = replace_achievements(&mut transaction, &id, &achievements).await {
+            let _ = transaction.rollback().await;
+            return internal_error("could not replace game achievements", e);
+        }
+    }
+    if let Err(e) = notify::publish(
+        &mut transaction,
+        CatalogEventType::GameBinaryReplaced,
+        Some(&id),
+        None,
+    )
+    .await
+    {
+        let _ = transaction.rollback().await;
+        return internal_error("could not publish catalog event", e);
+    }
+    // The binary the rows describe until the commit is kept, to be put back if the commit fails
+    if let Err(e) = copy_object(&state, binary_key(&id), previous_binary_key(&id)).await {
+        let _ = transaction.rollback().await;
+        return internal_error("could not keep previous game binary", e);
+    }
+    // The binary is stored only once the rows describing it are, and they are kept only if it is
+    if let Err(e) = upload_game(&form.file, &state, &id).await {
+        let _ = transaction.rollback().await;
+        remove_previous_binary(&state, &id).await;
+        return e.response();
+    }
+    if let Err(e) = transaction.commit().await {
+        if let Err(e) = copy_object(&state, previous_binary_key(&id), binary_key(&id)).await {
+            json::event(
+                Level::Error,
+                "could not restore previous game binary",
+                json!({
+                    "game_id": id,
+                    "error": e.to_string(),
+                }),
+            );
+        }
+        remove_previous_binary(&state, &id).await;
+        return internal_error("could not commit game binary", e);
+    }
+    remove_previous_binary(&state, &id).await;
+    // The binary and the game were last changed together, at `updated_at`
+    HttpResponse::Ok()
+        .insert_header(etag::etag(&game.updated_at))
+        .json(game.resource)
 }
 
 #[utoipa::path(
@@ -556,29 +1877,53 @@ pub async fn get_banner(state: Data<AppState>, path: Path<(String,)>) -> impl Re
     let (id,) = path.into_inner();
     if query_as::<_, Game>("SELECT * FROM game WHERE id = $1")
         .bind(&id)
-        .fetch_one(&state.db)
+        .fetch_one(Traced(&state.db))
         .await
         .is_err()
     {
         return HttpResponse::BadRequest().body("Game ID Does Not Exist");
     }
-    match state
-        .s3
-        .get_object()
-        .bucket(&GAMES_BUCKET.to_string())
-        .key(format!("{}/banner", id))
-        .send()
-        .await
+    match get_object(&state, format!("{}/banner", id)).await {
+        Ok(bytes) => HttpResponse::Ok().body(bytes),
+        Err(res) => res,
+    }
+}
+
+/// Record that the `component` image of game `id` was replaced, along with its catalog event
+async fn record_image_update(
+    state: &AppState,
+    id: &str,
+    component: ImageComponent,
+) -> HttpResponse {
+    let mut transaction = match state.db.begin().await {
+        Ok(transaction) => transaction,
+        Err(e) => return internal_error("could not begin transaction", e),
+    };
+    if let Err(e) = query(&format!(
+        "UPDATE game SET {}_updated_at = now(), updated_at = now() WHERE id = $1",
+        component.filename()
+    ))
+    .bind(id)
+    .execute(Traced(&mut transaction))
+    .await
     {
-        Ok(objout) => {
-            let bytestream = objout.body.collect().await;
-            match bytestream {
-                Ok(bytes) => HttpResponse::Ok().body(bytes.into_bytes()),
-                Err(e) => HttpResponse::InternalServerError()
-                    .body(format!("Error getting object body: {}", e)),
-            }
-        }
-        Err(e) => HttpResponse::InternalServerError().body(format!("Error getting object: {}", e)),
+        let _ = transaction.rollback().await;
+        return internal_error("could not record image update", e);
+    }
+    if let Err(e) = notify::publish(
+        &mut transaction,
+        CatalogEventType::GameUpdated,
+        Some(id),
+        None,
+    )
+    .await
+    {
+        let _ = transaction.rollback().await;
+        return internal_error("could not publish catalog event", e);
+    }
+    match transaction.commit().await {
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => internal_error("could not commit image update", e),
     }
 }
 
@@ -607,13 +1952,13 @@ pub async fn update_banner(
     let (id,) = path.into_inner();
     match query_as::<_, Game>("SELECT * FROM game WHERE id = $1")
         .bind(&id)
-        .fetch_one(&state.db)
+        .fetch_one(Traced(&state.db))
         .await
     {
         Ok(_) => {
-            match verify_and_upload_image(form.file, &state.s3, ImageComponent::Banner, &id).await {
-                Ok(_) => HttpResponse::Ok().finish(),
-                Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+            match verify_and_upload_image(form.file, &state, ImageComponent::Banner, &id).await {
+                Ok(_) => record_image_update(&state, &id, ImageComponent::Banner).await,
+                Err(e) => e.response(),
             }
         }
         Err(_) => HttpResponse::BadRequest().body("Game ID Does Not Exist"),
@@ -636,29 +1981,15 @@ pub async fn get_icon(state: Data<AppState>, path: Path<(String,)>) -> impl Resp
     let (id,) = path.into_inner();
     if query_as::<_, Game>("SELECT * FROM game WHERE id = $1")
         .bind(&id)
-        .fetch_one(&state.db)
+        .fetch_one(Traced(&state.db))
         .await
         .is_err()
     {
         return HttpResponse::BadRequest().body("Game ID Does Not Exist");
     }
-    match state
-        .s3
-        .get_object()
-        .bucket(&GAMES_BUCKET.to_string())
-        .key(format!("{}/icon", id))
-        .send()
-        .await
-    {
-        Ok(objout) => {
-            let bytestream = objout.body.collect().await;
-            match bytestream {
-                Ok(bytes) => HttpResponse::Ok().body(bytes.into_bytes()),
-                Err(e) => HttpResponse::InternalServerError()
-                    .body(format!("Error getting object body: {}", e)),
-            }
-        }
-        Err(e) => HttpResponse::InternalServerError().body(format!("Error getting object: {}", e)),
+    match get_object(&state, format!("{}/icon", id)).await {
+        Ok(bytes) => HttpResponse::Ok().body(bytes),
+        Err(res) => res,
     }
 }
 
@@ -687,13 +2018,13 @@ pub async fn update_icon(
     let (id,) = path.into_inner();
     match query_as::<_, Game>("SELECT * FROM game WHERE id = $1")
         .bind(&id)
-        .fetch_one(&state.db)
+        .fetch_one(Traced(&state.db))
         .await
     {
         Ok(_) => {
-            match verify_and_upload_image(form.file, &state.s3, ImageComponent::Icon, &id).await {
-                Ok(_) => HttpResponse::Ok().finish(),
-                Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+            match verify_and_upload_image(form.file, &state, ImageComponent::Icon, &id).await {
+                Ok(_) => record_image_update(&state, &id, ImageComponent::Icon).await,
+                Err(e) => e.response(),
             }
         }
         Err(_) => HttpResponse::BadRequest().body("Game ID Does Not Exist"),
@@ -1,13 +1,16 @@
 use std::{fs::File, io::Read};
 
 use crate::app::{configure_app, get_app_data};
+use crate::config::Config;
 #[cfg(test)]
 use crate::{
-    models::GameWithTags,
+    games::manifest::GameManifest,
+    models::{GameChanges, GameWithTags},
     tests::{
         get_test_server, TEST_GAME_A, TEST_GAME_A_WITH_TAGS, TEST_GAME_B, TEST_GAME_B_WITH_TAGS,
-        TEST_GAME_C, TEST_GAME_D, TEST_GAME_E,
+        TEST_GAME_C, TEST_GAME_D, TEST_GAME_E, TEST_GAME_N,
     },
+    validate::ValidationErrors,
 };
 
 use actix_web::{test, App};
@@ -139,7 +142,8 @@ async fn test_edit_game_data() {
     edited_game.description = "I changed the description!".to_string();
     let req = srv
         .put(format!("/games/{}", edited_game.id))
-        .insert_header(("frontend_api_key", "TESTING"));
+        .insert_header(("frontend_api_key", "TESTING"))
+        .insert_header(("If-Match", "*"));
     let mut res = req.send_json(&edited_game).await.unwrap();
     println!(
         "{} | {}",
@@ -202,7 +206,7 @@ async fn test_add_game() {
         description: "Chom".to_string(),
         author: "skyz".to_string(),
     };
-    let app_data = get_app_data().await;
+    let app_data = get_app_data(Config::load().unwrap()).await.unwrap();
     let app = test::init_service(
         App::new()
             .configure(configure_app)
@@ -241,7 +245,7 @@ async fn test_add_game_unauthorized() {
         description: "Chom".to_string(),
         author: "skyz".to_string(),
     };
-    let app_data = get_app_data().await;
+    let app_data = get_app_data(Config::load().unwrap()).await.unwrap();
     let app = test::init_service(
         App::new()
             .configure(configure_app)
@@ -282,7 +286,7 @@ async fn test_get_game_binary() {
 async fn test_edit_game_binary() {
     let gamefile = File::open("TESTING/data/HHHHHHHH-HHHH-HHHH-HHHH-HHHHHHHHHHHH/HHHHHHHH-HHHH-HHHH-HHHH-HHHHHHHHHHHH.zip").unwrap();
     let mut fileupload = FileUploadTest { file: gamefile };
-    let app_data = get_app_data().await;
+    let app_data = get_app_data(Config::load().unwrap()).await.unwrap();
     let app = test::init_service(
         App::new()
             .configure(configure_app)
@@ -294,6 +298,7 @@ async fn test_edit_game_binary() {
     let req = test::TestRequest::put()
         .uri("/games/GGGGGGGG-GGGG-GGGG-GGGG-GGGGGGGGGGGG/game")
         .append_header(("frontend_api_key", "TESTING"))
+        .append_header(("If-Match", "*"))
         .append_header((
             "Content-Type",
             "mutlipart/form-data; boundary=----------------43123453263245325234",
@@ -312,7 +317,7 @@ async fn test_edit_game_binary() {
 async fn test_edit_game_binary_unauthorized() {
     let gamefile = File::open("TESTING/data/HHHHHHHH-HHHH-HHHH-HHHH-HHHHHHHHHHHH/HHHHHHHH-HHHH-HHHH-HHHH-HHHHHHHHHHHH.zip").unwrap();
     let mut fileupload = FileUploadTest { file: gamefile };
-    let app_data = get_app_data().await;
+    let app_data = get_app_data(Config::load().unwrap()).await.unwrap();
     let app = test::init_service(
         App::new()
             .configure(configure_app)
@@ -355,7 +360,7 @@ async fn test_edit_game_banner() {
     let bannerfile =
         File::open("TESTING/data/HHHHHHHH-HHHH-HHHH-HHHH-HHHHHHHHHHHH/banner").unwrap();
     let mut fileupload = FileUploadTest { file: bannerfile };
-    let app_data = get_app_data().await;
+    let app_data = get_app_data(Config::load().unwrap()).await.unwrap();
     let app = test::init_service(
         App::new()
             .configure(configure_app)
@@ -385,7 +390,7 @@ async fn test_edit_game_banner_unauthorized() {
     let bannerfile =
         File::open("TESTING/data/HHHHHHHH-HHHH-HHHH-HHHH-HHHHHHHHHHHH/banner").unwrap();
     let mut fileupload = FileUploadTest { file: bannerfile };
-    let app_data = get_app_data().await;
+    let app_data = get_app_data(Config::load().unwrap()).await.unwrap();
     let app = test::init_service(
         App::new()
             .configure(configure_app)
@@ -426,7 +431,7 @@ async fn test_get_game_icon() {
 async fn test_edit_game_icon() {
     let iconfile = File::open("TESTING/data/HHHHHHHH-HHHH-HHHH-HHHH-HHHHHHHHHHHH/icon").unwrap();
     let mut fileupload = FileUploadTest { file: iconfile };
-    let app_data = get_app_data().await;
+    let app_data = get_app_data(Config::load().unwrap()).await.unwrap();
     let app = test::init_service(
         App::new()
             .configure(configure_app)
@@ -455,7 +460,7 @@ async fn test_edit_game_icon() {
 async fn test_edit_game_icon_unauthorized() {
     let iconfile = File::open("TESTING/data/HHHHHHHH-HHHH-HHHH-HHHH-HHHHHHHHHHHH/icon").unwrap();
     let mut fileupload = FileUploadTest { file: iconfile };
-    let app_data = get_app_data().await;
+    let app_data = get_app_data(Config::load().unwrap()).await.unwrap();
     let app = test::init_service(
         App::new()
             .configure(configure_app)
@@ -478,3 +483,448 @@ async fn test_edit_game_icon_unauthorized() {
     println!("{} | {:?}", resp.status(), resp.response().body());
     assert_eq!(resp.status().as_u16(), 401);
 }
+
+#[actix_web::test]
+async fn test_get_game_changes_full_sync() {
+    let srv = get_test_server().await;
+    let req = srv.get("/api/games/changes");
+    let mut res = req.send().await.unwrap();
+    assert!(res.status().is_success());
+    let changes: GameChanges = res.json::<GameChanges>().await.unwrap();
+    assert!(changes.since.is_none());
+    assert!(changes.updated.is_empty());
+    assert!(changes.added.iter().any(|game| game.id == TEST_GAME_A.id));
+}
+
+#[actix_web::test]
+async fn test_get_game_changes_since() {
+    let srv = get_test_server().await;
+    let req = srv.get("/api/games/changes");
+    let mut res = req.send().await.unwrap();
+    let full_sync: GameChanges = res.json::<GameChanges>().await.unwrap();
+    let req = srv
+        .get("/api/games/changes")
+        .query(&[("since", full_sync.until)])
+        .unwrap();
+    let mut res = req.send().await.unwrap();
+    assert!(res.status().is_success());
+    let changes: GameChanges = res.json::<GameChanges>().await.unwrap();
+    assert_eq!(changes.since, Some(full_sync.until));
+    assert!(changes.until >= full_sync.until);
+    assert!(changes.added.iter().all(|game| game.id != TEST_GAME_A.id));
+}
+
+fn zip_bytes(directories: &[&str], files: &[(&str, &str)]) -> Vec<u8> {
+    let mut writer = zip::ZipWriter::new(std::io::Cursor::new(vec![]));
+    for directory in directories {
+        writer
+            .add_directory(*directory, zip::write::FileOptions::default())
+            .unwrap();
+    }
+    for (name, contents) in files {
+        writer
+            .start_file(*name, zip::write::FileOptions::default())
+            .unwrap();
+        std::io::Write::write_all(&mut writer, contents.as_bytes()).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+fn manifest_zip(files: &[(&str, &str)]) -> zip::ZipArchive<std::io::Cursor<Vec<u8>>> {
+    zip::ZipArchive::new(std::io::Cursor::new(zip_bytes(&["publish/"], files))).unwrap()
+}
+
+/// Multipart body of `fields`, which are files when given a content type
+fn form(boundary: &str, fields: &[(&str, Option<&str>, &[u8])]) -> Vec<u8> {
+    let mut body = vec![];
+    for (name, content_type, contents) in fields {
+        let header = match content_type {
+            Some(content_type) => format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"upload\"\r\nContent-Type: {}\r\n\r\n",
+                boundary, name, content_type
+            ),
+            None => format!(
+                "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n",
+                boundary, name
+            ),
+        };
+        body.extend_from_slice(header.as_bytes());
+        body.extend_from_slice(contents);
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+    body
+}
+
+/// Multipart body with `contents` as its only field, `file`
+fn file_form(boundary: &str, content_type: &str, contents: &[u8]) -> Vec<u8> {
+    form(boundary, &[("file", Some(content_type), contents)])
+}
+
+#[actix_web::test]
+async fn test_add_game_invalid() {
+    let srv = get_test_server().await;
+    let upload = |manifest: &'static str, banner_type: &'static str| {
+        let req = srv
+            .post("/api/games/")
+            .insert_header(("frontend_api_key", "TESTING"))
+            .insert_header((
+                "Content-Type",
+                "multipart/form-data; boundary=devcadeboundary",
+            ));
+        async move {
+            let game = zip_bytes(&["publish/"], &[("devcade.json", manifest)]);
+            req.send_body(form(
+                "devcadeboundary",
+                &[
+                    ("game", Some("application/zip"), &game),
+                    ("banner", Some(banner_type), b"banner"),
+                    ("icon", Some("image/png"), b"icon"),
+                    ("author", None, b"skyz"),
+                ],
+            ))
+            .await
+            .unwrap()
+        }
+    };
+    // Rejected before anything is stored, without a title or description from either source
+    let mut res = upload("{\"credits\": [\"\"]}", "image/png").await;
+    assert_eq!(res.status().as_u16(), 422);
+    let errors = res.json::<ValidationErrors>().await.unwrap();
+    for field in ["name", "description", "credits"] {
+        assert!(errors.errors.contains_key(field), "{:?}", errors);
+    }
+    let res = upload(
+        "{\"name\": \"Game\", \"description\": \"Game\"}",
+        "text/plain",
+    )
+    .await;
+    assert_eq!(res.status().as_u16(), 400);
+}
+
+#[actix_web::test]
+async fn test_update_binary_rejected() {
+    let srv = get_test_server().await;
+    let upload = |content_type: &'static str, contents: Vec<u8>| {
+        let req = srv
+            .put(format!("/api/games/{}/game", TEST_GAME_B.id))
+            .insert_header(("frontend_api_key", "TESTING"))
+            .insert_header(("If-Match", "*"))
+            .insert_header((
+                "Content-Type",
+                "multipart/form-data; boundary=devcadeboundary",
+            ));
+        async move {
+            req.send_body(file_form("devcadeboundary", content_type, &contents))
+                .await
+                .unwrap()
+        }
+    };
+    let res = upload("text/plain", b"not a zip".to_vec()).await;
+    assert_eq!(res.status().as_u16(), 400);
+    let res = upload("application/zip", b"not a zip".to_vec()).await;
+    assert_eq!(res.status().as_u16(), 400);
+    let res = upload("application/zip", zip_bytes(&[], &[("README", "")])).await;
+    assert_eq!(res.status().as_u16(), 400);
+    let mut res = upload(
+        "application/zip",
+        zip_bytes(
+            &["publish/"],
+            &[("devcade.json", "{\"tags\": [\"NoSuchTag\"]}")],
+        ),
+    )
+    .await;
+    assert_eq!(res.status().as_u16(), 422);
+    let errors = res.json::<ValidationErrors>().await.unwrap();
+    assert!(errors.errors.contains_key("tags"));
+    let mut res = upload(
+        "application/zip",
+        zip_bytes(
+            &["publish/"],
+            &[("devcade.json", "{\"tags\": [\"featured\"]}")],
+        ),
+    )
+    .await;
+    assert_eq!(res.status().as_u16(), 422);
+    let errors = res.json::<ValidationErrors>().await.unwrap();
+    assert_eq!(errors.errors["tags"], vec!["featured is a system tag"]);
+}
+
+#[actix_web::test]
+async fn test_read_toml_manifest() {
+    let mut zip_archive = manifest_zip(&[
+        (
+            "devcade.toml",
+            "
+            name = \"BrickBreaker\"
+            version = \"1.2.0\"
+            entrypoint = \"publish/BrickBreaker\"
+            controls = [\"joystick\", \"buttons\"]
+            players = { min = 1, max = 2 }
+            ",
+        ),
+        ("publish/BrickBreaker", ""),
+        (
+            "achievements.json",
+            "[{\"id\": \"first_blood\", \"title\": \"First Blood\", \"description\": \"Break a brick\"}]",
+        ),
+    ]);
+    let manifest = GameManifest::read(&mut zip_archive).unwrap();
+    assert_eq!(manifest.name.as_deref(), Some("BrickBreaker"));
+    assert_eq!(manifest.players.unwrap().max, 2);
+    assert_eq!(manifest.achievements.unwrap()[0].id, "first_blood");
+}
+
+#[actix_web::test]
+async fn test_read_invalid_manifest() {
+    for (manifest, reason) in [
+        ("{\"version\": \"one\"}", "invalid version"),
+        ("{\"entrypoint\": \"publish/Missing\"}", "entrypoint"),
+        ("{\"players\": {\"min\": 3, \"max\": 2}}", "players"),
+        ("{\"controls\": [\"steering wheel\"]}", "unknown control"),
+    ] {
+        let mut zip_archive = manifest_zip(&[("devcade.json", manifest)]);
+        let error = GameManifest::read(&mut zip_archive).unwrap_err();
+        assert!(error.to_string().contains(reason), "{}", error);
+    }
+}
+
+#[actix_web::test]
+async fn test_edit_game_metadata_and_filter() {
+    let srv = get_test_server().await;
+    let req = srv
+        .put(format!("/api/games/{}", TEST_GAME_E.id))
+        .insert_header(("frontend_api_key", "TESTING"))
+        .insert_header(("If-Match", "*"));
+    let mut res = req
+        .send_json(&serde_json::json!({
+            "name": TEST_GAME_E.name,
+            "description": TEST_GAME_E.description,
+            "author": TEST_GAME_E.author,
+            "tags": [],
+            "min_players": 2,
+            "max_players": 4,
+            "controls": ["joystick"],
+            "session_minutes": 10,
+            "credits": ["Ella - Art"],
+            "license": "MIT",
+            "content_warnings": ["flashing lights"],
+        }))
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    let game = res.json::<crate::models::Game>().await.unwrap();
+    assert_eq!(game.max_players, Some(4));
+    assert_eq!(game.credits, vec!["Ella - Art".to_string()]);
+
+    let listed = |query: &'static str| {
+        let req = srv.get(format!("/api/games/?{}", query));
+        async move {
+            let mut res = req.send().await.unwrap();
+            assert!(res.status().is_success());
+            res.json::<Vec<GameWithTags>>()
+                .await
+                .unwrap()
+                .iter()
+                .any(|game| game.id == TEST_GAME_E.id)
+        }
+    };
+    assert!(listed("players=3&controls=joystick,buttons&max_session_minutes=10&license=MIT").await);
+    assert!(!listed("players=1").await);
+    assert!(!listed("controls=buttons").await);
+    assert!(!listed("exclude_warnings=gore,flashing%20lights").await);
+
+    let req = srv
+        .put(format!("/api/games/{}", TEST_GAME_E.id))
+        .insert_header(("frontend_api_key", "TESTING"))
+        .insert_header(("If-Match", "*"));
+    let res = req
+        .send_json(&serde_json::json!({
+            "name": TEST_GAME_E.name,
+            "description": TEST_GAME_E.description,
+            "author": TEST_GAME_E.author,
+            "tags": [],
+            "controls": ["steering wheel"],
+        }))
+        .await
+        .unwrap();
+    assert_eq!(res.status().as_u16(), 422);
+
+    // Fields left out keep their values, and a single bound is checked against the stored one
+    let edit = |body: serde_json::Value| {
+        let req = srv
+            .put(format!("/api/games/{}", TEST_GAME_E.id))
+            .insert_header(("frontend_api_key", "TESTING"))
+            .insert_header(("If-Match", "*"));
+        async move { req.send_json(&body).await.unwrap() }
+    };
+    let mut res = edit(serde_json::json!({
+        "name": TEST_GAME_E.name,
+        "description": TEST_GAME_E.description,
+        "author": TEST_GAME_E.author,
+        "tags": [],
+        "max_players": 3,
+    }))
+    .await;
+    assert!(res.status().is_success());
+    let game = res.json::<crate::models::Game>().await.unwrap();
+    assert_eq!((game.min_players, game.max_players), (Some(2), Some(3)));
+    assert_eq!(game.license.as_deref(), Some("MIT"));
+    assert_eq!(game.credits, vec!["Ella - Art".to_string()]);
+    for invalid in [
+        serde_json::json!({"max_players": 1}),
+        serde_json::json!({"min_players": 0}),
+        serde_json::json!({"session_minutes": 0}),
+    ] {
+        let mut body = serde_json::json!({
+            "name": TEST_GAME_E.name,
+            "description": TEST_GAME_E.description,
+            "author": TEST_GAME_E.author,
+            "tags": [],
+        });
+        body.as_object_mut()
+            .unwrap()
+            .extend(invalid.as_object().unwrap().clone());
+        assert_eq!(edit(body).await.status().as_u16(), 422);
+    }
+
+    // Games that don't declare a player count are not filtered out by it
+    let req = srv.get("/api/games/?players=1");
+    let mut res = req.send().await.unwrap();
+    let games = res.json::<Vec<GameWithTags>>().await.unwrap();
+    assert!(games.iter().any(|game| game.id == TEST_GAME_B.id));
+}
+
+#[actix_web::test]
+async fn test_system_tags() {
+    let srv = get_test_server().await;
+    let set_tags = |tags: &'static [&'static str]| {
+        let req = srv
+            .put(format!("/api/games/{}", TEST_GAME_N.id))
+            .insert_header(("frontend_api_key", "TESTING"))
+            .insert_header(("If-Match", "*"));
+        async move {
+            let res = req
+                .send_json(&serde_json::json!({
+                    "name": TEST_GAME_N.name,
+                    "description": TEST_GAME_N.description,
+                    "author": TEST_GAME_N.author,
+                    "tags": tags,
+                }))
+                .await
+                .unwrap();
+            assert!(res.status().is_success());
+        }
+    };
+    let listed = |path: &'static str, api_key: bool| {
+        let mut req = srv.get(path);
+        if api_key {
+            req = req.insert_header(("frontend_api_key", "TESTING"));
+        }
+        async move {
+            let mut res = req.send().await.unwrap();
+            assert!(res.status().is_success());
+            res.json::<Vec<GameWithTags>>()
+                .await
+                .unwrap()
+                .iter()
+                .any(|game| game.id == TEST_GAME_N.id)
+        }
+    };
+
+    set_tags(&["featured", "authrequired"]).await;
+    assert!(listed("/api/games/featured", false).await);
+    assert!(listed("/api/games/", false).await);
+    let req = srv.get(format!("/api/games/{}/game", TEST_GAME_N.id));
+    assert_eq!(req.send().await.unwrap().status().as_u16(), 401);
+    let req = srv
+        .get(format!("/api/games/{}/game", TEST_GAME_N.id))
+        .insert_header(("frontend_api_key", "TESTING"))
+        .insert_header(("X-Devcade-User", "nobody"));
+    assert_eq!(req.send().await.unwrap().status().as_u16(), 401);
+
+    set_tags(&["featured", "authrequired", "hidden"]).await;
+    assert!(!listed("/api/games/featured", false).await);
+    assert!(!listed("/api/games/", false).await);
+    assert!(listed("/api/games/?include_hidden=true", true).await);
+    let req = srv.get("/api/games/?include_hidden=true");
+    assert_eq!(req.send().await.unwrap().status().as_u16(), 401);
+    let req = srv.get(format!("/api/games/{}", TEST_GAME_N.id));
+    assert_eq!(req.send().await.unwrap().status().as_u16(), 400);
+    let req = srv
+        .get(format!("/api/games/{}", TEST_GAME_N.id))
+        .insert_header(("frontend_api_key", "TESTING"));
+    assert!(req.send().await.unwrap().status().is_success());
+    let req = srv.get(format!("/api/users/{}/games", TEST_GAME_N.author));
+    let mut res = req.send().await.unwrap();
+    let games = res.json::<Vec<GameWithTags>>().await.unwrap();
+    assert!(games.iter().all(|game| game.id != TEST_GAME_N.id));
+    let req = srv.get("/api/games/changes");
+    let mut res = req.send().await.unwrap();
+    let changes = res.json::<GameChanges>().await.unwrap();
+    assert!(changes.added.iter().all(|game| game.id != TEST_GAME_N.id));
+    assert!(changes.removed.iter().any(|game| game.id == TEST_GAME_N.id));
+
+    set_tags(&[]).await;
+}
+
+#[actix_web::test]
+async fn test_patch_game() {
+    let srv = get_test_server().await;
+    let patch = |body: serde_json::Value| {
+        let req = srv
+            .patch(format!("/api/games/{}", TEST_GAME_C.id))
+            .insert_header(("frontend_api_key", "TESTING"))
+            .insert_header(("If-Match", "*"))
+            .content_type("application/merge-patch+json");
+        async move { req.send_body(body.to_string()).await.unwrap() }
+    };
+
+    let mut res = patch(serde_json::json!({
+        "repo_url": "https://github.com/ComputerScienceHouse/devcade-games",
+        "content_warnings": ["flashing lights"],
+    }))
+    .await;
+    assert!(res.status().is_success());
+    let game = res.json::<GameWithTags>().await.unwrap();
+    assert_eq!(game.name, TEST_GAME_C.name);
+    assert_eq!(game.content_warnings, vec!["flashing lights".to_string()]);
+
+    let mut res = patch(serde_json::json!({"repo_url": null, "content_warnings": null})).await;
+    assert!(res.status().is_success());
+    let game = res.json::<GameWithTags>().await.unwrap();
+    assert_eq!(game.repo_url, None);
+    assert!(game.content_warnings.is_empty());
+
+    let res = patch(serde_json::json!({"author": "skyz"})).await;
+    assert_eq!(res.status().as_u16(), 400);
+    let res = patch(serde_json::json!({"name": "C".repeat(129)})).await;
+    assert_eq!(res.status().as_u16(), 422);
+}
+
+#[actix_web::test]
+async fn test_edit_game_invalid() {
+    let srv = get_test_server().await;
+    let req = srv
+        .put(format!("/api/games/{}", TEST_GAME_B.id))
+        .insert_header(("frontend_api_key", "TESTING"))
+        .insert_header(("If-Match", "*"));
+    let mut res = req
+        .send_json(&serde_json::json!({
+            "name": " ",
+            "description": "B".repeat(1501),
+            "author": "nobody",
+            "tags": ["TestTag1", "NoSuchTag"],
+            "repo_url": "github.com/ComputerScienceHouse",
+        }))
+        .await
+        .unwrap();
+    assert_eq!(res.status().as_u16(), 422);
+    let errors = res.json::<ValidationErrors>().await.unwrap().errors;
+    let fields: Vec<&str> = errors.keys().map(String::as_str).collect();
+    assert_eq!(
+        fields,
+        vec!["author", "description", "name", "repo_url", "tags"]
+    );
+    assert_eq!(errors["tags"], vec!["tag NoSuchTag does not exist"]);
+}
@@ -0,0 +1,128 @@
+use crate::{models::AppState, telemetry::query::Traced};
+use actix_web::{get, rt::time::timeout, web::Data, HttpResponse, Responder};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{query, query_scalar};
+use std::{future::Future, time::Duration};
+use utoipa::ToSchema;
+
+/// Longest a dependency may take to answer before the API is reported as not ready
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Outcome of checking one dependency
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct Check {
+    pub ok: bool,
+    #[schema(example = "timed out after 2s")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct Readiness {
+    /// Whether every dependency is reachable
+    pub ready: bool,
+    pub database: Check,
+    /// The games bucket
+    pub storage: Check,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct BuildInfo {
+    #[schema(example = "1.0.0")]
+    pub version: String,
+    /// Commit the API was built from, `unknown` when it could not be determined
+    #[schema(example = "72b5c24d8a8cf5d4bbdf25a7d3bde8b0e1e0c1f3")]
+    pub commit: String,
+    pub built_at: DateTime<Utc>,
+    /// Latest schema migration applied to the database, missing when it cannot be read
+    #[schema(example = 1)]
+    pub schema_version: Option<i64>,
+}
+
+async fn check<T, E: ToString>(operation: impl Future<Output = Result<T, E>>) -> Check {
+    match timeout(CHECK_TIMEOUT, operation).await {
+        Ok(Ok(_)) => Check {
+            ok: true,
+            error: None,
+        },
+        Ok(Err(e)) => Check {
+            ok: false,
+            error: Some(e.to_string()),
+        },
+        Err(_) => Check {
+            ok: false,
+            error: Some(format!("timed out after {}s", CHECK_TIMEOUT.as_secs())),
+        },
+    }
+}
+
+#[utoipa::path(
+    context_path = "",
+    responses(
+        (status = 200, description = "The API is running"),
+    )
+)]
+#[get("/health")]
+pub async fn get_health() -> impl Responder {
+    HttpResponse::Ok().body("OK")
+}
+
+#[utoipa::path(
+    context_path = "",
+    responses(
+        (status = 200, description = "The database and games bucket are reachable", body = Readiness),
+        (status = 503, description = "A dependency is unreachable", body = Readiness),
+    )
+)]
+#[get("/ready")]
+pub async fn get_ready(state: Data<AppState>) -> impl Responder {
+    let database = check(query("SELECT 1").execute(Traced(&state.db))).await;
+    let storage = check(
+        state
+            .s3
+            .head_bucket()
+            .bucket(state.config.s3_games_bucket.as_str())
+            .send(),
+    )
+    .await;
+    state.metrics.record_s3("head_bucket", storage.ok, 0);
+    let readiness = Readiness {
+        ready: database.ok && storage.ok,
+        database,
+        storage,
+    };
+    match readiness.ready {
+        true => HttpResponse::Ok().json(readiness),
+        false => HttpResponse::ServiceUnavailable().json(readiness),
+    }
+}
+
+#[utoipa::path(
+    context_path = "",
+    responses(
+        (status = 200, description = "What is running", body = BuildInfo),
+    )
+)]
+#[get("/version")]
+pub async fn get_version(state: Data<AppState>) -> impl Responder {
+    let schema_version = timeout(
+        CHECK_TIMEOUT,
+        query_scalar::<_, Option<i64>>("SELECT max(version) FROM schema_migrations")
+            .fetch_one(Traced(&state.db)),
+    )
+    .await
+    .ok()
+    .and_then(Result::ok)
+    .flatten();
+    let built_at = env!("DEVCADE_BUILD_TIMESTAMP")
+        .parse()
+        .ok()
+        .and_then(|timestamp| NaiveDateTime::from_timestamp_opt(timestamp, 0))
+        .unwrap_or_default();
+    HttpResponse::Ok().json(BuildInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        commit: env!("DEVCADE_GIT_COMMIT").to_string(),
+        built_at: DateTime::from_utc(built_at, Utc),
+        schema_version,
+    })
+}
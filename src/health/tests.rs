@@ -0,0 +1,40 @@
+#[cfg(test)]
+use crate::tests::get_test_server;
+use crate::{
+    health::routes::{BuildInfo, Readiness},
+    migrations::runner::MIGRATIONS,
+};
+
+#[actix_web::test]
+async fn test_health() {
+    let srv = get_test_server().await;
+    let res = srv.get("/health").send().await.unwrap();
+    assert!(res.status().is_success());
+}
+
+#[actix_web::test]
+async fn test_ready() {
+    let srv = get_test_server().await;
+    let mut res = srv.get("/ready").send().await.unwrap();
+    let status = res.status();
+    let readiness = res.json::<Readiness>().await.unwrap();
+    assert!(readiness.database.ok);
+    assert_eq!(
+        readiness.ready,
+        readiness.database.ok && readiness.storage.ok
+    );
+    assert_eq!(status.is_success(), readiness.ready);
+}
+
+#[actix_web::test]
+async fn test_version() {
+    let srv = get_test_server().await;
+    let mut res = srv.get("/version").send().await.unwrap();
+    assert!(res.status().is_success());
+    let info = res.json::<BuildInfo>().await.unwrap();
+    assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(
+        info.schema_version,
+        MIGRATIONS.last().map(|migration| migration.version)
+    );
+}
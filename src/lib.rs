@@ -1,8 +1,25 @@
+pub mod achievements;
 pub mod app;
+pub mod config;
+pub mod contributors;
+pub mod cors;
+pub mod etag;
+pub mod events;
 pub mod games;
+pub mod health;
+pub mod logging;
+pub mod metrics;
+pub mod migrations;
 pub mod models;
+pub mod patch;
+pub mod reviews;
+pub mod scores;
 pub mod security;
+pub mod sessions;
 pub mod tags;
+pub mod telemetry;
 #[cfg(test)]
 pub mod tests;
 pub mod users;
+pub mod validate;
+pub mod webhooks;
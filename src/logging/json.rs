@@ -0,0 +1,53 @@
+use chrono::{SecondsFormat, Utc};
+use env_logger::Target;
+use log::{Level, Record};
+use serde_json::{Map, Value};
+use std::io::Write;
+
+/// Target of records whose message is a JSON object of fields, logged through [`event`]
+const EVENT_TARGET: &str = "devcade_api_rs::event";
+
+/// Log as one JSON object per line, filtered by `RUST_LOG` and defaulting to `info`
+pub fn init() {
+    init_to(Target::Stderr);
+}
+
+/// Log as [`init`] does, writing the lines to `target`
+pub fn init_to(target: Target) {
+    let _ = env_logger::Builder::from_env(env_logger::Env::new().default_filter_or("info"))
+        .format(|buf, record| writeln!(buf, "{}", json_line(record)))
+        .target(target)
+        .try_init();
+}
+
+/// Log `message` along with `fields`, which are kept as top level keys of the log line
+pub fn event(level: Level, message: &str, fields: Value) {
+    let mut line = match fields {
+        Value::Object(fields) => fields,
+        _ => Map::new(),
+    };
+    line.insert("message".to_string(), message.into());
+    line.retain(|_, value| !value.is_null());
+    log::log!(target: EVENT_TARGET, level, "{}", Value::Object(line));
+}
+
+/// The log line written for `record`
+pub fn json_line(record: &Record) -> String {
+    let mut line = Map::new();
+    line.insert(
+        "timestamp".to_string(),
+        Utc::now()
+            .to_rfc3339_opts(SecondsFormat::Millis, true)
+            .into(),
+    );
+    line.insert("level".to_string(), record.level().as_str().into());
+    let message = record.args().to_string();
+    match serde_json::from_str::<Value>(&message) {
+        Ok(Value::Object(fields)) if record.target() == EVENT_TARGET => line.extend(fields),
+        _ => {
+            line.insert("target".to_string(), record.target().into());
+            line.insert("message".to_string(), message.into());
+        }
+    }
+    Value::Object(line).to_string()
+}
@@ -0,0 +1,149 @@
+use crate::{
+    logging::json,
+    security::{acting_user, has_api_key},
+};
+use actix_web::{
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    http::header::{HeaderName, HeaderValue},
+    HttpMessage, HttpResponse,
+};
+use futures::future::LocalBoxFuture;
+use log::Level;
+use serde_json::json;
+use std::{
+    fmt::Display,
+    future::{self, Ready},
+    time::Instant,
+};
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Id of the request being served, taken from `X-Request-Id` or generated
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestId(pub String);
+
+/// Error behind a `500 Internal Server Error`, kept on the response to be logged
+#[derive(Debug, Clone)]
+struct ErrorContext {
+    /// What the handler was doing when it failed
+    context: &'static str,
+    error: String,
+}
+
+/// Respond with `500 Internal Server Error` for `e`, which is logged along with the request and
+/// `context`, such as `"could not fetch games"`
+pub fn internal_error(context: &'static str, e: impl Display) -> HttpResponse {
+    let error = e.to_string();
+    let mut response = HttpResponse::InternalServerError().body(error.clone());
+    response
+        .extensions_mut()
+        .insert(ErrorContext { context, error });
+    response
+}
+
+/// Ids passed in by clients are kept when short and printable, so they can't forge log lines
+fn valid_request_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 128 && id.chars().all(|c| c.is_ascii_graphic())
+}
+
+/// Who a request was made by, as far as the API can tell
+fn actor(req: &ServiceRequest) -> String {
+    match acting_user(req.request()) {
+        Some(user) => user.to_string(),
+        None if has_api_key(req.request()) => "frontend".to_string(),
+        None => "anonymous".to_string(),
+    }
+}
+
+/// Tags every request with an id, echoed in `X-Request-Id`, and logs it once served
+pub struct RequestLog;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestLog
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Transform = RequestLogMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        future::ready(Ok(RequestLogMiddleware { service }))
+    }
+}
+
+pub struct RequestLogMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestLogMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, actix_web::Error>>;
+
+    fn poll_ready(
+        &self,
+        ctx: &mut core::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let started = Instant::now();
+        let id = req
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|id| id.to_str().ok())
+            .filter(|id| valid_request_id(id))
+            .map_or(Uuid::new_v4().to_string(), str::to_string);
+        req.extensions_mut().insert(RequestId(id.clone()));
+        let method = req.method().to_string();
+        let path = req.path().to_string();
+        let actor = actor(&req);
+        let future = self.service.call(req);
+
+        Box::pin(async move {
+            let mut response = future.await?;
+            if let Ok(value) = HeaderValue::from_str(&id) {
+                response
+                    .headers_mut()
+                    .insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+            }
+            let status = response.status();
+            let error = response
+                .response()
+                .extensions()
+                .get::<ErrorContext>()
+                .cloned();
+            let level = match status.as_u16() {
+                500.. => Level::Error,
+                _ => Level::Info,
+            };
+            json::event(
+                level,
+                "request served",
+                json!({
+                    "request_id": id,
+                    "method": method,
+                    "path": path,
+                    "route": response.request().match_pattern(),
+                    "status": status.as_u16(),
+                    "latency_ms": started.elapsed().as_secs_f64() * 1000.0,
+                    "actor": actor,
+                    "error": error.as_ref().map(|error| &error.error),
+                    "error_context": error.as_ref().map(|error| error.context),
+                }),
+            );
+            Ok(response)
+        })
+    }
+}
@@ -0,0 +1,4 @@
+pub mod json;
+pub mod middleware;
+#[cfg(test)]
+pub mod tests;
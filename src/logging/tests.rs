@@ -0,0 +1,146 @@
+#[cfg(test)]
+use crate::{
+    app::{configure_app, get_app_data},
+    config::Config,
+    logging::{
+        json::json_line,
+        middleware::{internal_error, RequestLog, REQUEST_ID_HEADER},
+    },
+    tests::logged_lines,
+};
+use actix_web::{web, App};
+use log::{Level, Record};
+use serde_json::Value;
+
+#[actix_web::test]
+async fn test_request_id() {
+    let app_data = get_app_data(Config::load().unwrap()).await.unwrap();
+    let srv = actix_test::start(move || {
+        App::new()
+            .wrap(RequestLog)
+            .configure(configure_app)
+            .app_data(app_data.clone())
+    });
+    let res = srv
+        .get("/api/tags/")
+        .insert_header((REQUEST_ID_HEADER, "lb-1234"))
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.headers().get(REQUEST_ID_HEADER).unwrap(), "lb-1234");
+
+    let res = srv.get("/api/tags/").send().await.unwrap();
+    let id = res
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .unwrap()
+        .to_str()
+        .unwrap();
+    assert!(uuid::Uuid::parse_str(id).is_ok());
+
+    let res = srv
+        .get("/api/tags/")
+        .insert_header((REQUEST_ID_HEADER, "forged\tline"))
+        .send()
+        .await
+        .unwrap();
+    assert_ne!(
+        res.headers().get(REQUEST_ID_HEADER).unwrap(),
+        "forged\tline"
+    );
+}
+
+/// The line logged for the request with `id`
+fn request_line(id: &str) -> Value {
+    logged_lines()
+        .into_iter()
+        .find(|line| line["message"] == "request served" && line["request_id"] == id)
+        .unwrap()
+}
+
+#[actix_web::test]
+async fn test_internal_error() {
+    logged_lines();
+    let srv = actix_test::start(|| {
+        App::new().wrap(RequestLog).route(
+            "/fail",
+            web::get().to(|| async { internal_error("could not test", "connection refused") }),
+        )
+    });
+    let res = srv
+        .get("/fail")
+        .insert_header((REQUEST_ID_HEADER, "internal-error-test"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status().as_u16(), 500);
+    let line = request_line("internal-error-test");
+    assert_eq!(line["level"], "ERROR");
+    assert_eq!(line["status"], 500);
+    assert_eq!(line["error"], "connection refused");
+    assert_eq!(line["error_context"], "could not test");
+}
+
+#[actix_web::test]
+async fn test_request_log_fields() {
+    logged_lines();
+    let app_data = get_app_data(Config::load().unwrap()).await.unwrap();
+    let srv = actix_test::start(move || {
+        App::new()
+            .wrap(RequestLog)
+            .configure(configure_app)
+            .app_data(app_data.clone())
+    });
+    let res = srv
+        .get("/api/tags/")
+        .insert_header((REQUEST_ID_HEADER, "request-log-test"))
+        .insert_header(("frontend_api_key", "TESTING"))
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    let line = request_line("request-log-test");
+    assert!(line["timestamp"].is_string());
+    assert_eq!(line["level"], "INFO");
+    assert_eq!(line["method"], "GET");
+    assert_eq!(line["path"], "/api/tags/");
+    assert_eq!(line["route"], "/api/tags/");
+    assert_eq!(line["status"], 200);
+    assert!(line["latency_ms"].is_f64());
+    assert_eq!(line["actor"], "frontend");
+    assert!(line.get("error").is_none());
+    assert!(line.get("error_context").is_none());
+}
+
+#[test]
+fn test_json_line() {
+    let line = json_line(
+        &Record::builder()
+            .level(Level::Warn)
+            .target("devcade_api_rs::users")
+            .args(format_args!("LDAP lookup of {} failed", "skyz"))
+            .build(),
+    );
+    let line = serde_json::from_str::<Value>(&line).unwrap();
+    assert_eq!(line["level"], "WARN");
+    assert_eq!(line["target"], "devcade_api_rs::users");
+    assert_eq!(line["message"], "LDAP lookup of skyz failed");
+    assert!(line["timestamp"].is_string());
+
+    let line = json_line(
+        &Record::builder()
+            .level(Level::Info)
+            .target("devcade_api_rs::event")
+            .args(format_args!(
+                "{}",
+                r#"{"message":"request served","request_id":"lb-1234","status":200}"#
+            ))
+            .build(),
+    );
+    let line = serde_json::from_str::<Value>(&line).unwrap();
+    assert_eq!(line["message"], "request served");
+    assert_eq!(line["request_id"], "lb-1234");
+    assert_eq!(line["status"], 200);
+    assert!(line.get("target").is_none());
+}
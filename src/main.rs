@@ -1,30 +1,50 @@
-use actix_cors::Cors;
-use actix_web::{http, middleware::Logger, App, HttpServer};
+use actix_multipart::form::MultipartFormConfig;
+use actix_web::{web, App, HttpServer};
 
-use devcade_api_rs::app::{configure_app, get_app_data};
+use devcade_api_rs::{
+    app::{configure_app, get_app_data},
+    config::Config,
+    cors::cors,
+    logging::{json, middleware::RequestLog},
+    metrics::middleware::RecordMetrics,
+    telemetry::middleware::TraceRequests,
+};
 
-use std::env;
+use std::process;
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
-    let app_data = get_app_data().await;
+    json::init();
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            log::error!("{}", e);
+            process::exit(1);
+        }
+    };
+    let listen_address = config.listen_address;
+    let app_data = match get_app_data(config).await {
+        Ok(app_data) => app_data,
+        Err(e) => {
+            log::error!("Could not start the API: {}", e);
+            process::exit(1);
+        }
+    };
     HttpServer::new(move || {
-        let cors = Cors::default()
-            .allowed_origin(&env::var("DOMAIN").unwrap())
-            .allowed_methods(vec!["GET", "POST", "PUT", "DELETE"])
-            .allowed_headers(vec![http::header::AUTHORIZATION, http::header::ACCEPT])
-            .allowed_header(http::header::CONTENT_TYPE)
-            .max_age(3600);
+        let config = &app_data.config;
         App::new()
-            .wrap(cors)
-            .wrap(Logger::new(
-                "%a \"%r\" %s %b \"%{Referer}i\" \"%{User-Agent}i\" %T",
-            ))
+            .wrap(cors(&config.cors))
+            .wrap(TraceRequests)
+            .wrap(RequestLog)
+            // Registered last so it runs first, and times and counts every response the others
+            // send as well
+            .wrap(RecordMetrics)
             .configure(configure_app)
+            .app_data(web::JsonConfig::default().limit(config.json_limit))
+            .app_data(MultipartFormConfig::default().total_limit(config.upload_limit))
             .app_data(app_data.clone())
     })
-    .bind(("0.0.0.0", 8080))?
+    .bind(listen_address)?
     .run()
     .await
 }
@@ -0,0 +1,77 @@
+use crate::models::AppState;
+use actix_web::{
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    web::Data,
+};
+use futures::future::LocalBoxFuture;
+use std::{
+    future::{self, Ready},
+    time::Instant,
+};
+
+/// Records the count and latency of every request by route, wrapped around the whole app
+pub struct RecordMetrics;
+
+impl<S, B> Transform<S, ServiceRequest> for RecordMetrics
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Transform = MetricsMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        future::ready(Ok(MetricsMiddleware { service }))
+    }
+}
+
+pub struct MetricsMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for MetricsMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, actix_web::Error>>;
+
+    fn poll_ready(
+        &self,
+        ctx: &mut core::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let started = Instant::now();
+        let state = req.app_data::<Data<AppState>>().cloned();
+        let method = req.method().to_string();
+        let future = self.service.call(req);
+
+        Box::pin(async move {
+            let response = future.await?;
+            if let Some(state) = state {
+                // Label by route pattern rather than path to keep the number of series bounded
+                let route = response
+                    .request()
+                    .match_pattern()
+                    .unwrap_or("unmatched".to_string());
+                state.metrics.record_request(
+                    &method,
+                    &route,
+                    response.status().as_u16(),
+                    started.elapsed().as_secs_f64(),
+                );
+            }
+            Ok(response)
+        })
+    }
+}
@@ -0,0 +1,5 @@
+pub mod middleware;
+pub mod registry;
+pub mod routes;
+#[cfg(test)]
+pub mod tests;
@@ -0,0 +1,245 @@
+use std::{collections::BTreeMap, fmt::Write, sync::Mutex};
+
+/// Upper bounds of the request latency buckets, in seconds
+const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+/// Upper bounds of the game upload size buckets, in bytes
+const SIZE_BUCKETS: &[f64] = &[
+    1_048_576.0,
+    5_242_880.0,
+    10_485_760.0,
+    26_214_400.0,
+    52_428_800.0,
+    104_857_600.0,
+];
+
+/// Descriptions of every metric, also the order they are exported in
+const HELP: &[(&str, &str)] = &[
+    (
+        "http_requests_total",
+        "Requests served, by route and status",
+    ),
+    (
+        "http_request_duration_seconds",
+        "Time taken to serve requests, by route",
+    ),
+    ("db_pool_connections", "Database connections, by state"),
+    (
+        "db_pool_max_connections",
+        "Most database connections the pool will open",
+    ),
+    (
+        "s3_operations_total",
+        "Object storage operations, by operation",
+    ),
+    (
+        "s3_errors_total",
+        "Object storage operations that failed, by operation",
+    ),
+    (
+        "s3_bytes_total",
+        "Bytes sent to or read from object storage, by operation",
+    ),
+    ("game_uploads_total", "Game binaries uploaded, by result"),
+    (
+        "game_upload_failures_total",
+        "Game binaries turned away, by reason",
+    ),
+    ("game_upload_size_bytes", "Size of accepted game binaries"),
+    ("game_downloads_total", "Game binaries downloaded, by game"),
+];
+
+type Labels = Vec<(&'static str, String)>;
+/// A value read at the time of the scrape, as `(name, labels, value)`
+pub type Gauge<'a> = (&'static str, &'a [(&'static str, &'a str)], f64);
+
+#[derive(Debug, Clone)]
+struct Histogram {
+    buckets: &'static [f64],
+    /// Observations at or below each bucket, not cumulative
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+/// Counters and histograms kept in memory and exported in the Prometheus text format
+#[derive(Debug, Default)]
+pub struct Metrics {
+    counters: Mutex<BTreeMap<&'static str, BTreeMap<Labels, u64>>>,
+    histograms: Mutex<BTreeMap<&'static str, BTreeMap<Labels, Histogram>>>,
+}
+
+fn labels(labels: &[(&'static str, &str)]) -> Labels {
+    labels
+        .iter()
+        .map(|(name, value)| (*name, value.to_string()))
+        .collect()
+}
+
+fn format_labels(labels: &[(&'static str, String)], extra: Option<(&str, &str)>) -> String {
+    let pairs = labels
+        .iter()
+        .map(|(name, value)| (*name, value.as_str()))
+        .chain(extra)
+        .map(|(name, value)| {
+            let value = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{}=\"{}\"", name, value)
+        })
+        .collect::<Vec<_>>();
+    match pairs.is_empty() {
+        true => String::new(),
+        false => format!("{{{}}}", pairs.join(",")),
+    }
+}
+
+impl Metrics {
+    pub fn add(&self, name: &'static str, label_values: &[(&'static str, &str)], by: u64) {
+        *self
+            .counters
+            .lock()
+            .unwrap()
+            .entry(name)
+            .or_default()
+            .entry(labels(label_values))
+            .or_default() += by;
+    }
+
+    pub fn increment(&self, name: &'static str, label_values: &[(&'static str, &str)]) {
+        self.add(name, label_values, 1);
+    }
+
+    pub fn observe(
+        &self,
+        name: &'static str,
+        label_values: &[(&'static str, &str)],
+        buckets: &'static [f64],
+        value: f64,
+    ) {
+        let mut histograms = self.histograms.lock().unwrap();
+        let histogram = histograms
+            .entry(name)
+            .or_default()
+            .entry(labels(label_values))
+            .or_insert_with(|| Histogram {
+                buckets,
+                counts: vec![0; buckets.len()],
+                sum: 0.0,
+                count: 0,
+            });
+        if let Some(bucket) = buckets.iter().position(|bound| value <= *bound) {
+            histogram.counts[bucket] += 1;
+        }
+        histogram.sum += value;
+        histogram.count += 1;
+    }
+
+    pub fn record_request(&self, method: &str, route: &str, status: u16, seconds: f64) {
+        self.increment(
+            "http_requests_total",
+            &[
+                ("method", method),
+                ("route", route),
+                ("status", &status.to_string()),
+            ],
+        );
+        self.observe(
+            "http_request_duration_seconds",
+            &[("method", method), ("route", route)],
+            LATENCY_BUCKETS,
+            seconds,
+        );
+    }
+
+    /// Count an object storage operation that sent or read `bytes`
+    pub fn record_s3(&self, operation: &'static str, succeeded: bool, bytes: u64) {
+        self.increment("s3_operations_total", &[("operation", operation)]);
+        if !succeeded {
+            self.increment("s3_errors_total", &[("operation", operation)]);
+        }
+        self.add("s3_bytes_total", &[("operation", operation)], bytes);
+    }
+
+    pub fn record_upload_accepted(&self, bytes: usize) {
+        self.increment("game_uploads_total", &[("result", "accepted")]);
+        self.observe("game_upload_size_bytes", &[], SIZE_BUCKETS, bytes as f64);
+    }
+
+    pub fn record_upload_rejected(&self, reason: &'static str) {
+        self.increment("game_uploads_total", &[("result", "rejected")]);
+        self.increment("game_upload_failures_total", &[("reason", reason)]);
+    }
+
+    pub fn record_download(&self, game: &str) {
+        self.increment("game_downloads_total", &[("game", game)]);
+    }
+
+    /// Drop the series of a deleted game, so deleted games do not pile up in every scrape
+    pub fn forget_game(&self, game: &str) {
+        if let Some(series) = self
+            .counters
+            .lock()
+            .unwrap()
+            .get_mut("game_downloads_total")
+        {
+            series.remove(&labels(&[("game", game)]));
+        }
+    }
+
+    /// Every metric in the Prometheus text exposition format, along with `gauges`
+    pub fn render(&self, gauges: &[Gauge]) -> String {
+        let counters = self.counters.lock().unwrap().clone();
+        let histograms = self.histograms.lock().unwrap().clone();
+        let mut out = String::new();
+        for (name, help) in HELP {
+            if let Some(series) = counters.get(name) {
+                let _ = writeln!(out, "# HELP {} {}\n# TYPE {} counter", name, help, name);
+                for (labels, value) in series {
+                    let _ = writeln!(out, "{}{} {}", name, format_labels(labels, None), value);
+                }
+            } else if let Some(series) = histograms.get(name) {
+                let _ = writeln!(out, "# HELP {} {}\n# TYPE {} histogram", name, help, name);
+                for (labels, histogram) in series {
+                    let mut cumulative = 0;
+                    for (bound, count) in histogram.buckets.iter().zip(&histogram.counts) {
+                        cumulative += count;
+                        let le = bound.to_string();
+                        let _ = writeln!(
+                            out,
+                            "{}_bucket{} {}",
+                            name,
+                            format_labels(labels, Some(("le", &le))),
+                            cumulative
+                        );
+                    }
+                    let _ = writeln!(
+                        out,
+                        "{}_bucket{} {}",
+                        name,
+                        format_labels(labels, Some(("le", "+Inf"))),
+                        histogram.count
+                    );
+                    let labels = format_labels(labels, None);
+                    let _ = writeln!(out, "{}_sum{} {}", name, labels, histogram.sum);
+                    let _ = writeln!(out, "{}_count{} {}", name, labels, histogram.count);
+                }
+            } else if gauges.iter().any(|(gauge, _, _)| gauge == name) {
+                let _ = writeln!(out, "# HELP {} {}\n# TYPE {} gauge", name, help, name);
+                for (_, label_values, value) in gauges.iter().filter(|(gauge, _, _)| gauge == name)
+                {
+                    let _ = writeln!(
+                        out,
+                        "{}{} {}",
+                        name,
+                        format_labels(&labels(label_values), None),
+                        value
+                    );
+                }
+            }
+        }
+        out
+    }
+}
@@ -0,0 +1,28 @@
+use crate::models::AppState;
+use actix_web::{get, web::Data, HttpResponse, Responder};
+
+#[utoipa::path(
+    context_path = "",
+    responses(
+        (status = 200, description = "Request, database, storage, and upload metrics in the Prometheus text format", content_type = "text/plain"),
+    )
+)]
+#[get("/metrics")]
+pub async fn get_metrics(state: Data<AppState>) -> impl Responder {
+    let in_use = (state.db.size() as usize).saturating_sub(state.db.num_idle());
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(state.metrics.render(&[
+            (
+                "db_pool_connections",
+                &[("state", "idle")],
+                state.db.num_idle() as f64,
+            ),
+            ("db_pool_connections", &[("state", "in_use")], in_use as f64),
+            (
+                "db_pool_max_connections",
+                &[],
+                state.config.db_max_connections as f64,
+            ),
+        ]))
+}
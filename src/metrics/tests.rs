@@ -0,0 +1,64 @@
+#[cfg(test)]
+use crate::{
+    app::{configure_app, get_app_data},
+    config::Config,
+    metrics::{middleware::RecordMetrics, registry::Metrics},
+};
+use actix_web::App;
+
+#[test]
+fn test_render_metrics() {
+    let metrics = Metrics::default();
+    metrics.record_request("GET", "/api/games/{id}", 200, 0.02);
+    metrics.record_request("GET", "/api/games/{id}", 200, 3.0);
+    metrics.record_upload_rejected("missing_publish");
+    metrics.record_download("a \"quoted\" game");
+    let text = metrics.render(&[("db_pool_max_connections", &[], 10.0)]);
+    assert!(text.contains("# TYPE http_requests_total counter"));
+    assert!(text.contains(
+        "http_requests_total{method=\"GET\",route=\"/api/games/{id}\",status=\"200\"} 2"
+    ));
+    assert!(text.contains(
+        "http_request_duration_seconds_bucket{method=\"GET\",route=\"/api/games/{id}\",le=\"0.025\"} 1"
+    ));
+    assert!(text.contains(
+        "http_request_duration_seconds_bucket{method=\"GET\",route=\"/api/games/{id}\",le=\"+Inf\"} 2"
+    ));
+    assert!(text.contains(
+        "http_request_duration_seconds_count{method=\"GET\",route=\"/api/games/{id}\"} 2"
+    ));
+    assert!(text.contains("game_uploads_total{result=\"rejected\"} 1"));
+    assert!(text.contains("game_upload_failures_total{reason=\"missing_publish\"} 1"));
+    assert!(text.contains("game_downloads_total{game=\"a \\\"quoted\\\" game\"} 1"));
+    assert!(text.contains("# TYPE db_pool_max_connections gauge\ndb_pool_max_connections 10"));
+
+    metrics.forget_game("a \"quoted\" game");
+    let text = metrics.render(&[]);
+    assert!(!text.contains("game_downloads_total{"));
+}
+
+#[actix_web::test]
+async fn test_get_metrics() {
+    let app_data = get_app_data(Config::load().unwrap()).await.unwrap();
+    let srv = actix_test::start(move || {
+        App::new()
+            .wrap(RecordMetrics)
+            .configure(configure_app)
+            .app_data(app_data.clone())
+    });
+    let res = srv.get("/api/tags/").send().await.unwrap();
+    assert!(res.status().is_success());
+    let res = srv.get("/nowhere").send().await.unwrap();
+    assert_eq!(res.status().as_u16(), 404);
+    let mut res = srv.get("/metrics").send().await.unwrap();
+    assert!(res.status().is_success());
+    let body = res.body().await.unwrap();
+    let text = std::str::from_utf8(&body).unwrap();
+    assert!(
+        text.contains("http_requests_total{method=\"GET\",route=\"/api/tags/\",status=\"200\"} 1")
+    );
+    assert!(
+        text.contains("http_requests_total{method=\"GET\",route=\"unmatched\",status=\"404\"} 1")
+    );
+    assert!(text.contains("db_pool_connections{state=\"idle\"}"));
+}
@@ -0,0 +1,10 @@
+-- The schema every later migration starts from, created by hand before migrations were recorded
+SELECT
+    'public.collection'::regclass,
+    'public.contains_game'::regclass,
+    'public.game'::regclass,
+    'public.game_tags'::regclass,
+    'public.saves_user'::regclass,
+    'public.tags'::regclass,
+    'public.users'::regclass,
+    'public.UserType'::regtype;
@@ -0,0 +1,266 @@
+-- Everything added to the baseline schema before migrations were recorded. Each step is skipped
+-- when it is already there, since databases were upgraded by hand up to different points.
+
+DO $$ BEGIN
+    CREATE TYPE public.CatalogEventType AS ENUM (
+        'game_created',
+        'game_updated',
+        'game_binary_replaced',
+        'game_deleted',
+        'game_approved',
+        'tag_created',
+        'tag_updated',
+        'tag_deleted'
+    );
+EXCEPTION WHEN duplicate_object THEN NULL;
+END $$;
+
+DO $$ BEGIN
+    CREATE TYPE public.ContributorRole AS ENUM ('owner', 'developer', 'artist', 'audio');
+EXCEPTION WHEN duplicate_object THEN NULL;
+END $$;
+
+DO $$ BEGIN
+    CREATE TYPE public.TagCategory AS ENUM ('genre', 'input', 'player_count', 'status');
+EXCEPTION WHEN duplicate_object THEN NULL;
+END $$;
+
+DO $$ BEGIN
+    CREATE TYPE public.WebhookDeliveryStatus AS ENUM ('pending', 'delivered', 'failed');
+EXCEPTION WHEN duplicate_object THEN NULL;
+END $$;
+
+ALTER TABLE public.game
+    ADD COLUMN IF NOT EXISTS created_at timestamp with time zone DEFAULT now() NOT NULL,
+    ADD COLUMN IF NOT EXISTS updated_at timestamp with time zone DEFAULT now() NOT NULL,
+    ADD COLUMN IF NOT EXISTS binary_updated_at timestamp with time zone DEFAULT now() NOT NULL,
+    ADD COLUMN IF NOT EXISTS banner_updated_at timestamp with time zone DEFAULT now() NOT NULL,
+    ADD COLUMN IF NOT EXISTS icon_updated_at timestamp with time zone DEFAULT now() NOT NULL,
+    ADD COLUMN IF NOT EXISTS version character varying(32),
+    ADD COLUMN IF NOT EXISTS entrypoint character varying(255),
+    ADD COLUMN IF NOT EXISTS min_client_version character varying(32),
+    ADD COLUMN IF NOT EXISTS min_players smallint,
+    ADD COLUMN IF NOT EXISTS max_players smallint,
+    ADD COLUMN IF NOT EXISTS controls character varying(32)[] DEFAULT '{}' NOT NULL,
+    ADD COLUMN IF NOT EXISTS session_minutes smallint,
+    ADD COLUMN IF NOT EXISTS credits character varying(128)[] DEFAULT '{}' NOT NULL,
+    ADD COLUMN IF NOT EXISTS repo_url character varying(255),
+    ADD COLUMN IF NOT EXISTS license character varying(64),
+    ADD COLUMN IF NOT EXISTS content_warnings character varying(64)[] DEFAULT '{}' NOT NULL;
+
+ALTER TABLE public.tags
+    ADD COLUMN IF NOT EXISTS category TagCategory,
+    ADD COLUMN IF NOT EXISTS parent character varying(32),
+    ADD COLUMN IF NOT EXISTS display_order integer DEFAULT 0 NOT NULL,
+    ADD COLUMN IF NOT EXISTS color character varying(7),
+    ADD COLUMN IF NOT EXISTS icon character varying(255),
+    ADD COLUMN IF NOT EXISTS updated_at timestamp with time zone DEFAULT now() NOT NULL;
+
+ALTER TABLE public.users
+    ADD COLUMN IF NOT EXISTS updated_at timestamp with time zone DEFAULT now() NOT NULL;
+
+DO $$ BEGIN
+    CREATE TYPE public.GameContributor AS (
+        contributor public.users,
+        role public.ContributorRole
+    );
+EXCEPTION WHEN duplicate_object THEN NULL;
+END $$;
+
+CREATE TABLE IF NOT EXISTS public.catalog_events (
+    id bigserial NOT NULL,
+    event_type CatalogEventType NOT NULL,
+    game_id character varying(36),
+    tag_name character varying(32),
+    created_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS public.game_tombstones (
+    id character(36) NOT NULL,
+    deleted_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS public.game_contributors (
+    game_id character(36) NOT NULL,
+    user_id character varying(32) NOT NULL,
+    role ContributorRole NOT NULL,
+    created_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS public.tag_aliases (
+    alias character varying(32) NOT NULL,
+    tag_name character varying(32) NOT NULL,
+    created_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS public.play_sessions (
+    id character(36) NOT NULL,
+    game_id character(36) NOT NULL,
+    cabinet_id character varying(64) NOT NULL,
+    user_id character varying(32),
+    started_at timestamp with time zone DEFAULT now() NOT NULL,
+    ended_at timestamp with time zone,
+    duration_seconds integer
+);
+
+CREATE TABLE IF NOT EXISTS public.scores (
+    id bigserial NOT NULL,
+    game_id character(36) NOT NULL,
+    user_id character varying(32) NOT NULL,
+    board character varying(32) DEFAULT 'default' NOT NULL,
+    score bigint NOT NULL,
+    nonce character varying(64) NOT NULL,
+    created_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+-- Scores recorded before submissions carried a nonce get a random one, so none of them collide
+ALTER TABLE public.scores
+    ADD COLUMN IF NOT EXISTS nonce character varying(64) DEFAULT md5(random()::text) NOT NULL;
+ALTER TABLE public.scores ALTER COLUMN nonce DROP DEFAULT;
+
+CREATE TABLE IF NOT EXISTS public.score_secrets (
+    game_id character(36) NOT NULL,
+    secret character varying(255) NOT NULL,
+    created_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS public.achievements (
+    game_id character(36) NOT NULL,
+    id character varying(64) NOT NULL,
+    title character varying(128) NOT NULL,
+    description character varying(500) NOT NULL,
+    icon character varying(255),
+    hidden boolean DEFAULT false NOT NULL,
+    created_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS public.achievement_unlocks (
+    game_id character(36) NOT NULL,
+    achievement_id character varying(64) NOT NULL,
+    user_id character varying(32) NOT NULL,
+    unlocked_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS public.reviews (
+    game_id character(36) NOT NULL,
+    user_id character varying(32) NOT NULL,
+    rating smallint NOT NULL CHECK (rating BETWEEN 1 AND 5),
+    review character varying(2000),
+    hidden boolean DEFAULT false NOT NULL,
+    created_at timestamp with time zone DEFAULT now() NOT NULL,
+    updated_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS public.webhooks (
+    id serial NOT NULL,
+    url character varying(2048) NOT NULL,
+    secret text NOT NULL,
+    event_types CatalogEventType[] NOT NULL,
+    active boolean DEFAULT true NOT NULL,
+    created_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS public.webhook_deliveries (
+    id bigserial NOT NULL,
+    webhook_id integer NOT NULL,
+    event_id bigint NOT NULL,
+    status WebhookDeliveryStatus DEFAULT 'pending' NOT NULL,
+    attempts integer DEFAULT 0 NOT NULL,
+    next_attempt_at timestamp with time zone DEFAULT now() NOT NULL,
+    last_status_code integer,
+    last_error text,
+    delivered_at timestamp with time zone,
+    created_at timestamp with time zone DEFAULT now() NOT NULL
+);
+
+CREATE OR REPLACE FUNCTION public.notify_catalog_event() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify('catalog_events', row_to_json(NEW)::text);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS catalog_events_notify ON public.catalog_events;
+CREATE TRIGGER catalog_events_notify AFTER INSERT ON public.catalog_events
+    FOR EACH ROW EXECUTE FUNCTION public.notify_catalog_event();
+
+CREATE OR REPLACE FUNCTION public.publish_catalog_event(new_event_type CatalogEventType, new_game_id text, new_tag_name text) RETURNS void AS $$
+BEGIN
+    WITH event AS (
+        INSERT INTO public.catalog_events (event_type, game_id, tag_name)
+        VALUES (new_event_type, new_game_id, new_tag_name)
+        RETURNING id, event_type
+    )
+    INSERT INTO public.webhook_deliveries (webhook_id, event_id)
+    SELECT webhooks.id, event.id FROM public.webhooks, event
+    WHERE webhooks.active AND event.event_type = ANY(webhooks.event_types);
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION public.publish_game_approved() RETURNS trigger AS $$
+BEGIN
+    -- Checked at commit, so replacing every tag of a game or deleting it is not an approval
+    IF EXISTS (SELECT 1 FROM public.game WHERE id = OLD.game_id)
+        AND NOT EXISTS (
+            SELECT 1 FROM public.game_tags WHERE game_id = OLD.game_id AND tag_name = 'hidden'
+        ) THEN
+        PERFORM public.publish_catalog_event('game_approved', OLD.game_id, NULL);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS game_tags_approved ON public.game_tags;
+CREATE CONSTRAINT TRIGGER game_tags_approved AFTER DELETE ON public.game_tags
+    DEFERRABLE INITIALLY DEFERRED
+    FOR EACH ROW WHEN (OLD.tag_name = 'hidden') EXECUTE FUNCTION public.publish_game_approved();
+
+CREATE FUNCTION pg_temp.add_constraint(target regclass, name text, definition text) RETURNS void AS $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_constraint WHERE conrelid = target AND conname = name) THEN
+        EXECUTE format('ALTER TABLE ONLY %s ADD CONSTRAINT %I %s', target, name, definition);
+    END IF;
+END;
+$$ LANGUAGE plpgsql;
+
+SELECT pg_temp.add_constraint('public.catalog_events', 'catalog_events_pk', 'PRIMARY KEY (id)');
+SELECT pg_temp.add_constraint('public.game_tombstones', 'game_tombstones_pk', 'PRIMARY KEY (id)');
+SELECT pg_temp.add_constraint('public.play_sessions', 'play_sessions_pk', 'PRIMARY KEY (id)');
+SELECT pg_temp.add_constraint('public.scores', 'scores_pk', 'PRIMARY KEY (id)');
+SELECT pg_temp.add_constraint('public.scores', 'scores_nonce_key', 'UNIQUE (game_id, nonce)');
+SELECT pg_temp.add_constraint('public.score_secrets', 'score_secrets_pk', 'PRIMARY KEY (game_id)');
+SELECT pg_temp.add_constraint('public.achievements', 'achievements_pk', 'PRIMARY KEY (game_id, id)');
+SELECT pg_temp.add_constraint('public.achievement_unlocks', 'achievement_unlocks_pk', 'PRIMARY KEY (game_id, achievement_id, user_id)');
+SELECT pg_temp.add_constraint('public.reviews', 'reviews_pk', 'PRIMARY KEY (game_id, user_id)');
+SELECT pg_temp.add_constraint('public.game_contributors', 'game_contributors_pk', 'PRIMARY KEY (game_id, user_id)');
+SELECT pg_temp.add_constraint('public.tag_aliases', 'tag_aliases_pk', 'PRIMARY KEY (alias)');
+SELECT pg_temp.add_constraint('public.webhooks', 'webhooks_pk', 'PRIMARY KEY (id)');
+SELECT pg_temp.add_constraint('public.webhook_deliveries', 'webhook_deliveries_pk', 'PRIMARY KEY (id)');
+
+SELECT pg_temp.add_constraint('public.play_sessions', 'play_sessions_game_fk', 'FOREIGN KEY (game_id) REFERENCES public.game(id) ON UPDATE CASCADE ON DELETE CASCADE');
+SELECT pg_temp.add_constraint('public.play_sessions', 'play_sessions_user_fk', 'FOREIGN KEY (user_id) REFERENCES public.users(id) ON UPDATE CASCADE ON DELETE SET NULL');
+SELECT pg_temp.add_constraint('public.scores', 'scores_game_fk', 'FOREIGN KEY (game_id) REFERENCES public.game(id) ON UPDATE CASCADE ON DELETE CASCADE');
+SELECT pg_temp.add_constraint('public.scores', 'scores_user_fk', 'FOREIGN KEY (user_id) REFERENCES public.users(id) ON UPDATE CASCADE ON DELETE CASCADE');
+SELECT pg_temp.add_constraint('public.score_secrets', 'score_secrets_game_fk', 'FOREIGN KEY (game_id) REFERENCES public.game(id) ON UPDATE CASCADE ON DELETE CASCADE');
+SELECT pg_temp.add_constraint('public.achievements', 'achievements_game_fk', 'FOREIGN KEY (game_id) REFERENCES public.game(id) ON UPDATE CASCADE ON DELETE CASCADE');
+SELECT pg_temp.add_constraint('public.achievement_unlocks', 'achievement_unlocks_achievement_fk', 'FOREIGN KEY (game_id, achievement_id) REFERENCES public.achievements(game_id, id) ON UPDATE CASCADE ON DELETE CASCADE');
+SELECT pg_temp.add_constraint('public.achievement_unlocks', 'achievement_unlocks_user_fk', 'FOREIGN KEY (user_id) REFERENCES public.users(id) ON UPDATE CASCADE ON DELETE CASCADE');
+SELECT pg_temp.add_constraint('public.reviews', 'reviews_game_fk', 'FOREIGN KEY (game_id) REFERENCES public.game(id) ON UPDATE CASCADE ON DELETE CASCADE');
+SELECT pg_temp.add_constraint('public.reviews', 'reviews_user_fk', 'FOREIGN KEY (user_id) REFERENCES public.users(id) ON UPDATE CASCADE ON DELETE CASCADE');
+SELECT pg_temp.add_constraint('public.game_contributors', 'game_contributors_game_fk', 'FOREIGN KEY (game_id) REFERENCES public.game(id) ON UPDATE CASCADE ON DELETE CASCADE');
+SELECT pg_temp.add_constraint('public.game_contributors', 'game_contributors_user_fk', 'FOREIGN KEY (user_id) REFERENCES public.users(id) ON UPDATE CASCADE ON DELETE CASCADE');
+SELECT pg_temp.add_constraint('public.tag_aliases', 'tag_aliases_tag_fk', 'FOREIGN KEY (tag_name) REFERENCES public.tags(name) ON UPDATE CASCADE ON DELETE CASCADE');
+SELECT pg_temp.add_constraint('public.webhook_deliveries', 'webhook_deliveries_webhook_fk', 'FOREIGN KEY (webhook_id) REFERENCES public.webhooks(id) ON UPDATE CASCADE ON DELETE CASCADE');
+SELECT pg_temp.add_constraint('public.webhook_deliveries', 'webhook_deliveries_event_fk', 'FOREIGN KEY (event_id) REFERENCES public.catalog_events(id) ON UPDATE CASCADE ON DELETE CASCADE');
+SELECT pg_temp.add_constraint('public.tags', 'tags_parent_fk', 'FOREIGN KEY (parent) REFERENCES public.tags(name) ON UPDATE CASCADE ON DELETE SET NULL');
+
+DROP FUNCTION pg_temp.add_constraint(regclass, text, text);
+
+CREATE INDEX IF NOT EXISTS play_sessions_game_idx ON public.play_sessions (game_id, started_at);
+CREATE INDEX IF NOT EXISTS scores_board_idx ON public.scores (game_id, board, score DESC);
+CREATE INDEX IF NOT EXISTS webhook_deliveries_pending_idx ON public.webhook_deliveries (next_attempt_at) WHERE status = 'pending';
+
+-- Games uploaded before contributors were tracked are owned by their author
+INSERT INTO public.game_contributors (game_id, user_id, role)
+SELECT id, author, 'owner' FROM public.game
+ON CONFLICT DO NOTHING;
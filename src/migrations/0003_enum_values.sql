@@ -0,0 +1,20 @@
+-- Values added to types that already existed, which Postgres refuses to do inside a transaction
+ALTER TYPE public.CatalogEventType ADD VALUE IF NOT EXISTS 'game_created';
+ALTER TYPE public.CatalogEventType ADD VALUE IF NOT EXISTS 'game_updated';
+ALTER TYPE public.CatalogEventType ADD VALUE IF NOT EXISTS 'game_binary_replaced';
+ALTER TYPE public.CatalogEventType ADD VALUE IF NOT EXISTS 'game_deleted';
+ALTER TYPE public.CatalogEventType ADD VALUE IF NOT EXISTS 'game_approved';
+ALTER TYPE public.CatalogEventType ADD VALUE IF NOT EXISTS 'tag_created';
+ALTER TYPE public.CatalogEventType ADD VALUE IF NOT EXISTS 'tag_updated';
+ALTER TYPE public.CatalogEventType ADD VALUE IF NOT EXISTS 'tag_deleted';
+ALTER TYPE public.ContributorRole ADD VALUE IF NOT EXISTS 'owner';
+ALTER TYPE public.ContributorRole ADD VALUE IF NOT EXISTS 'developer';
+ALTER TYPE public.ContributorRole ADD VALUE IF NOT EXISTS 'artist';
+ALTER TYPE public.ContributorRole ADD VALUE IF NOT EXISTS 'audio';
+ALTER TYPE public.TagCategory ADD VALUE IF NOT EXISTS 'genre';
+ALTER TYPE public.TagCategory ADD VALUE IF NOT EXISTS 'input';
+ALTER TYPE public.TagCategory ADD VALUE IF NOT EXISTS 'player_count';
+ALTER TYPE public.TagCategory ADD VALUE IF NOT EXISTS 'status';
+ALTER TYPE public.WebhookDeliveryStatus ADD VALUE IF NOT EXISTS 'pending';
+ALTER TYPE public.WebhookDeliveryStatus ADD VALUE IF NOT EXISTS 'delivered';
+ALTER TYPE public.WebhookDeliveryStatus ADD VALUE IF NOT EXISTS 'failed';
@@ -0,0 +1,7 @@
+-- The tags the API gives a meaning to. A tag already using one of these names keeps its
+-- description, and is filed under status like the others.
+INSERT INTO public.tags (name, description, category) VALUES
+('authrequired', 'Required CSH Authentication to Access', 'status'),
+('hidden', 'Hidden from public game listings', 'status'),
+('featured', 'Featured on the cabinet', 'status')
+ON CONFLICT (name) DO UPDATE SET category = EXCLUDED.category;
@@ -0,0 +1,56 @@
+-- Every change to a game, recorded by triggers in the transaction making it. The changes feed
+-- hands out the oldest transaction still in flight as its sync token, so a change committed
+-- after a sync is never behind the token that sync returned.
+CREATE TABLE public.game_changes (
+    id bigserial NOT NULL,
+    game_id character(36) NOT NULL,
+    added boolean DEFAULT false NOT NULL,
+    transaction_id bigint DEFAULT pg_current_xact_id()::text::bigint NOT NULL,
+    changed_at timestamp with time zone DEFAULT now() NOT NULL,
+    CONSTRAINT game_changes_pk PRIMARY KEY (id)
+);
+
+ALTER TABLE public.game_changes OWNER TO devcade;
+
+CREATE INDEX game_changes_transaction_idx ON public.game_changes (transaction_id);
+
+CREATE FUNCTION public.record_game_change() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        INSERT INTO public.game_changes (game_id) VALUES (OLD.id);
+    ELSE
+        INSERT INTO public.game_changes (game_id, added) VALUES (NEW.id, TG_OP = 'INSERT');
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+ALTER FUNCTION public.record_game_change() OWNER TO devcade;
+
+CREATE TRIGGER game_changes_record AFTER INSERT OR UPDATE OR DELETE ON public.game
+    FOR EACH ROW EXECUTE FUNCTION public.record_game_change();
+
+-- Hiding a game removes it from the feed, and unhiding it adds it back
+CREATE FUNCTION public.record_game_visibility_change() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        INSERT INTO public.game_changes (game_id, added) VALUES (OLD.game_id, true);
+    ELSE
+        INSERT INTO public.game_changes (game_id) VALUES (NEW.game_id);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+ALTER FUNCTION public.record_game_visibility_change() OWNER TO devcade;
+
+CREATE TRIGGER game_tags_hidden_record AFTER INSERT ON public.game_tags
+    FOR EACH ROW WHEN (NEW.tag_name = 'hidden') EXECUTE FUNCTION public.record_game_visibility_change();
+
+CREATE TRIGGER game_tags_unhidden_record AFTER DELETE ON public.game_tags
+    FOR EACH ROW WHEN (OLD.tag_name = 'hidden') EXECUTE FUNCTION public.record_game_visibility_change();
+
+INSERT INTO public.game_changes (game_id, added, changed_at)
+SELECT id, true, created_at FROM public.game
+UNION ALL
+SELECT id, false, deleted_at FROM public.game_tombstones;
@@ -0,0 +1,3 @@
+pub mod runner;
+#[cfg(test)]
+pub mod tests;
@@ -0,0 +1,121 @@
+use sqlx::{query, query_scalar, Connection, Executor, PgConnection, Pool, Postgres};
+
+/// Key of the advisory lock held while migrating, so replicas starting together apply each
+/// migration once
+const LOCK_KEY: i64 = 0x0064_6576_6361_6465;
+
+pub struct Migration {
+    pub version: i64,
+    pub description: &'static str,
+    sql: &'static str,
+    /// Whether the migration runs in a transaction. Statements Postgres refuses to run in one,
+    /// like `ALTER TYPE ... ADD VALUE`, are run one at a time instead.
+    transactional: bool,
+}
+
+/// Every migration in the order it is applied. `TESTING/create_db.sql` creates the schema they
+/// end at and records all of their versions.
+pub const MIGRATIONS: [Migration; 5] = [
+    Migration {
+        version: 1,
+        description: "Baseline schema",
+        sql: include_str!("0001_baseline.sql"),
+        transactional: true,
+    },
+    Migration {
+        version: 2,
+        description:
+            "Catalog, contributors, play stats, scores, achievements, reviews and webhooks",
+        sql: include_str!("0002_catalog.sql"),
+        transactional: true,
+    },
+    Migration {
+        version: 3,
+        description: "Enum values",
+        sql: include_str!("0003_enum_values.sql"),
+        transactional: false,
+    },
+    Migration {
+        version: 4,
+        description: "System tags",
+        sql: include_str!("0004_system_tags.sql"),
+        transactional: true,
+    },
+    Migration {
+        version: 5,
+        description: "Game change log",
+        sql: include_str!("0005_game_changes.sql"),
+        transactional: true,
+    },
+];
+
+/// Apply every migration not yet recorded in `schema_migrations`
+pub async fn run(db: &Pool<Postgres>) -> Result<(), sqlx::Error> {
+    let mut conn = db.acquire().await?;
+    query("SELECT pg_advisory_lock($1)")
+        .bind(LOCK_KEY)
+        .execute(&mut conn)
+        .await?;
+    let migrated = apply(&mut conn).await;
+    query("SELECT pg_advisory_unlock($1)")
+        .bind(LOCK_KEY)
+        .execute(&mut conn)
+        .await?;
+    migrated
+}
+
+async fn apply(conn: &mut PgConnection) -> Result<(), sqlx::Error> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS public.schema_migrations (
+            version bigint NOT NULL PRIMARY KEY,
+            description text NOT NULL,
+            applied_at timestamp with time zone DEFAULT now() NOT NULL
+        )",
+    )
+    .await?;
+    let applied: Vec<i64> = query_scalar("SELECT version FROM schema_migrations")
+        .fetch_all(&mut *conn)
+        .await?;
+    for migration in MIGRATIONS
+        .iter()
+        .filter(|migration| !applied.contains(&migration.version))
+    {
+        log::info!(
+            "Applying migration {}: {}",
+            migration.version,
+            migration.description
+        );
+        if migration.transactional {
+            let mut transaction = conn.begin().await?;
+            transaction.execute(migration.sql).await?;
+            record(&mut transaction, migration).await?;
+            transaction.commit().await?;
+        } else {
+            for statement in statements(migration.sql) {
+                conn.execute(statement).await?;
+            }
+            record(conn, migration).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn record(conn: &mut PgConnection, migration: &Migration) -> Result<(), sqlx::Error> {
+    query("INSERT INTO schema_migrations (version, description) VALUES ($1, $2)")
+        .bind(migration.version)
+        .bind(migration.description)
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+/// The statements of a migration run outside a transaction, split at every `;` ending a line,
+/// without the ones that are only comments. Such migrations hold no function bodies or `DO`
+/// blocks, whose own statements would be split as well.
+pub fn statements(sql: &str) -> impl Iterator<Item = &str> {
+    sql.split(";\n").filter(|statement| {
+        statement
+            .lines()
+            .any(|line| !line.trim().is_empty() && !line.trim_start().starts_with("--"))
+    })
+}
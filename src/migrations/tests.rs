@@ -0,0 +1,29 @@
+use crate::{
+    app::get_app_data,
+    config::Config,
+    migrations::runner::{self, statements, MIGRATIONS},
+};
+use sqlx::query_scalar;
+
+#[test]
+fn test_statements() {
+    let sql = "-- A comment\nSELECT 1;\n-- Another comment\n;\nSELECT 2;\n";
+    let statements: Vec<&str> = statements(sql).collect();
+    assert_eq!(statements, vec!["-- A comment\nSELECT 1", "SELECT 2"]);
+}
+
+#[actix_web::test]
+async fn test_migrations_recorded() {
+    let app_data = get_app_data(Config::load().unwrap()).await.unwrap();
+    // Already applied, so running them again changes nothing
+    runner::run(&app_data.db).await.unwrap();
+    let versions: Vec<i64> = query_scalar("SELECT version FROM schema_migrations ORDER BY version")
+        .fetch_all(&app_data.db)
+        .await
+        .unwrap();
+    let expected: Vec<i64> = MIGRATIONS
+        .iter()
+        .map(|migration| migration.version)
+        .collect();
+    assert_eq!(versions, expected);
+}
@@ -1,12 +1,28 @@
+use crate::{
+    config::Config,
+    metrics::registry::Metrics,
+    validate::{Validate, ValidationErrors},
+};
 use aws_sdk_s3::Client;
+use lazy_static::lazy_static;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use sqlx::{
-    postgres::{PgHasArrayType, PgTypeInfo},
-    types::chrono::NaiveDate,
-    FromRow, Pool, Postgres,
+    error::BoxDynError,
+    postgres::{types::PgRecordDecoder, PgHasArrayType, PgTypeInfo, PgValueRef},
+    types::chrono::{DateTime, NaiveDate, Utc},
+    Decode, FromRow, Pool, Postgres,
 };
+use tokio::sync::broadcast::Sender;
 use utoipa::{self, ToSchema};
 
+lazy_static! {
+    /// Tags are listed comma separated and used in paths
+    static ref TAG_NAME: Regex = Regex::new(r"^[^,/\s]([^,/]*[^,/\s])?$").unwrap();
+    static ref HEX_COLOR: Regex = Regex::new(r"^#[0-9a-fA-F]{6}$").unwrap();
+    static ref USER_ID: Regex = Regex::new(r"^[A-Za-z0-9._-]+$").unwrap();
+}
+
 #[derive(Serialize, Deserialize, FromRow, ToSchema, Clone, PartialEq)]
 pub struct Game {
     #[schema(example = "a1c6cef6-d987-4225-8bc4-def387e8b5bf")]
@@ -21,6 +37,33 @@ pub struct Game {
     pub hash: String,
     #[schema(example = "Break bricks, get points")]
     pub description: String,
+    #[schema(example = "1.2.0")]
+    pub version: Option<String>,
+    #[schema(example = "publish/BrickBreaker")]
+    pub entrypoint: Option<String>,
+    #[schema(example = "2.0.0")]
+    pub min_client_version: Option<String>,
+    #[schema(example = 1)]
+    pub min_players: Option<i16>,
+    #[schema(example = 2)]
+    pub max_players: Option<i16>,
+    #[serde(default)]
+    #[schema(example = json!(["joystick", "buttons"]))]
+    pub controls: Vec<String>,
+    /// Expected length of a play session in minutes
+    #[schema(example = 5)]
+    pub session_minutes: Option<i16>,
+    /// People credited for the game besides its author
+    #[serde(default)]
+    #[schema(example = json!(["Ella - Art", "Skyz - Music"]))]
+    pub credits: Vec<String>,
+    #[schema(example = "https://github.com/ComputerScienceHouse/BrickBreaker")]
+    pub repo_url: Option<String>,
+    #[schema(example = "MIT")]
+    pub license: Option<String>,
+    #[serde(default)]
+    #[schema(example = json!(["flashing lights"]))]
+    pub content_warnings: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, FromRow, ToSchema, Clone, PartialEq, Debug)]
@@ -37,6 +80,30 @@ pub struct GameWithTags {
     pub hash: String,
     #[schema(example = "Huh")]
     pub description: String,
+    #[schema(example = "1.2.0")]
+    pub version: Option<String>,
+    #[schema(example = "publish/BrickBreaker")]
+    pub entrypoint: Option<String>,
+    #[schema(example = "2.0.0")]
+    pub min_client_version: Option<String>,
+    #[schema(example = 1)]
+    pub min_players: Option<i16>,
+    #[schema(example = 2)]
+    pub max_players: Option<i16>,
+    #[schema(example = json!(["joystick", "buttons"]))]
+    pub controls: Vec<String>,
+    /// Expected length of a play session in minutes
+    #[schema(example = 5)]
+    pub session_minutes: Option<i16>,
+    /// People credited for the game besides its author
+    #[schema(example = json!(["Ella - Art", "Skyz - Music"]))]
+    pub credits: Vec<String>,
+    #[schema(example = "https://github.com/ComputerScienceHouse/BrickBreaker")]
+    pub repo_url: Option<String>,
+    #[schema(example = "MIT")]
+    pub license: Option<String>,
+    #[schema(example = json!(["flashing lights"]))]
+    pub content_warnings: Vec<String>,
     #[schema(
         example = "[{\"name\": \"authrequired\", \"description\": \"Required CSH Authentication to Access\"}]"
     )]
@@ -51,6 +118,13 @@ pub struct GameWithTags {
             \"email\": \"wam2134@g.rit.edu\"
         }")]
     pub user: User,
+    /// Everyone credited with a role on the game, owners first
+    pub contributors: Vec<Contributor>,
+    /// Average of the game's visible ratings, absent until the game is rated
+    #[schema(example = 4.5)]
+    pub rating_average: Option<f64>,
+    #[schema(example = 12)]
+    pub rating_count: i64,
 }
 
 impl GameWithTags {
@@ -62,18 +136,123 @@ impl GameWithTags {
             name: game.name.clone(),
             hash: game.hash.clone(),
             description: game.description,
+            version: game.version,
+            entrypoint: game.entrypoint,
+            min_client_version: game.min_client_version,
+            min_players: game.min_players,
+            max_players: game.max_players,
+            controls: game.controls,
+            session_minutes: game.session_minutes,
+            credits: game.credits,
+            repo_url: game.repo_url,
+            license: game.license,
+            content_warnings: game.content_warnings,
             tags,
+            contributors: vec![Contributor {
+                user: user.clone(),
+                role: ContributorRole::Owner,
+            }],
             user,
+            rating_average: None,
+            rating_count: 0,
         }
     }
 }
 
-#[derive(sqlx::Type, Serialize, Deserialize, FromRow, ToSchema, Clone, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone, PartialEq, Debug)]
+pub struct GameChange {
+    #[schema(example = "a1c6cef6-d987-4225-8bc4-def387e8b5bf")]
+    pub id: String,
+    #[schema(example = "BrickBreaker")]
+    pub name: String,
+    #[schema(example = "kisQdebh0jnh6rb+bqQeM1EAxrg=")]
+    pub hash: String,
+    #[schema(example = "2023-03-20T18:30:00Z")]
+    pub updated_at: DateTime<Utc>,
+    #[schema(example = "2023-03-20T18:30:00Z")]
+    pub binary_updated_at: DateTime<Utc>,
+    #[schema(example = "2023-03-20T18:30:00Z")]
+    pub banner_updated_at: DateTime<Utc>,
+    #[schema(example = "2023-03-20T18:30:00Z")]
+    pub icon_updated_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone, PartialEq, Debug)]
+pub struct GameTombstone {
+    #[schema(example = "a1c6cef6-d987-4225-8bc4-def387e8b5bf")]
+    pub id: String,
+    #[schema(example = "2023-03-20T18:30:00Z")]
+    pub deleted_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Clone, PartialEq, Debug)]
+pub struct GameChanges {
+    /// Sync token the diff was computed from, absent on a full sync
+    #[schema(example = 7401)]
+    pub since: Option<i64>,
+    /// Sync token to pass as `since` on the next sync
+    #[schema(example = 7433)]
+    pub until: i64,
+    pub added: Vec<GameChange>,
+    pub updated: Vec<GameChange>,
+    pub removed: Vec<GameTombstone>,
+}
+
+#[derive(sqlx::Type, Serialize, Deserialize, Clone, Copy, ToSchema, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum TagCategory {
+    Genre,
+    Input,
+    PlayerCount,
+    Status,
+}
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone, Default, PartialEq, Debug)]
 pub struct Tag {
     #[schema(example = "authrequired")]
     pub name: String,
     #[schema(example = "Required CSH Authentication to Access")]
     pub description: String,
+    #[serde(default)]
+    #[schema(example = "genre")]
+    pub category: Option<TagCategory>,
+    /// Broader tag this one sits under, games tagged with it match filters on the parent
+    #[serde(default)]
+    #[schema(example = "action")]
+    pub parent: Option<String>,
+    /// Position of the tag within its category on the cabinet, lowest first
+    #[serde(default)]
+    #[schema(example = 0)]
+    pub display_order: i32,
+    /// Hex color of the tag on the cabinet
+    #[serde(default)]
+    #[schema(example = "#e11c52")]
+    pub color: Option<String>,
+    #[serde(default)]
+    #[schema(example = "gamepad")]
+    pub icon: Option<String>,
+}
+
+impl Validate for Tag {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.length("name", &self.name, 1, 32);
+        errors.pattern(
+            "name",
+            &self.name,
+            &TAG_NAME,
+            "free of commas, slashes, and surrounding spaces",
+        );
+        if let Some(parent) = &self.parent {
+            errors.length("parent", parent, 1, 32);
+        }
+        if let Some(color) = &self.color {
+            errors.pattern("color", color, &HEX_COLOR, "a hex color like #e11c52");
+        }
+        if let Some(icon) = &self.icon {
+            errors.length("icon", icon, 1, 255);
+        }
+    }
 }
 
 impl PgHasArrayType for Tag {
@@ -82,6 +261,52 @@ impl PgHasArrayType for Tag {
     }
 }
 
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone, PartialEq, Debug)]
+pub struct TagWithUsage {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub tag: Tag,
+    /// Number of games carrying the tag
+    #[schema(example = 3)]
+    pub usage_count: i64,
+}
+
+/// Outcome of a bulk tag change, nothing is changed when anything is missing
+#[derive(Serialize, Deserialize, ToSchema, Clone, PartialEq, Debug)]
+pub struct TagAssignment {
+    /// Games or tags that were attached, ones that already were are left out
+    #[schema(example = json!(["a1c6cef6-d987-4225-8bc4-def387e8b5bf"]))]
+    pub added: Vec<String>,
+    /// Games or tags that were detached, ones that already were are left out
+    #[schema(example = json!([]))]
+    pub removed: Vec<String>,
+    /// Games or tags that do not exist
+    #[schema(example = json!([]))]
+    pub missing: Vec<String>,
+}
+
+// Derived composite decoding cannot handle the optional columns of a tag
+impl sqlx::Type<Postgres> for Tag {
+    fn type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("tags")
+    }
+}
+
+impl<'r> Decode<'r, Postgres> for Tag {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        let mut decoder = PgRecordDecoder::new(value)?;
+        Ok(Tag {
+            name: decoder.try_decode()?,
+            description: decoder.try_decode()?,
+            category: decoder.try_decode()?,
+            parent: decoder.try_decode()?,
+            display_order: decoder.try_decode()?,
+            color: decoder.try_decode()?,
+            icon: decoder.try_decode()?,
+        })
+    }
+}
+
 #[derive(sqlx::Type, Serialize, Deserialize, Clone, ToSchema, Debug, PartialEq)]
 pub enum UserType {
     CSH,
@@ -107,6 +332,96 @@ pub struct User {
     pub email: String,
 }
 
+impl PgHasArrayType for User {
+    fn array_type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("_users")
+    }
+}
+
+#[derive(sqlx::Type, Serialize, Deserialize, Clone, Copy, ToSchema, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum ContributorRole {
+    Owner,
+    Developer,
+    Artist,
+    Audio,
+}
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone, PartialEq, Debug)]
+pub struct Contributor {
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub user: User,
+    #[schema(example = "developer")]
+    pub role: ContributorRole,
+}
+
+// Contributors are listed along with a game as `GameContributor` records
+impl sqlx::Type<Postgres> for Contributor {
+    fn type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("gamecontributor")
+    }
+}
+
+impl PgHasArrayType for Contributor {
+    fn array_type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("_gamecontributor")
+    }
+}
+
+impl<'r> Decode<'r, Postgres> for Contributor {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        let mut decoder = PgRecordDecoder::new(value)?;
+        Ok(Contributor {
+            user: decoder.try_decode()?,
+            role: decoder.try_decode()?,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Clone, PartialEq, Debug)]
+pub struct UserPage {
+    pub users: Vec<User>,
+    #[schema(example = 0)]
+    pub page: i64,
+    #[schema(example = 20)]
+    pub per_page: i64,
+    /// Number of users matching the search across all pages
+    #[schema(example = 42)]
+    pub total: i64,
+}
+
+/// User after a login sync, with the CSH groups the directory reported
+#[derive(Serialize, Deserialize, ToSchema, Clone, PartialEq, Debug)]
+pub struct SyncedUser {
+    #[serde(flatten)]
+    pub user: User,
+    #[schema(example = json!(["member", "devcade"]))]
+    pub groups: Vec<String>,
+}
+
+impl Validate for User {
+    fn validate(&self, errors: &mut ValidationErrors) {
+        errors.length("id", &self.id, 1, 32);
+        errors.pattern(
+            "id",
+            &self.id,
+            &USER_ID,
+            "letters, digits, dots, dashes, or underscores",
+        );
+        errors.length("first_name", &self.first_name, 1, 32);
+        errors.length("last_name", &self.last_name, 1, 32);
+        // Users without a picture are shown a placeholder
+        if !self.picture.is_empty() {
+            errors.length("picture", &self.picture, 1, 255);
+            errors.url("picture", &self.picture);
+        }
+        errors.length("email", &self.email, 1, 255);
+        errors.email("email", &self.email);
+    }
+}
+
 impl User {
     pub fn from_csh(username: &str, first_name: &str, last_name: &str, admin: bool) -> User {
         User {
@@ -121,7 +436,284 @@ impl User {
     }
 }
 
+#[derive(sqlx::Type, Serialize, Deserialize, Clone, Copy, ToSchema, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum CatalogEventType {
+    GameCreated,
+    GameUpdated,
+    GameBinaryReplaced,
+    GameDeleted,
+    /// The hidden tag was removed from the game
+    GameApproved,
+    TagCreated,
+    TagUpdated,
+    TagDeleted,
+}
+
+impl CatalogEventType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CatalogEventType::GameCreated => "game_created",
+            CatalogEventType::GameUpdated => "game_updated",
+            CatalogEventType::GameBinaryReplaced => "game_binary_replaced",
+            CatalogEventType::GameDeleted => "game_deleted",
+            CatalogEventType::GameApproved => "game_approved",
+            CatalogEventType::TagCreated => "tag_created",
+            CatalogEventType::TagUpdated => "tag_updated",
+            CatalogEventType::TagDeleted => "tag_deleted",
+        }
+    }
+}
+
+impl PgHasArrayType for CatalogEventType {
+    fn array_type_info() -> PgTypeInfo {
+        PgTypeInfo::with_name("_catalogeventtype")
+    }
+}
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone, PartialEq, Debug)]
+pub struct CatalogEvent {
+    #[schema(example = 42)]
+    pub id: i64,
+    #[schema(example = CatalogEventType::GameUpdated)]
+    pub event_type: CatalogEventType,
+    #[schema(example = "a1c6cef6-d987-4225-8bc4-def387e8b5bf")]
+    pub game_id: Option<String>,
+    #[schema(example = "authrequired")]
+    pub tag_name: Option<String>,
+    #[schema(example = "2023-03-20T18:30:00Z")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone, PartialEq, Debug)]
+pub struct PlaySession {
+    #[schema(example = "5b3ee1d4-3a2b-4b43-a1b1-4c1f0d0a3e2f")]
+    pub id: String,
+    #[schema(example = "a1c6cef6-d987-4225-8bc4-def387e8b5bf")]
+    pub game_id: String,
+    #[schema(example = "cabinet-1")]
+    pub cabinet_id: String,
+    #[schema(example = "skyz")]
+    pub user_id: Option<String>,
+    #[schema(example = "2023-03-20T18:30:00Z")]
+    pub started_at: DateTime<Utc>,
+    #[schema(example = "2023-03-20T18:42:00Z")]
+    pub ended_at: Option<DateTime<Utc>>,
+    #[schema(example = 720)]
+    pub duration_seconds: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone, PartialEq, Debug)]
+pub struct PlayStats {
+    #[schema(example = "a1c6cef6-d987-4225-8bc4-def387e8b5bf")]
+    pub game_id: String,
+    #[schema(example = 42)]
+    pub plays: i64,
+    #[schema(example = 30240)]
+    pub total_seconds: i64,
+    #[schema(example = 17)]
+    pub unique_players: i64,
+}
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone, PartialEq, Debug)]
+pub struct DailyPlays {
+    #[schema(example = "2023-03-20")]
+    pub day: NaiveDate,
+    #[schema(example = 6)]
+    pub plays: i64,
+    #[schema(example = 4320)]
+    pub total_seconds: i64,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Clone, PartialEq, Debug)]
+pub struct GamePlayStats {
+    #[schema(example = "a1c6cef6-d987-4225-8bc4-def387e8b5bf")]
+    pub game_id: String,
+    #[schema(example = 42)]
+    pub plays: i64,
+    #[schema(example = 30240)]
+    pub total_seconds: i64,
+    #[schema(example = 17)]
+    pub unique_players: i64,
+    pub daily: Vec<DailyPlays>,
+}
+
+impl GamePlayStats {
+    pub fn new(stats: PlayStats, daily: Vec<DailyPlays>) -> GamePlayStats {
+        GamePlayStats {
+            game_id: stats.game_id,
+            plays: stats.plays,
+            total_seconds: stats.total_seconds,
+            unique_players: stats.unique_players,
+            daily,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone, PartialEq, Debug)]
+pub struct Score {
+    #[schema(example = 7)]
+    pub id: i64,
+    #[schema(example = "a1c6cef6-d987-4225-8bc4-def387e8b5bf")]
+    pub game_id: String,
+    #[schema(example = "skyz")]
+    pub user_id: String,
+    #[schema(example = "normal")]
+    pub board: String,
+    #[schema(example = 133700)]
+    pub score: i64,
+    #[schema(example = "2023-03-20T18:30:00Z")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone, PartialEq, Debug)]
+pub struct LeaderboardEntry {
+    #[schema(example = 1)]
+    pub rank: i64,
+    #[schema(example = 7)]
+    pub score_id: i64,
+    #[schema(example = "skyz")]
+    pub user_id: String,
+    #[schema(example = 133700)]
+    pub score: i64,
+    #[schema(example = "2023-03-20T18:30:00Z")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone, PartialEq, Debug)]
+pub struct ScoreSecret {
+    #[schema(example = "a1c6cef6-d987-4225-8bc4-def387e8b5bf")]
+    pub game_id: String,
+    #[schema(example = "3f0c9d1e5a7b4c2d8e6f1a0b9c8d7e6f5a4b3c2d1e0f9a8b7c6d5e4f3a2b1c0d")]
+    pub secret: String,
+}
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone, PartialEq, Debug)]
+pub struct Achievement {
+    #[schema(example = "a1c6cef6-d987-4225-8bc4-def387e8b5bf")]
+    pub game_id: String,
+    #[schema(example = "first_blood")]
+    pub id: String,
+    #[schema(example = "First Blood")]
+    pub title: String,
+    #[schema(example = "Break your first brick")]
+    pub description: String,
+    /// Path of the icon inside the game zip
+    #[schema(example = "publish/achievements/first_blood.png")]
+    pub icon: Option<String>,
+    /// Hidden achievements should not be revealed to players until unlocked
+    #[schema(example = false)]
+    pub hidden: bool,
+    #[schema(example = "2023-03-20T18:30:00Z")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone, PartialEq, Debug)]
+pub struct AchievementUnlock {
+    #[schema(example = "a1c6cef6-d987-4225-8bc4-def387e8b5bf")]
+    pub game_id: String,
+    #[schema(example = "first_blood")]
+    pub achievement_id: String,
+    #[schema(example = "skyz")]
+    pub user_id: String,
+    #[schema(example = "2023-03-20T18:30:00Z")]
+    pub unlocked_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone, PartialEq, Debug)]
+pub struct AchievementStats {
+    #[schema(example = "first_blood")]
+    pub achievement_id: String,
+    #[schema(example = 42)]
+    pub unlocks: i64,
+    /// Share of the game's players that unlocked the achievement, from 0 to 100
+    #[schema(example = 87.5)]
+    pub percentage: f64,
+}
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone, PartialEq, Debug)]
+pub struct Review {
+    #[schema(example = "a1c6cef6-d987-4225-8bc4-def387e8b5bf")]
+    pub game_id: String,
+    #[schema(example = "skyz")]
+    pub user_id: String,
+    #[schema(example = 5)]
+    pub rating: i16,
+    #[schema(example = "Best brick breaking on campus")]
+    pub review: Option<String>,
+    /// Hidden reviews are excluded from listings and rating aggregates
+    #[schema(example = false)]
+    pub hidden: bool,
+    #[schema(example = "2023-03-20T18:30:00Z")]
+    pub created_at: DateTime<Utc>,
+    #[schema(example = "2023-03-20T18:30:00Z")]
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema, Clone, PartialEq, Debug)]
+pub struct ReviewPage {
+    pub reviews: Vec<Review>,
+    #[schema(example = 0)]
+    pub page: i64,
+    #[schema(example = 20)]
+    pub per_page: i64,
+    /// Number of visible reviews of the game across all pages
+    #[schema(example = 42)]
+    pub total: i64,
+}
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone, PartialEq, Debug)]
+pub struct Webhook {
+    #[schema(example = 1)]
+    pub id: i32,
+    #[schema(example = "https://hooks.example.com/devcade")]
+    pub url: String,
+    #[schema(example = json!([CatalogEventType::GameCreated, CatalogEventType::GameDeleted]))]
+    pub event_types: Vec<CatalogEventType>,
+    #[schema(example = true)]
+    pub active: bool,
+    #[schema(example = "2023-03-20T18:30:00Z")]
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(sqlx::Type, Serialize, Deserialize, Clone, Copy, ToSchema, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum WebhookDeliveryStatus {
+    Pending,
+    Delivered,
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, FromRow, ToSchema, Clone, PartialEq, Debug)]
+pub struct WebhookDelivery {
+    #[schema(example = 12)]
+    pub id: i64,
+    #[schema(example = 1)]
+    pub webhook_id: i32,
+    #[schema(example = 42)]
+    pub event_id: i64,
+    #[schema(example = WebhookDeliveryStatus::Delivered)]
+    pub status: WebhookDeliveryStatus,
+    #[schema(example = 1)]
+    pub attempts: i32,
+    #[schema(example = "2023-03-20T18:30:00Z")]
+    pub next_attempt_at: DateTime<Utc>,
+    #[schema(example = 200)]
+    pub last_status_code: Option<i32>,
+    #[schema(example = json!(null))]
+    pub last_error: Option<String>,
+    #[schema(example = "2023-03-20T18:30:00Z")]
+    pub delivered_at: Option<DateTime<Utc>>,
+    #[schema(example = "2023-03-20T18:30:00Z")]
+    pub created_at: DateTime<Utc>,
+}
+
 pub struct AppState {
     pub db: Pool<Postgres>,
     pub s3: Client,
+    pub events: Sender<CatalogEvent>,
+    pub config: Config,
+    pub metrics: Metrics,
 }
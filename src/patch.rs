@@ -0,0 +1,43 @@
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+/// Apply `patch` to `target` following JSON Merge Patch (RFC 7396): objects are merged
+/// recursively, `null` removes a field, and anything else replaces it.
+pub fn merge(target: &mut Value, patch: &Value) {
+    let patch = match patch {
+        Value::Object(patch) => patch,
+        patch => {
+            *target = patch.clone();
+            return;
+        }
+    };
+    if !target.is_object() {
+        *target = Value::Object(Default::default());
+    }
+    let target = target.as_object_mut().unwrap();
+    for (field, value) in patch {
+        if value.is_null() {
+            target.remove(field);
+        } else {
+            merge(target.entry(field.as_str()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+/// Merge `patch` into `current`, rejecting patches touching anything but the `editable` fields
+pub fn apply<T: Serialize + DeserializeOwned>(
+    current: &T,
+    patch: &Value,
+    editable: &[&str],
+) -> Result<T, String> {
+    let fields = patch.as_object().ok_or("patch must be a JSON object")?;
+    if let Some(field) = fields
+        .keys()
+        .find(|field| !editable.contains(&field.as_str()))
+    {
+        return Err(format!("{} cannot be patched", field));
+    }
+    let mut value = serde_json::to_value(current).map_err(|e| e.to_string())?;
+    merge(&mut value, patch);
+    serde_json::from_value(value).map_err(|e| format!("invalid patch: {}", e))
+}
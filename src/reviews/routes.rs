@@ -0,0 +1,220 @@
+use crate::{
+    logging::middleware::internal_error,
+    models::{AppState, Review, ReviewPage},
+    security::RequireApiKey,
+    telemetry::query::Traced,
+};
+use actix_web::{
+    delete, get, put,
+    web::{Data, Json, Path, Query},
+    HttpResponse, Responder,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{query, query_as, query_scalar};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ReviewData {
+    /// Rating from 1 to 5
+    #[schema(example = 5)]
+    pub rating: i16,
+    #[schema(example = "Best brick breaking on campus")]
+    pub review: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ReviewVisibility {
+    #[schema(example = true)]
+    pub hidden: bool,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ReviewPageQuery {
+    /// Zero based page number, defaults to 0
+    page: Option<i64>,
+    /// Number of reviews per page, defaults to 20
+    per_page: Option<i64>,
+}
+
+#[utoipa::path(
+    context_path = "/reviews",
+    params(
+        ("game_id", description = "Unique id of game"),
+        ReviewPageQuery,
+    ),
+    responses(
+        (status = 200, description = "Visible reviews of the game, newest first", body = ReviewPage),
+        (status = 400, description = "Invalid page"),
+        (status = 500, description = "Error Created by Query"),
+    )
+)]
+#[get("/{game_id}")]
+pub async fn get_game_reviews(
+    state: Data<AppState>,
+    path: Path<(String,)>,
+    params: Query<ReviewPageQuery>,
+) -> impl Responder {
+    let (game_id,) = path.into_inner();
+    let page = params.page.unwrap_or(0);
+    let per_page = params.per_page.unwrap_or(20);
+    if page < 0 || !(1..=100).contains(&per_page) {
+        return HttpResponse::BadRequest().body("page must be positive and per_page within 1-100");
+    }
+    let offset = match page.checked_mul(per_page) {
+        Some(offset) => offset,
+        None => return HttpResponse::BadRequest().body("page is too large"),
+    };
+    let total = match query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM reviews WHERE game_id = $1 AND NOT hidden",
+    )
+    .bind(&game_id)
+    .fetch_one(Traced(&state.db))
+    .await
+    {
+        Ok(total) => total,
+        Err(e) => return internal_error("could not count reviews", e),
+    };
+    match query_as::<_, Review>(
+        "
+        SELECT * FROM reviews WHERE game_id = $1 AND NOT hidden
+        ORDER BY updated_at DESC, user_id ASC
+        LIMIT $2 OFFSET $3
+        ",
+    )
+    .bind(&game_id)
+    .bind(per_page)
+    .bind(offset)
+    .fetch_all(Traced(&state.db))
+    .await
+    {
+        Ok(reviews) => HttpResponse::Ok().json(ReviewPage {
+            reviews,
+            page,
+            per_page,
+            total,
+        }),
+        Err(e) => internal_error("could not fetch reviews", e),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/reviews",
+    request_body(content=ReviewData, content_type="application/json", description="Rating and optional review of the user"),
+    responses(
+        (status = 200, description = "Created or replaced the user's review", body = Review),
+        (status = 400, description = "Invalid rating, or missing game or user"),
+        (status = 401, description = "Invalid/Missing API Key"),
+        (status = 500, description = "Error Created by Query"),
+    ),
+    params(
+        ("game_id", description = "Unique id of game"),
+        ("uid", description = "Unique id of the reviewing user"),
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[put("/{game_id}/{uid}", wrap = "RequireApiKey")]
+pub async fn put_review(
+    state: Data<AppState>,
+    path: Path<(String, String)>,
+    review: Json<ReviewData>,
+) -> impl Responder {
+    let (game_id, uid) = path.into_inner();
+    if !(1..=5).contains(&review.rating) {
+        return HttpResponse::BadRequest().body("rating must be between 1 and 5");
+    }
+    match query_as::<_, Review>(
+        "
+        INSERT INTO reviews (game_id, user_id, rating, review) VALUES ($1, $2, $3, $4)
+        ON CONFLICT (game_id, user_id) DO UPDATE
+        SET rating = $3, review = $4, updated_at = now()
+        RETURNING *
+        ",
+    )
+    .bind(&game_id)
+    .bind(&uid)
+    .bind(review.rating)
+    .bind(&review.review)
+    .fetch_one(Traced(&state.db))
+    .await
+    {
+        Ok(review) => HttpResponse::Ok().json(review),
+        Err(sqlx::Error::Database(e)) if e.code().as_deref() == Some("23503") => {
+            HttpResponse::BadRequest().body("Game Or User Does Not Exist")
+        }
+        Err(e) => internal_error("could not save review", e),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/reviews",
+    request_body(content=ReviewVisibility, content_type="application/json", description="Whether the review is hidden from players"),
+    responses(
+        (status = 200, description = "Changed the visibility of the review", body = Review),
+        (status = 400, description = "Missing review"),
+        (status = 401, description = "Invalid/Missing API Key"),
+        (status = 500, description = "Error Created by Query"),
+    ),
+    params(
+        ("game_id", description = "Unique id of game"),
+        ("uid", description = "Unique id of the reviewing user"),
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[put("/{game_id}/{uid}/visibility", wrap = "RequireApiKey")]
+pub async fn set_review_visibility(
+    state: Data<AppState>,
+    path: Path<(String, String)>,
+    visibility: Json<ReviewVisibility>,
+) -> impl Responder {
+    let (game_id, uid) = path.into_inner();
+    match query_as::<_, Review>(
+        "UPDATE reviews SET hidden = $3 WHERE game_id = $1 AND user_id = $2 RETURNING *",
+    )
+    .bind(&game_id)
+    .bind(&uid)
+    .bind(visibility.hidden)
+    .fetch_optional(Traced(&state.db))
+    .await
+    {
+        Ok(Some(review)) => HttpResponse::Ok().json(review),
+        Ok(None) => HttpResponse::BadRequest().body("Review Does Not Exist"),
+        Err(e) => internal_error("could not set review visibility", e),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/reviews",
+    responses(
+        (status = 200, description = "Removed review"),
+        (status = 400, description = "Missing review"),
+        (status = 401, description = "Invalid/Missing API Key"),
+        (status = 500, description = "Error Created by Deletion"),
+    ),
+    params(
+        ("game_id", description = "Unique id of game"),
+        ("uid", description = "Unique id of the reviewing user"),
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[delete("/{game_id}/{uid}", wrap = "RequireApiKey")]
+pub async fn delete_review(state: Data<AppState>, path: Path<(String, String)>) -> impl Responder {
+    let (game_id, uid) = path.into_inner();
+    match query("DELETE FROM reviews WHERE game_id = $1 AND user_id = $2")
+        .bind(&game_id)
+        .bind(&uid)
+        .execute(Traced(&state.db))
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => {
+            HttpResponse::BadRequest().body("Review Does Not Exist")
+        }
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => internal_error("could not delete review", e),
+    }
+}
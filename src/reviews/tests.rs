@@ -0,0 +1,103 @@
+#[cfg(test)]
+use crate::tests::{get_test_server, TEST_GAME_I};
+use crate::{
+    models::{GameWithTags, Review, ReviewPage},
+    reviews::routes::{ReviewData, ReviewVisibility},
+};
+
+async fn review(
+    srv: &actix_test::TestServer,
+    user_id: &str,
+    rating: i16,
+) -> awc::ClientResponse<actix_web::dev::Decompress<actix_web::dev::Payload>> {
+    let req = srv
+        .put(format!("/api/reviews/{}/{}", TEST_GAME_I.id, user_id))
+        .insert_header(("frontend_api_key", "TESTING"));
+    req.send_json(&ReviewData {
+        rating,
+        review: Some(format!("{} stars", rating)),
+    })
+    .await
+    .unwrap()
+}
+
+#[actix_web::test]
+async fn test_put_review_unauthorized() {
+    let srv = get_test_server().await;
+    let req = srv.put(format!("/api/reviews/{}/qel", TEST_GAME_I.id));
+    let res = req
+        .send_json(&ReviewData {
+            rating: 5,
+            review: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(res.status().as_u16(), 401);
+}
+
+#[actix_web::test]
+async fn test_put_review_invalid_rating() {
+    let srv = get_test_server().await;
+    assert_eq!(review(&srv, "qel", 6).await.status().as_u16(), 400);
+    assert_eq!(review(&srv, "qel", 0).await.status().as_u16(), 400);
+}
+
+#[actix_web::test]
+async fn test_reviews() {
+    let srv = get_test_server().await;
+    assert!(review(&srv, "atom", 1).await.status().is_success());
+    assert!(review(&srv, "atom", 4).await.status().is_success());
+    let mut res = review(&srv, "joeneil", 2).await;
+    assert!(res.status().is_success());
+    assert_eq!(res.json::<Review>().await.unwrap().rating, 2);
+
+    let req = srv
+        .put(format!(
+            "/api/reviews/{}/joeneil/visibility",
+            TEST_GAME_I.id
+        ))
+        .insert_header(("frontend_api_key", "TESTING"));
+    let mut res = req
+        .send_json(&ReviewVisibility { hidden: true })
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    assert!(res.json::<Review>().await.unwrap().hidden);
+
+    let req = srv
+        .get(format!("/api/reviews/{}", TEST_GAME_I.id))
+        .query(&[("per_page", "1")])
+        .unwrap();
+    let mut res = req.send().await.unwrap();
+    assert!(res.status().is_success());
+    let page = res.json::<ReviewPage>().await.unwrap();
+    assert_eq!(page.total, 1);
+    assert_eq!(page.reviews.len(), 1);
+    assert_eq!(page.reviews[0].user_id, "atom");
+    assert_eq!(page.reviews[0].rating, 4);
+
+    let req = srv
+        .get(format!("/api/reviews/{}", TEST_GAME_I.id))
+        .query(&[("page", i64::MAX), ("per_page", 100)])
+        .unwrap();
+    let res = req.send().await.unwrap();
+    assert_eq!(res.status().as_u16(), 400);
+
+    let req = srv.get(format!("/api/games/{}", TEST_GAME_I.id));
+    let mut res = req.send().await.unwrap();
+    let game = res.json::<GameWithTags>().await.unwrap();
+    assert_eq!(game.rating_count, 1);
+    assert_eq!(game.rating_average, Some(4.0));
+
+    let req = srv.get("/api/games/?sort=rating");
+    let mut res = req.send().await.unwrap();
+    assert!(res.status().is_success());
+    let games = res.json::<Vec<GameWithTags>>().await.unwrap();
+    assert_eq!(games[0].id, TEST_GAME_I.id);
+
+    let req = srv
+        .delete(format!("/api/reviews/{}/joeneil", TEST_GAME_I.id))
+        .insert_header(("frontend_api_key", "TESTING"));
+    let res = req.send().await.unwrap();
+    assert!(res.status().is_success());
+}
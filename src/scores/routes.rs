@@ -0,0 +1,412 @@
+use crate::{
+    logging::middleware::internal_error,
+    models::{AppState, Game, LeaderboardEntry, Score, ScoreSecret, User},
+    security::{verify_signature, RequireApiKey, SecretKey, SIGNATURE_HEADER},
+    telemetry::query::Traced,
+};
+use actix_web::{
+    delete, get, post,
+    web::{Bytes, Data, Path, Query},
+    HttpRequest, HttpResponse, Responder,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::{query, query_as, query_scalar};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+const DEFAULT_BOARD: &str = "default";
+/// Seconds a signed submission may be away from the server's clock
+const MAX_CLOCK_SKEW: u64 = 300;
+
+/// The key score secrets are encrypted with, or the response to give when it is not configured
+fn secret_key(state: &AppState) -> Result<&SecretKey, HttpResponse> {
+    state.config.score_secret_key.as_ref().ok_or_else(|| {
+        HttpResponse::ServiceUnavailable().body("SCORE_SECRET_KEY is not configured")
+    })
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ScoreSubmission {
+    #[schema(example = "skyz")]
+    pub user_id: String,
+    #[schema(example = 133700)]
+    pub score: i64,
+    /// Leaderboard to submit to, `default` when omitted
+    #[schema(example = "normal")]
+    pub board: Option<String>,
+    /// Unix time the score was signed at, refused once five minutes away from the server's
+    #[schema(example = 1679337000)]
+    pub timestamp: i64,
+    /// Random value unique to the submission, so a signed score is only recorded once
+    #[schema(example = "6f1c8e0b2a7d4f3e9b5a1c0d8e7f6a5b")]
+    pub nonce: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoreWindow {
+    Daily,
+    Weekly,
+    AllTime,
+}
+
+impl ScoreWindow {
+    fn start(&self) -> &'static str {
+        match self {
+            ScoreWindow::Daily => "date_trunc('day', now())",
+            ScoreWindow::Weekly => "date_trunc('week', now())",
+            ScoreWindow::AllTime => "'-infinity'::timestamptz",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct LeaderboardQuery {
+    /// Leaderboard to read, `default` when omitted
+    board: Option<String>,
+    /// Only count scores from the current day or week, all time by default
+    window: Option<ScoreWindow>,
+    /// Number of entries to return, defaults to 10
+    limit: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AroundQuery {
+    /// Leaderboard to read, `default` when omitted
+    board: Option<String>,
+    /// Only count scores from the current day or week, all time by default
+    window: Option<ScoreWindow>,
+    /// Number of ranks to include above and below the user, defaults to 5
+    range: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct WindowQuery {
+    /// Only count scores from the current day or week, all time by default
+    window: Option<ScoreWindow>,
+}
+
+/// Best score of every user on a board, ranked highest first
+fn ranked_scores(window: ScoreWindow) -> String {
+    format!(
+        "
+        WITH best AS (
+            SELECT DISTINCT ON (user_id) id, user_id, score, created_at
+            FROM scores
+            WHERE game_id = $1 AND board = $2 AND created_at >= {}
+            ORDER BY user_id, score DESC, created_at ASC
+        ), ranked AS (
+            SELECT RANK() OVER (ORDER BY score DESC) AS rank,
+                id AS score_id, user_id, score, created_at
+            FROM best
+        )
+        ",
+        window.start()
+    )
+}
+
+#[utoipa::path(
+    context_path = "/scores",
+    request_body(content=ScoreSubmission, content_type="application/json", description="Score signed with the game's secret in X-Devcade-Signature"),
+    responses(
+        (status = 201, description = "Recorded score", body = Score),
+        (status = 400, description = "Missing game or user, or invalid nonce"),
+        (status = 401, description = "Invalid/Missing signature, or timestamp too far from the server's clock"),
+        (status = 409, description = "Submission with the same nonce was already recorded"),
+        (status = 500, description = "Error Created by Query"),
+        (status = 503, description = "No key is configured to decrypt score secrets with"),
+    ),
+    params(
+        ("game_id", description = "Unique id of game"),
+        ("X-Devcade-Signature" = String, Header, description = "sha256=<HMAC-SHA256 of the body with the game's score secret>")
+    )
+)]
+#[post("/{game_id}")]
+pub async fn submit_score(
+    req: HttpRequest,
+    state: Data<AppState>,
+    path: Path<(String,)>,
+    body: Bytes,
+) -> impl Responder {
+    let (game_id,) = path.into_inner();
+    let key = match secret_key(&state) {
+        Ok(key) => key,
+        Err(res) => return res,
+    };
+    let sealed =
+        match query_scalar::<_, String>("SELECT secret FROM score_secrets WHERE game_id = $1")
+            .bind(&game_id)
+            .fetch_optional(Traced(&state.db))
+            .await
+        {
+            Ok(Some(sealed)) => sealed,
+            Ok(None) => return HttpResponse::BadRequest().body("Game Does Not Accept Scores"),
+            Err(e) => return internal_error("could not fetch score secret", e),
+        };
+    let secret = match key.open(&sealed) {
+        Some(secret) => secret,
+        None => {
+            return internal_error(
+                "could not decrypt score secret",
+                "sealed under another key, the secret must be rotated",
+            )
+        }
+    };
+    let signature = req
+        .headers()
+        .get(SIGNATURE_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    if !verify_signature(&secret, &body, signature) {
+        return HttpResponse::Unauthorized().body("invalid score signature");
+    }
+    let submission = match serde_json::from_slice::<ScoreSubmission>(&body) {
+        Ok(submission) => submission,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+    // The timestamp and nonce are signed along with the score, so a captured submission can't
+    // be replayed later, or more than once while it is fresh
+    if Utc::now().timestamp().abs_diff(submission.timestamp) > MAX_CLOCK_SKEW {
+        return HttpResponse::Unauthorized().body("score signature expired");
+    }
+    if submission.nonce.is_empty() || submission.nonce.len() > 64 {
+        return HttpResponse::BadRequest().body("nonce must be 1 to 64 characters");
+    }
+    if query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+        .bind(&submission.user_id)
+        .fetch_one(Traced(&state.db))
+        .await
+        .is_err()
+    {
+        return HttpResponse::BadRequest().body("User Does Not Exist");
+    }
+    match query_as::<_, Score>(
+        "
+        INSERT INTO scores (game_id, user_id, board, score, nonce) VALUES ($1, $2, $3, $4, $5)
+        RETURNING *
+        ",
+    )
+    .bind(&game_id)
+    .bind(&submission.user_id)
+    .bind(submission.board.as_deref().unwrap_or(DEFAULT_BOARD))
+    .bind(submission.score)
+    .bind(&submission.nonce)
+    .fetch_one(Traced(&state.db))
+    .await
+    {
+        Ok(score) => HttpResponse::Created().json(score),
+        Err(sqlx::Error::Database(e)) if e.code().as_deref() == Some("23505") => {
+            HttpResponse::Conflict().body("Score Already Submitted")
+        }
+        Err(e) => internal_error("could not record score", e),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/scores",
+    responses(
+        (status = 201, description = "Generated a new score secret, replacing any previous one", body = ScoreSecret),
+        (status = 400, description = "Missing game"),
+        (status = 401, description = "Invalid/Missing API Key"),
+        (status = 500, description = "Error Created by Query"),
+        (status = 503, description = "No key is configured to encrypt score secrets with"),
+    ),
+    params(
+        ("game_id", description = "Unique id of game")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[post("/{game_id}/secret", wrap = "RequireApiKey")]
+pub async fn rotate_score_secret(state: Data<AppState>, path: Path<(String,)>) -> impl Responder {
+    let (game_id,) = path.into_inner();
+    if query_as::<_, Game>("SELECT * FROM game WHERE id = $1")
+        .bind(&game_id)
+        .fetch_one(Traced(&state.db))
+        .await
+        .is_err()
+    {
+        return HttpResponse::BadRequest().body("Game ID Does Not Exist");
+    }
+    let key = match secret_key(&state) {
+        Ok(key) => key,
+        Err(res) => return res,
+    };
+    let secret = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    let sealed = match key.seal(&secret) {
+        Ok(sealed) => sealed,
+        Err(e) => return internal_error("could not encrypt score secret", e),
+    };
+    // Only the encrypted secret is stored, so this response is the one chance to read it
+    match query(
+        "
+        INSERT INTO score_secrets (game_id, secret) VALUES ($1, $2)
+        ON CONFLICT (game_id) DO UPDATE SET secret = $2, created_at = now()
+        ",
+    )
+    .bind(&game_id)
+    .bind(&sealed)
+    .execute(Traced(&state.db))
+    .await
+    {
+        Ok(_) => HttpResponse::Created().json(ScoreSecret { game_id, secret }),
+        Err(e) => internal_error("could not rotate score secret", e),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/scores",
+    params(
+        ("game_id", description = "Unique id of game"),
+        LeaderboardQuery,
+    ),
+    responses(
+        (status = 200, description = "Top scores of the board, one per user", body = [LeaderboardEntry]),
+        (status = 400, description = "limit is not positive"),
+        (status = 500, description = "Error Created by Query"),
+    )
+)]
+#[get("/{game_id}")]
+pub async fn get_leaderboard(
+    state: Data<AppState>,
+    path: Path<(String,)>,
+    params: Query<LeaderboardQuery>,
+) -> impl Responder {
+    let (game_id,) = path.into_inner();
+    let limit = params.limit.unwrap_or(10);
+    if limit < 1 {
+        return HttpResponse::BadRequest().body("limit must be positive");
+    }
+    match query_as::<_, LeaderboardEntry>(&format!(
+        "{} SELECT * FROM ranked ORDER BY rank ASC, created_at ASC LIMIT $3",
+        ranked_scores(params.window.unwrap_or(ScoreWindow::AllTime))
+    ))
+    .bind(&game_id)
+    .bind(params.board.as_deref().unwrap_or(DEFAULT_BOARD))
+    .bind(limit)
+    .fetch_all(Traced(&state.db))
+    .await
+    {
+        Ok(entries) => HttpResponse::Ok().json(entries),
+        Err(e) => internal_error("could not fetch leaderboard", e),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/scores",
+    params(
+        ("game_id", description = "Unique id of game"),
+        ("uid", description = "User to center the leaderboard on"),
+        AroundQuery,
+    ),
+    responses(
+        (status = 200, description = "Scores ranked around the user's best", body = [LeaderboardEntry]),
+        (status = 400, description = "User has no score on the board, or range is negative"),
+        (status = 500, description = "Error Created by Query"),
+    )
+)]
+#[get("/{game_id}/around/{uid}")]
+pub async fn get_leaderboard_around(
+    state: Data<AppState>,
+    path: Path<(String, String)>,
+    params: Query<AroundQuery>,
+) -> impl Responder {
+    let (game_id, uid) = path.into_inner();
+    let range = params.range.unwrap_or(5);
+    if range < 0 {
+        return HttpResponse::BadRequest().body("range must not be negative");
+    }
+    match query_as::<_, LeaderboardEntry>(&format!(
+        "
+        {}
+        SELECT * FROM ranked
+        WHERE rank BETWEEN (SELECT rank FROM ranked WHERE user_id = $3) - $4
+            AND (SELECT rank FROM ranked WHERE user_id = $3) + $4
+        ORDER BY rank ASC, created_at ASC
+        ",
+        ranked_scores(params.window.unwrap_or(ScoreWindow::AllTime))
+    ))
+    .bind(&game_id)
+    .bind(params.board.as_deref().unwrap_or(DEFAULT_BOARD))
+    .bind(&uid)
+    .bind(range)
+    .fetch_all(Traced(&state.db))
+    .await
+    {
+        Ok(entries) if entries.is_empty() => {
+            HttpResponse::BadRequest().body("User Has No Score On Board")
+        }
+        Ok(entries) => HttpResponse::Ok().json(entries),
+        Err(e) => internal_error("could not fetch leaderboard", e),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/scores",
+    params(
+        ("uid", description = "Unique id of user"),
+        WindowQuery,
+    ),
+    responses(
+        (status = 200, description = "Best score of the user on every game and board", body = [Score]),
+        (status = 500, description = "Error Created by Query"),
+    )
+)]
+#[get("/users/{uid}")]
+pub async fn get_user_bests(
+    state: Data<AppState>,
+    path: Path<(String,)>,
+    params: Query<WindowQuery>,
+) -> impl Responder {
+    let (uid,) = path.into_inner();
+    match query_as::<_, Score>(&format!(
+        "
+        SELECT DISTINCT ON (game_id, board) * FROM scores
+        WHERE user_id = $1 AND created_at >= {}
+        ORDER BY game_id, board, score DESC, created_at ASC
+        ",
+        params.window.unwrap_or(ScoreWindow::AllTime).start()
+    ))
+    .bind(&uid)
+    .fetch_all(Traced(&state.db))
+    .await
+    {
+        Ok(scores) => HttpResponse::Ok().json(scores),
+        Err(e) => internal_error("could not fetch user bests", e),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/scores",
+    responses(
+        (status = 200, description = "Removed score"),
+        (status = 400, description = "Missing score"),
+        (status = 401, description = "Invalid/Missing API Key"),
+        (status = 500, description = "Error Created by Deletion"),
+    ),
+    params(
+        ("game_id", description = "Unique id of game"),
+        ("score_id", description = "Unique id of score"),
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[delete("/{game_id}/{score_id}", wrap = "RequireApiKey")]
+pub async fn delete_score(state: Data<AppState>, path: Path<(String, i64)>) -> impl Responder {
+    let (game_id, score_id) = path.into_inner();
+    match query("DELETE FROM scores WHERE id = $1 AND game_id = $2")
+        .bind(score_id)
+        .bind(&game_id)
+        .execute(Traced(&state.db))
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => {
+            HttpResponse::BadRequest().body("Score Does Not Exist")
+        }
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => internal_error("could not delete score", e),
+    }
+}
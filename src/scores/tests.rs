@@ -0,0 +1,179 @@
+#[cfg(test)]
+use crate::tests::{get_test_server, TEST_GAME_A, TEST_GAME_B};
+use crate::{
+    app::get_app_data,
+    config::Config,
+    models::{LeaderboardEntry, Score, ScoreSecret},
+    scores::routes::ScoreSubmission,
+    security::{sign, SIGNATURE_HEADER},
+};
+use chrono::Utc;
+use sqlx::query_scalar;
+use uuid::Uuid;
+
+const BOARD: &str = "scores_test_board";
+
+fn submission(user_id: &str, score: i64, timestamp: i64) -> Vec<u8> {
+    serde_json::to_vec(&ScoreSubmission {
+        user_id: user_id.to_string(),
+        score,
+        board: Some(BOARD.to_string()),
+        timestamp,
+        nonce: Uuid::new_v4().simple().to_string(),
+    })
+    .unwrap()
+}
+
+async fn send_signed(
+    srv: &actix_test::TestServer,
+    secret: &str,
+    body: Vec<u8>,
+) -> awc::ClientResponse<actix_web::dev::Decompress<actix_web::dev::Payload>> {
+    srv.post(format!("/api/scores/{}", TEST_GAME_A.id))
+        .insert_header((SIGNATURE_HEADER, sign(secret, &body)))
+        .insert_header(("Content-Type", "application/json"))
+        .send_body(body)
+        .await
+        .unwrap()
+}
+
+async fn submit(
+    srv: &actix_test::TestServer,
+    secret: &str,
+    user_id: &str,
+    score: i64,
+) -> awc::ClientResponse<actix_web::dev::Decompress<actix_web::dev::Payload>> {
+    send_signed(
+        srv,
+        secret,
+        submission(user_id, score, Utc::now().timestamp()),
+    )
+    .await
+}
+
+#[actix_web::test]
+async fn test_rotate_score_secret_unauthorized() {
+    let srv = get_test_server().await;
+    let req = srv.post(format!("/api/scores/{}/secret", TEST_GAME_B.id));
+    let res = req.send().await.unwrap();
+    assert_eq!(res.status().as_u16(), 401);
+}
+
+#[actix_web::test]
+async fn test_submit_score_without_secret() {
+    let srv = get_test_server().await;
+    let body = serde_json::to_vec(&ScoreSubmission {
+        user_id: "qel".to_string(),
+        score: 1,
+        board: None,
+        timestamp: Utc::now().timestamp(),
+        nonce: Uuid::new_v4().simple().to_string(),
+    })
+    .unwrap();
+    let req = srv.post(format!("/api/scores/{}", TEST_GAME_B.id));
+    let res = req.send_body(body).await.unwrap();
+    assert_eq!(res.status().as_u16(), 400);
+}
+
+#[actix_web::test]
+async fn test_leaderboard() {
+    let srv = get_test_server().await;
+    let req = srv
+        .post(format!("/api/scores/{}/secret", TEST_GAME_A.id))
+        .insert_header(("frontend_api_key", "TESTING"));
+    let mut res = req.send().await.unwrap();
+    assert_eq!(res.status().as_u16(), 201);
+    let secret = res.json::<ScoreSecret>().await.unwrap().secret;
+    let state = get_app_data(Config::load().unwrap()).await.unwrap();
+    let stored = query_scalar::<_, String>("SELECT secret FROM score_secrets WHERE game_id = $1")
+        .bind(TEST_GAME_A.id.as_str())
+        .fetch_one(&state.db)
+        .await
+        .unwrap();
+    assert_ne!(stored, secret);
+    let key = state.config.score_secret_key.as_ref().unwrap();
+    assert_eq!(key.open(&stored), Some(secret.clone()));
+
+    let res = submit(&srv, "FORGED SECRET", "skyz", 999999).await;
+    assert_eq!(res.status().as_u16(), 401);
+
+    let mut scores = vec![];
+    for (user_id, score) in [("skyz", 100), ("qel", 300), ("ella", 200), ("skyz", 50)] {
+        let mut res = submit(&srv, &secret, user_id, score).await;
+        assert_eq!(res.status().as_u16(), 201);
+        scores.push(res.json::<Score>().await.unwrap());
+    }
+
+    let req = srv
+        .get(format!("/api/scores/{}", TEST_GAME_A.id))
+        .query(&[("board", BOARD), ("window", "daily")])
+        .unwrap();
+    let mut res = req.send().await.unwrap();
+    assert!(res.status().is_success());
+    let leaderboard = res.json::<Vec<LeaderboardEntry>>().await.unwrap();
+    let ranking: Vec<(i64, &str, i64)> = leaderboard
+        .iter()
+        .map(|entry| (entry.rank, entry.user_id.as_str(), entry.score))
+        .collect();
+    assert_eq!(
+        ranking,
+        vec![(1, "qel", 300), (2, "ella", 200), (3, "skyz", 100)]
+    );
+
+    let req = srv
+        .get(format!("/api/scores/{}/around/ella", TEST_GAME_A.id))
+        .query(&[("board", BOARD), ("range", "0")])
+        .unwrap();
+    let mut res = req.send().await.unwrap();
+    assert!(res.status().is_success());
+    let around = res.json::<Vec<LeaderboardEntry>>().await.unwrap();
+    assert_eq!(around.len(), 1);
+    assert_eq!(around[0].user_id, "ella");
+
+    let req = srv.get("/api/scores/users/skyz");
+    let mut res = req.send().await.unwrap();
+    assert!(res.status().is_success());
+    let bests = res.json::<Vec<Score>>().await.unwrap();
+    assert!(bests
+        .iter()
+        .any(|best| best.board == BOARD && best.score == 100));
+
+    let req = srv
+        .delete(format!("/api/scores/{}/{}", TEST_GAME_A.id, scores[1].id))
+        .insert_header(("frontend_api_key", "TESTING"));
+    let res = req.send().await.unwrap();
+    assert!(res.status().is_success());
+    let req = srv
+        .delete(format!("/api/scores/{}/{}", TEST_GAME_A.id, scores[1].id))
+        .insert_header(("frontend_api_key", "TESTING"));
+    let res = req.send().await.unwrap();
+    assert_eq!(res.status().as_u16(), 400);
+
+    // A captured submission is refused once recorded, or once it is too old
+    let body = submission("qel", 1, Utc::now().timestamp());
+    let res = send_signed(&srv, &secret, body.clone()).await;
+    assert_eq!(res.status().as_u16(), 201);
+    let res = send_signed(&srv, &secret, body).await;
+    assert_eq!(res.status().as_u16(), 409);
+    let body = submission("qel", 1, Utc::now().timestamp() - 3600);
+    let res = send_signed(&srv, &secret, body).await;
+    assert_eq!(res.status().as_u16(), 401);
+}
+
+#[actix_web::test]
+async fn test_leaderboard_invalid_window() {
+    let srv = get_test_server().await;
+    let req = srv
+        .get(format!("/api/scores/{}", TEST_GAME_A.id))
+        .query(&[("limit", "-1")])
+        .unwrap();
+    let res = req.send().await.unwrap();
+    assert_eq!(res.status().as_u16(), 400);
+
+    let req = srv
+        .get(format!("/api/scores/{}/around/skyz", TEST_GAME_A.id))
+        .query(&[("range", "-1")])
+        .unwrap();
+    let res = req.send().await.unwrap();
+    assert_eq!(res.status().as_u16(), 400);
+}
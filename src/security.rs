@@ -1,18 +1,131 @@
+use crate::{
+    logging::{json, middleware::RequestId},
+    models::AppState,
+};
 use actix_web::{
     dev::{Service, ServiceRequest, ServiceResponse, Transform},
-    HttpResponse,
+    web::Data,
+    HttpMessage, HttpRequest, HttpResponse,
 };
+use data_encoding::HEXLOWER;
 use futures::future::LocalBoxFuture;
-use lazy_static::lazy_static;
+use hmac::{Hmac, Mac};
+use log::Level;
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN},
+    rand::{SecureRandom, SystemRandom},
+};
+use serde_json::json;
+use sha2::Sha256;
 use std::{
-    env,
+    fmt,
     future::{self, Ready},
+    str::FromStr,
 };
 
-const API_KEY_NAME: &str = "frontend_api_key";
+pub const API_KEY_NAME: &str = "frontend_api_key";
+pub const SIGNATURE_HEADER: &str = "X-Devcade-Signature";
+/// User a frontend is acting for, trusted only alongside a valid API key
+pub const USER_HEADER: &str = "X-Devcade-User";
+
+/// Hex encoded HMAC-SHA256 of `body`, formatted as `sha256=<digest>`
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    format!("sha256={}", HEXLOWER.encode(&mac.finalize().into_bytes()))
+}
+
+/// Check a `sha256=<digest>` signature produced by [`sign`] in constant time
+pub fn verify_signature(secret: &str, body: &[u8], signature: &str) -> bool {
+    let digest = match signature
+        .strip_prefix("sha256=")
+        .and_then(|digest| HEXLOWER.decode(digest.to_lowercase().as_bytes()).ok())
+    {
+        Some(digest) => digest,
+        None => return false,
+    };
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    mac.verify_slice(&digest).is_ok()
+}
+
+/// AES-256-GCM key secrets are encrypted with before they are stored, given as 64 hex digits
+#[derive(Clone, PartialEq)]
+pub struct SecretKey([u8; 32]);
 
-lazy_static! {
-    static ref API_KEY: String = env::var("FRONTEND_API_KEY").unwrap();
+impl FromStr for SecretKey {
+    type Err = String;
+
+    fn from_str(key: &str) -> Result<SecretKey, String> {
+        HEXLOWER
+            .decode(key.to_lowercase().as_bytes())
+            .ok()
+            .and_then(|key| <[u8; 32]>::try_from(key).ok())
+            .map(SecretKey)
+            .ok_or_else(|| "expected 64 hex digits".to_string())
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SecretKey(..)")
+    }
+}
+
+impl SecretKey {
+    fn cipher(&self) -> LessSafeKey {
+        LessSafeKey::new(UnboundKey::new(&AES_256_GCM, &self.0).expect("key is 32 bytes"))
+    }
+
+    /// Encrypt `secret` under a random nonce, as hex encoded nonce and ciphertext
+    pub fn seal(&self, secret: &str) -> Result<String, ring::error::Unspecified> {
+        let mut nonce = [0; NONCE_LEN];
+        SystemRandom::new().fill(&mut nonce)?;
+        let mut sealed = secret.as_bytes().to_vec();
+        self.cipher().seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::empty(),
+            &mut sealed,
+        )?;
+        Ok(HEXLOWER.encode(&[&nonce[..], &sealed].concat()))
+    }
+
+    /// Decrypt a secret encrypted by [`SecretKey::seal`], or `None` if it was sealed under
+    /// another key or tampered with
+    pub fn open(&self, sealed: &str) -> Option<String> {
+        let sealed = HEXLOWER.decode(sealed.as_bytes()).ok()?;
+        if sealed.len() < NONCE_LEN {
+            return None;
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).ok()?;
+        let mut ciphertext = ciphertext.to_vec();
+        let secret = self
+            .cipher()
+            .open_in_place(nonce, Aad::empty(), &mut ciphertext)
+            .ok()?;
+        String::from_utf8(secret.to_vec()).ok()
+    }
+}
+
+/// Whether the request carries the frontend API key, for routes only partly behind it
+pub fn has_api_key(req: &HttpRequest) -> bool {
+    matches!(
+        (req.headers().get(API_KEY_NAME), req.app_data::<Data<AppState>>()),
+        (Some(key), Some(state)) if key == state.config.frontend_api_key.as_str()
+    )
+}
+
+/// User a request was made for, when a frontend holding the API key names one
+pub fn acting_user(req: &HttpRequest) -> Option<&str> {
+    if !has_api_key(req) {
+        return None;
+    }
+    req.headers()
+        .get(USER_HEADER)
+        .and_then(|user| user.to_str().ok())
 }
 
 pub struct RequireApiKey;
@@ -70,26 +183,28 @@ where
             Box::pin(async { Ok(req.into_response(response)) })
         };
 
-        match req.headers().get(API_KEY_NAME) {
-            Some(key) if key != &API_KEY.to_string() => {
-                if self.log_only {
-                    println!("Incorrect api api provided!!!")
-                } else {
-                    return response(req, HttpResponse::Unauthorized().body("incorrect api key"));
-                }
-            }
-            None => {
-                if self.log_only {
-                    println!("Missing api key!!!")
-                } else {
-                    return response(req, HttpResponse::Unauthorized().body("missing api key"));
-                }
+        let expected = req
+            .app_data::<Data<AppState>>()
+            .map(|state| state.config.frontend_api_key.as_bytes());
+        let problem = match req.headers().get(API_KEY_NAME) {
+            Some(key) if Some(key.as_bytes()) != expected => Some("incorrect api key"),
+            None => Some("missing api key"),
+            _ => None, // just passthrough
+        };
+        let fields = json!({
+            "request_id": req.extensions().get::<RequestId>().map(|id| id.0.clone()),
+            "method": req.method().as_str(),
+            "path": req.path(),
+        });
+        if let Some(problem) = problem {
+            json::event(Level::Warn, problem, fields.clone());
+            if !self.log_only {
+                return response(req, HttpResponse::Unauthorized().body(problem));
             }
-            _ => (), // just passthrough
         }
 
         if self.log_only {
-            println!("Performing operation")
+            json::event(Level::Debug, "performing operation", fields);
         }
 
         let future = self.service.call(req);
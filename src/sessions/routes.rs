@@ -0,0 +1,219 @@
+use crate::{
+    logging::middleware::internal_error,
+    models::{AppState, DailyPlays, Game, GamePlayStats, PlaySession, PlayStats},
+    security::RequireApiKey,
+    telemetry::query::Traced,
+};
+use actix_web::{
+    get, post,
+    web::{Data, Json, Path, Query},
+    HttpResponse, Responder,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{query_as, Pool, Postgres};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SessionStart {
+    #[schema(example = "a1c6cef6-d987-4225-8bc4-def387e8b5bf")]
+    pub game_id: String,
+    #[schema(example = "cabinet-1")]
+    pub cabinet_id: String,
+    #[schema(example = "skyz")]
+    pub user_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SessionEnd {
+    /// Seconds played as measured by the cabinet. Defaults to the time since the session started
+    #[schema(example = 720)]
+    pub duration_seconds: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct HistogramQuery {
+    /// Number of days to include in the daily histogram, defaults to 30
+    days: Option<i32>,
+}
+
+async fn game_exists(db: &Pool<Postgres>, id: &str) -> bool {
+    query_as::<_, Game>("SELECT * FROM game WHERE id = $1")
+        .bind(id)
+        .fetch_one(Traced(db))
+        .await
+        .is_ok()
+}
+
+#[utoipa::path(
+    context_path = "/sessions",
+    request_body(content=SessionStart, content_type="application/json", description="Game and cabinet the session started on"),
+    responses(
+        (status = 201, description = "Started play session", body = PlaySession),
+        (status = 400, description = "Missing game or user"),
+        (status = 401, description = "Invalid/Missing API Key"),
+        (status = 500, description = "Error Created by Query"),
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[post("/", wrap = "RequireApiKey")]
+pub async fn start_session(state: Data<AppState>, session: Json<SessionStart>) -> impl Responder {
+    if !game_exists(&state.db, &session.game_id).await {
+        return HttpResponse::BadRequest().body("Game ID Does Not Exist");
+    }
+    match query_as::<_, PlaySession>(
+        "INSERT INTO play_sessions (id, game_id, cabinet_id, user_id) VALUES ($1, $2, $3, $4) RETURNING *",
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(&session.game_id)
+    .bind(&session.cabinet_id)
+    .bind(&session.user_id)
+    .fetch_one(Traced(&state.db))
+    .await
+    {
+        Ok(session) => HttpResponse::Created().json(session),
+        Err(sqlx::Error::Database(e)) if e.code().as_deref() == Some("23503") => {
+            HttpResponse::BadRequest().body("Game Or User Does Not Exist")
+        }
+        Err(e) => internal_error("could not start session", e),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/sessions",
+    request_body(content=SessionEnd, content_type="application/json", description="Optional duration measured by the cabinet"),
+    responses(
+        (status = 200, description = "Ended play session", body = PlaySession),
+        (status = 400, description = "Negative duration, or missing or already ended session"),
+        (status = 401, description = "Invalid/Missing API Key"),
+        (status = 500, description = "Error Created by Query"),
+    ),
+    params(
+        ("id", description = "Unique id of play session")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[post("/{id}/end", wrap = "RequireApiKey")]
+pub async fn end_session(
+    state: Data<AppState>,
+    path: Path<(String,)>,
+    session: Json<SessionEnd>,
+) -> impl Responder {
+    let (id,) = path.into_inner();
+    if matches!(session.duration_seconds, Some(duration) if duration < 0) {
+        return HttpResponse::BadRequest().body("duration_seconds must not be negative");
+    }
+    match query_as::<_, PlaySession>(
+        "
+        UPDATE play_sessions
+        SET ended_at = now(),
+            duration_seconds = COALESCE($2, EXTRACT(EPOCH FROM now() - started_at)::integer)
+        WHERE id = $1 AND ended_at IS NULL
+        RETURNING *
+        ",
+    )
+    .bind(&id)
+    .bind(session.duration_seconds)
+    .fetch_optional(Traced(&state.db))
+    .await
+    {
+        Ok(Some(session)) => HttpResponse::Ok().json(session),
+        Ok(None) => HttpResponse::BadRequest().body("Session Does Not Exist Or Already Ended"),
+        Err(e) => internal_error("could not end session", e),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/sessions",
+    responses(
+        (status = 200, description = "Play statistics of every game, most played first", body = [PlayStats]),
+        (status = 500, description = "Error Created by Query"),
+    )
+)]
+#[get("/stats")]
+pub async fn get_all_stats(state: Data<AppState>) -> impl Responder {
+    match query_as::<_, PlayStats>(
+        "
+        SELECT game.id AS game_id,
+            COUNT(play_sessions.id) AS plays,
+            COALESCE(SUM(play_sessions.duration_seconds), 0)::bigint AS total_seconds,
+            COUNT(DISTINCT play_sessions.user_id) AS unique_players
+        FROM game
+        LEFT JOIN play_sessions ON play_sessions.game_id = game.id
+        GROUP BY game.id ORDER BY plays DESC, game.name ASC
+        ",
+    )
+    .fetch_all(Traced(&state.db))
+    .await
+    {
+        Ok(stats) => HttpResponse::Ok().json(stats),
+        Err(e) => internal_error("could not fetch play stats", e),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/sessions",
+    params(
+        ("id", description = "Unique id of game"),
+        HistogramQuery,
+    ),
+    responses(
+        (status = 200, description = "Play statistics of game with a daily histogram", body = GamePlayStats),
+        (status = 400, description = "Missing game, or days is not positive"),
+        (status = 500, description = "Error Created by Query"),
+    )
+)]
+#[get("/stats/{id}")]
+pub async fn get_game_stats(
+    state: Data<AppState>,
+    path: Path<(String,)>,
+    params: Query<HistogramQuery>,
+) -> impl Responder {
+    let (id,) = path.into_inner();
+    let days = params.days.unwrap_or(30);
+    if days < 1 {
+        return HttpResponse::BadRequest().body("days must be positive");
+    }
+    let stats = match query_as::<_, PlayStats>(
+        "
+        SELECT game.id AS game_id,
+            COUNT(play_sessions.id) AS plays,
+            COALESCE(SUM(play_sessions.duration_seconds), 0)::bigint AS total_seconds,
+            COUNT(DISTINCT play_sessions.user_id) AS unique_players
+        FROM game
+        LEFT JOIN play_sessions ON play_sessions.game_id = game.id
+        WHERE game.id = $1
+        GROUP BY game.id
+        ",
+    )
+    .bind(&id)
+    .fetch_optional(Traced(&state.db))
+    .await
+    {
+        Ok(Some(stats)) => stats,
+        Ok(None) => return HttpResponse::BadRequest().body("Game ID Does Not Exist"),
+        Err(e) => return internal_error("could not fetch play stats", e),
+    };
+    match query_as::<_, DailyPlays>(
+        "
+        SELECT (started_at AT TIME ZONE 'UTC')::date AS day,
+            COUNT(*) AS plays,
+            COALESCE(SUM(duration_seconds), 0)::bigint AS total_seconds
+        FROM play_sessions
+        WHERE game_id = $1 AND started_at >= now() - make_interval(days => $2)
+        GROUP BY day ORDER BY day ASC
+        ",
+    )
+    .bind(&id)
+    .bind(days)
+    .fetch_all(Traced(&state.db))
+    .await
+    {
+        Ok(daily) => HttpResponse::Ok().json(GamePlayStats::new(stats, daily)),
+        Err(e) => internal_error("could not fetch daily plays", e),
+    }
+}
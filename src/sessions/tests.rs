@@ -0,0 +1,148 @@
+#[cfg(test)]
+use crate::tests::{get_test_server, TEST_GAME_B, TEST_GAME_E};
+use crate::{
+    models::{GamePlayStats, GameWithTags, PlaySession},
+    sessions::routes::{SessionEnd, SessionStart},
+};
+
+async fn start_session(srv: &actix_test::TestServer, game_id: &str, user_id: &str) -> PlaySession {
+    let req = srv
+        .post("/api/sessions/")
+        .insert_header(("frontend_api_key", "TESTING"));
+    let mut res = req
+        .send_json(&SessionStart {
+            game_id: game_id.to_string(),
+            cabinet_id: "test-cabinet".to_string(),
+            user_id: Some(user_id.to_string()),
+        })
+        .await
+        .unwrap();
+    assert_eq!(res.status().as_u16(), 201);
+    res.json::<PlaySession>().await.unwrap()
+}
+
+#[actix_web::test]
+async fn test_start_session_unauthorized() {
+    let srv = get_test_server().await;
+    let req = srv.post("/api/sessions/");
+    let res = req
+        .send_json(&SessionStart {
+            game_id: TEST_GAME_B.id.clone(),
+            cabinet_id: "test-cabinet".to_string(),
+            user_id: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(res.status().as_u16(), 401);
+}
+
+#[actix_web::test]
+async fn test_start_session_missing_game() {
+    let srv = get_test_server().await;
+    let req = srv
+        .post("/api/sessions/")
+        .insert_header(("frontend_api_key", "TESTING"));
+    let res = req
+        .send_json(&SessionStart {
+            game_id: "NOT A GAME".to_string(),
+            cabinet_id: "test-cabinet".to_string(),
+            user_id: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(res.status().as_u16(), 400);
+}
+
+#[actix_web::test]
+async fn test_end_session() {
+    let srv = get_test_server().await;
+    let session = start_session(&srv, &TEST_GAME_B.id, "qel").await;
+    let req = srv
+        .post(format!("/api/sessions/{}/end", session.id))
+        .insert_header(("frontend_api_key", "TESTING"));
+    let mut res = req
+        .send_json(&SessionEnd {
+            duration_seconds: Some(90),
+        })
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    let ended = res.json::<PlaySession>().await.unwrap();
+    assert_eq!(ended.duration_seconds, Some(90));
+    assert!(ended.ended_at.is_some());
+
+    let req = srv
+        .post(format!("/api/sessions/{}/end", session.id))
+        .insert_header(("frontend_api_key", "TESTING"));
+    let res = req
+        .send_json(&SessionEnd {
+            duration_seconds: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(res.status().as_u16(), 400);
+
+    let req = srv.get(format!("/api/sessions/stats/{}", TEST_GAME_B.id));
+    let mut res = req.send().await.unwrap();
+    assert!(res.status().is_success());
+    let stats = res.json::<GamePlayStats>().await.unwrap();
+    assert!(stats.plays >= 1);
+    assert!(stats.total_seconds >= 90);
+    assert!(stats.unique_players >= 1);
+    assert!(!stats.daily.is_empty());
+}
+
+#[actix_web::test]
+async fn test_get_all_stats() {
+    let srv = get_test_server().await;
+    let req = srv.get("/api/sessions/stats");
+    let res = req.send().await.unwrap();
+    assert!(res.status().is_success());
+}
+
+#[actix_web::test]
+async fn test_get_all_games_by_popularity() {
+    let srv = get_test_server().await;
+    for user in ["atom", "joeneil", "skyz"] {
+        start_session(&srv, &TEST_GAME_E.id, user).await;
+    }
+    let req = srv.get("/api/games/?sort=popularity");
+    let mut res = req.send().await.unwrap();
+    assert!(res.status().is_success());
+    let games = res.json::<Vec<GameWithTags>>().await.unwrap();
+    let position = |id: &str| games.iter().position(|game| game.id == id).unwrap();
+    assert!(position(&TEST_GAME_E.id) < position(&TEST_GAME_B.id));
+}
+
+#[actix_web::test]
+async fn test_session_invalid_input() {
+    let srv = get_test_server().await;
+    let req = srv
+        .post("/api/sessions/")
+        .insert_header(("frontend_api_key", "TESTING"));
+    let res = req
+        .send_json(&SessionStart {
+            game_id: TEST_GAME_B.id.clone(),
+            cabinet_id: "test-cabinet".to_string(),
+            user_id: Some("NOT A USER".to_string()),
+        })
+        .await
+        .unwrap();
+    assert_eq!(res.status().as_u16(), 400);
+
+    let session = start_session(&srv, &TEST_GAME_B.id, "qel").await;
+    let req = srv
+        .post(format!("/api/sessions/{}/end", session.id))
+        .insert_header(("frontend_api_key", "TESTING"));
+    let res = req
+        .send_json(&SessionEnd {
+            duration_seconds: Some(-90),
+        })
+        .await
+        .unwrap();
+    assert_eq!(res.status().as_u16(), 400);
+
+    let req = srv.get(format!("/api/sessions/stats/{}?days=-1", TEST_GAME_B.id));
+    let res = req.send().await.unwrap();
+    assert_eq!(res.status().as_u16(), 400);
+}
@@ -1,29 +1,179 @@
 use crate::{
-    models::{AppState, Game, Tag},
+    etag::{self, Precondition, Versioned},
+    events::notify,
+    logging::middleware::internal_error,
+    models::{AppState, CatalogEventType, Game, Tag, TagAssignment, TagCategory, TagWithUsage},
+    patch,
     security::RequireApiKey,
+    telemetry::query::Traced,
+    validate::ValidationErrors,
 };
 use actix_web::{
-    delete, get, post, put,
-    web::{Data, Json, Path},
-    HttpResponse, Responder,
+    delete, get,
+    http::StatusCode,
+    patch, post, put,
+    web::{Data, Json, Path, Query},
+    HttpRequest, HttpResponse, Responder,
 };
-use sqlx::{query, query_as};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{query, query_as, query_scalar, Pool, Postgres};
+use utoipa::{IntoParams, ToSchema};
+
+/// Fields of a tag a JSON Merge Patch may change
+const TAG_FIELDS: [&str; 7] = [
+    "name",
+    "description",
+    "category",
+    "parent",
+    "display_order",
+    "color",
+    "icon",
+];
+
+/// Binary downloads require an authenticated CSH user
+pub const AUTH_REQUIRED_TAG: &str = "authrequired";
+/// Excluded from public game listings
+pub const HIDDEN_TAG: &str = "hidden";
+/// Listed by the featured games endpoint
+pub const FEATURED_TAG: &str = "featured";
+/// Tags the server gives meaning to, which cannot be deleted or renamed
+pub const SYSTEM_TAGS: [&str; 3] = [AUTH_REQUIRED_TAG, HIDDEN_TAG, FEATURED_TAG];
+
+/// Condition on `game` carrying the system tag `tag`
+pub fn has_tag(tag: &str) -> String {
+    debug_assert!(SYSTEM_TAGS.contains(&tag));
+    format!(
+        "EXISTS (SELECT 1 FROM game_tags WHERE game_tags.game_id = game.id AND game_tags.tag_name = '{}')",
+        tag
+    )
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct TagListQuery {
+    /// Only tags in this category
+    category: Option<TagCategory>,
+    /// Only tags no game carries, to find tags to prune
+    unused: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TagMerge {
+    /// Tag receiving the games of the merged tag
+    #[schema(example = "action")]
+    pub into: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct GameIds {
+    #[schema(example = json!(["a1c6cef6-d987-4225-8bc4-def387e8b5bf"]))]
+    pub games: Vec<String>,
+}
+
+/// Name of the tag called `name`, or of the tag it was renamed or merged into
+pub async fn resolve_tag(db: &Pool<Postgres>, name: &str) -> Result<Option<String>, sqlx::Error> {
+    query_scalar::<_, Option<String>>(
+        "
+        SELECT COALESCE(
+            (SELECT name FROM tags WHERE name = $1),
+            (SELECT tag_name FROM tag_aliases WHERE alias = $1)
+        )
+        ",
+    )
+    .bind(name)
+    .fetch_one(Traced(db))
+    .await
+}
+
+/// Query of the names of the tag bound to `param`, resolving aliases, and every tag below it
+pub fn tag_and_descendants(param: &str) -> String {
+    format!(
+        "
+        WITH RECURSIVE descendants AS (
+            SELECT name FROM tags
+            WHERE name = {0} OR name = (SELECT tag_name FROM tag_aliases WHERE alias = {0})
+            UNION
+            SELECT tags.name FROM tags JOIN descendants ON tags.parent = descendants.name
+        )
+        SELECT name FROM descendants
+        ",
+        param
+    )
+}
+
+/// Every problem with the fields of `tag`, including a parent that does not exist
+async fn validate_tag(db: &Pool<Postgres>, tag: &Tag) -> Result<ValidationErrors, sqlx::Error> {
+    let mut errors = ValidationErrors::of(tag);
+    if let Some(parent) = &tag.parent {
+        errors
+            .tags_exist(db, "parent", std::slice::from_ref(parent))
+            .await?;
+    }
+    Ok(errors)
+}
+
+/// Point the parent of `tag` at the tag it names, when it is the old name of one
+async fn resolve_parent(db: &Pool<Postgres>, tag: &mut Tag) -> Result<(), sqlx::Error> {
+    if let Some(parent) = &tag.parent {
+        if let Some(resolved) = resolve_tag(db, parent).await? {
+            tag.parent = Some(resolved);
+        }
+    }
+    Ok(())
+}
+
+/// Check the display fields of a tag, and that its parent does not sit below the tag itself
+async fn check_tag(
+    db: &Pool<Postgres>,
+    tag: &Tag,
+    current: &str,
+) -> Result<Option<String>, sqlx::Error> {
+    if let Some(parent) = &tag.parent {
+        if parent == current || parent == &tag.name {
+            return Ok(Some("Tag Cannot Be Its Own Parent".to_string()));
+        }
+        let below =
+            query_scalar::<_, bool>(&format!("SELECT $1 IN ({})", tag_and_descendants("$2")))
+                .bind(parent)
+                .bind(current)
+                .fetch_one(Traced(db))
+                .await?;
+        if below {
+            return Ok(Some("Parent Tag Cannot Be Below The Tag".to_string()));
+        }
+    }
+    Ok(None)
+}
 
 #[utoipa::path(
     context_path = "/tags",
+    params(TagListQuery),
     responses(
-        (status = 200, description = "List all tags", body = [Tag]),
+        (status = 200, description = "List all tags with the number of games carrying them, grouped by category and in display order", body = [TagWithUsage]),
         (status = 500, description = "Error Created by Query"),
     )
 )]
 #[get("/")]
-pub async fn get_all_tags(state: Data<AppState>) -> impl Responder {
-    match query_as::<_, Tag>("SELECT * FROM tags ORDER BY name ASC")
-        .fetch_all(&state.db)
-        .await
+pub async fn get_all_tags(state: Data<AppState>, params: Query<TagListQuery>) -> impl Responder {
+    match query_as::<_, TagWithUsage>(
+        "
+        SELECT * FROM (
+            SELECT tags.*,
+                (SELECT COUNT(*) FROM game_tags WHERE game_tags.tag_name = tags.name) AS usage_count
+            FROM tags
+        ) tags
+        WHERE ($1::tagcategory IS NULL OR category = $1)
+            AND (NOT COALESCE($2, false) OR usage_count = 0)
+        ORDER BY category ASC NULLS LAST, display_order ASC, name ASC
+        ",
+    )
+    .bind(params.category)
+    .bind(params.unused)
+    .fetch_all(Traced(&state.db))
+    .await
     {
         Ok(tags) => HttpResponse::Ok().json(tags),
-        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(e) => internal_error("could not fetch tags", e),
     }
 }
 
@@ -31,8 +181,10 @@ pub async fn get_all_tags(state: Data<AppState>) -> impl Responder {
     context_path = "/tags",
     request_body(content=Tag, content_type="application/json", description="Tag Information"),
     responses(
-        (status = 201, description = "Created new tag"),
+        (status = 201, description = "Created new tag", body = Tag),
+        (status = 400, description = "Tag would be its own parent"),
         (status = 401, description = "Invalid/Missing API Key"),
+        (status = 422, description = "Invalid fields or missing parent, by field", body = ValidationErrors),
         (status = 500, description = "Error Created by Query"),
     ),
     security(
@@ -41,36 +193,100 @@ pub async fn get_all_tags(state: Data<AppState>) -> impl Responder {
 )]
 #[post("/", wrap = "RequireApiKey")]
 pub async fn add_tag(state: Data<AppState>, tag: Json<Tag>) -> impl Responder {
-    match query("INSERT INTO tags VALUES ($1, $2)")
+    let mut tag = tag.into_inner();
+    if let Err(e) = resolve_parent(&state.db, &mut tag).await {
+        return internal_error("could not resolve parent tag", e);
+    }
+    match validate_tag(&state.db, &tag).await {
+        Ok(errors) if errors.is_empty() => {}
+        Ok(errors) => return errors.response(),
+        Err(e) => return internal_error("could not validate tag", e),
+    }
+    match check_tag(&state.db, &tag, &tag.name).await {
+        Ok(None) => {}
+        Ok(Some(reason)) => return HttpResponse::BadRequest().body(reason),
+        Err(e) => return internal_error("could not check tag", e),
+    }
+    let mut transaction = match state.db.begin().await {
+        Ok(transaction) => transaction,
+        Err(e) => return internal_error("could not begin transaction", e),
+    };
+    let tag = match query_as::<_, Tag>(
+        "
+        INSERT INTO tags (name, description, category, parent, display_order, color, icon)
+        VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING *
+        ",
+    )
+    .bind(&tag.name)
+    .bind(&tag.description)
+    .bind(tag.category)
+    .bind(&tag.parent)
+    .bind(tag.display_order)
+    .bind(&tag.color)
+    .bind(&tag.icon)
+    .fetch_one(Traced(&mut transaction))
+    .await
+    {
+        Ok(tag) => tag,
+        Err(sqlx::Error::Database(e)) if e.code().as_deref() == Some("23503") => {
+            let _ = transaction.rollback().await;
+            return HttpResponse::BadRequest().body("Parent Tag Does Not Exist");
+        }
+        Err(e) => {
+            let _ = transaction.rollback().await;
+            return internal_error("could not add tag", e);
+        }
+    };
+    // A new tag takes its name back from any tag it was an alias of
+    if let Err(e) = query("DELETE FROM tag_aliases WHERE alias = $1")
         .bind(&tag.name)
-        .bind(&tag.description)
-        .execute(&state.db)
+        .execute(Traced(&mut transaction))
         .await
     {
-        Ok(_) => HttpResponse::Created().json(Tag {
-            name: tag.name.clone(),
-            description: tag.description.clone(),
-        }),
-        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+        let _ = transaction.rollback().await;
+        return internal_error("could not free alias of new tag", e);
     }
+    if let Err(e) = notify::publish(
+        &mut transaction,
+        CatalogEventType::TagCreated,
+        None,
+        Some(&tag.name),
+    )
+    .await
+    {
+        let _ = transaction.rollback().await;
+        return internal_error("could not publish catalog event", e);
+    }
+    if let Err(e) = transaction.commit().await {
+        return internal_error("could not commit tag", e);
+    }
+    HttpResponse::Created().json(tag)
 }
 
 #[utoipa::path(
     context_path = "/tags",
     responses(
-        (status = 200, description = "Get specified tag", body = Tag),
+        (status = 200, description = "Get specified tag, following renames and merges, with its version in the ETag header", body = Tag),
+        (status = 400, description = "Missing tag"),
         (status = 500, description = "Error Created by Query"),
     )
 )]
 #[get("/{tag}")]
 pub async fn get_tag(state: Data<AppState>, path: Path<(String,)>) -> impl Responder {
     let (name,) = path.into_inner();
-    match query_as::<_, Tag>("SELECT * FROM tags WHERE name = $1")
+    let name = match resolve_tag(&state.db, &name).await {
+        Ok(Some(name)) => name,
+        Ok(None) => return HttpResponse::BadRequest().body("Tag Does Not Exist"),
+        Err(e) => return internal_error("could not resolve tag", e),
+    };
+    match query_as::<_, Versioned<Tag>>("SELECT * FROM tags WHERE name = $1")
         .bind(name)
-        .fetch_one(&state.db)
+        .fetch_one(Traced(&state.db))
         .await
     {
-        Ok(tags) => HttpResponse::Ok().json(tags),
+        Ok(tag) => HttpResponse::Ok()
+            .insert_header(etag::etag(&tag.updated_at))
+            .json(tag.resource),
         Err(e) => HttpResponse::BadRequest().body(e.to_string()),
     }
 }
@@ -79,7 +295,7 @@ pub async fn get_tag(state: Data<AppState>, path: Path<(String,)>) -> impl Respo
     context_path = "/tags",
     responses(
         (status = 200, description = "Delete tag"),
-        (status = 400, description = "Missing tag"),
+        (status = 400, description = "Missing tag or system tag"),
         (status = 401, description = "Invalid/Missing API Key"),
         (status = 500, description = "Error Created by Deletion"),
     ),
@@ -93,32 +309,72 @@ pub async fn get_tag(state: Data<AppState>, path: Path<(String,)>) -> impl Respo
 #[delete("/{tag}", wrap = "RequireApiKey")]
 pub async fn delete_tag(state: Data<AppState>, path: Path<(String,)>) -> impl Responder {
     let (name,) = path.into_inner();
+    if SYSTEM_TAGS.contains(&name.as_str()) {
+        return HttpResponse::BadRequest().body("System Tags Cannot Be Deleted");
+    }
     if query_as::<_, Tag>("SELECT * FROM tags WHERE name = $1")
         .bind(&name)
-        .fetch_one(&state.db)
+        .fetch_one(Traced(&state.db))
         .await
         .is_err()
     {
         return HttpResponse::BadRequest().body("Tag Does Not Exist");
     }
-    match query("DELETE FROM tags WHERE name = $1")
+    let mut transaction = match state.db.begin().await {
+        Ok(transaction) => transaction,
+        Err(e) => return internal_error("could not begin transaction", e),
+    };
+    if let Err(e) = query(
+        "UPDATE game SET updated_at = now() WHERE id IN (SELECT game_id FROM game_tags WHERE tag_name = $1)",
+    )
+    .bind(&name)
+    .execute(Traced(&mut transaction))
+    .await
+    {
+        let _ = transaction.rollback().await;
+        return internal_error("could not touch tagged games", e);
+    }
+    if let Err(e) = query("DELETE FROM tags WHERE name = $1")
         .bind(&name)
-        .execute(&state.db)
+        .execute(Traced(&mut transaction))
         .await
     {
-        Ok(_) => HttpResponse::Ok().finish(),
-        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+        let _ = transaction.rollback().await;
+        return internal_error("could not delete tag", e);
     }
+    if let Err(e) = notify::publish(
+        &mut transaction,
+        CatalogEventType::TagDeleted,
+        None,
+        Some(&name),
+    )
+    .await
+    {
+        let _ = transaction.rollback().await;
+        return internal_error("could not publish catalog event", e);
+    }
+    if let Err(e) = transaction.commit().await {
+        return internal_error("could not commit tag deletion", e);
+    }
+    HttpResponse::Ok().finish()
 }
 
 #[utoipa::path(
     context_path = "/tags",
     request_body(content=Tag, content_type="application/json", description="Tag Information"),
     responses(
-        (status = 201, description = "Updated tag"),
+        (status = 201, description = "Updated tag, a renamed tag keeps its old name as an alias", body = Tag),
+        (status = 400, description = "Missing tag, the parent sits below the tag, or renaming a system tag"),
         (status = 401, description = "Invalid/Missing API Key"),
+        (status = 412, description = "Tag changed since the version in If-Match"),
+        (status = 422, description = "Invalid fields or missing parent, by field", body = ValidationErrors),
+        (status = 428, description = "Missing If-Match header"),
         (status = 500, description = "Error Created by Query"),
     ),
+    params(
+        ("tag", description = "Tag to change"),
+        ("If-Match" = String, Header, description = "ETag of the version being edited, or * for any version"),
+    ),
     security(
         ("api_key" = [])
     )
@@ -128,55 +384,477 @@ pub async fn edit_tag(
     state: Data<AppState>,
     path: Path<(String,)>,
     tag: Json<Tag>,
+    req: HttpRequest,
 ) -> impl Responder {
     let (name,) = path.into_inner();
-    if query_as::<_, Tag>("SELECT * FROM tags WHERE name = $1")
+    let precondition = match Precondition::from_request(&req) {
+        Ok(precondition) => precondition,
+        Err(res) => return res,
+    };
+    match query_as::<_, Versioned<Tag>>("SELECT * FROM tags WHERE name = $1")
         .bind(&name)
-        .fetch_one(&state.db)
+        .fetch_optional(Traced(&state.db))
         .await
-        .is_err()
     {
-        return HttpResponse::BadRequest().body("Tag Does Not Exist");
+        Ok(Some(current)) if precondition.matches(&current.updated_at) => {}
+        Ok(Some(_)) => return etag::precondition_failed(),
+        Ok(None) => return HttpResponse::BadRequest().body("Tag Does Not Exist"),
+        Err(e) => return internal_error("could not fetch tag", e),
     }
-    match query("UPDATE tags SET name = $1, description = $2 WHERE name = $3")
-        .bind(&tag.name)
-        .bind(&tag.description)
+    update_tag(
+        &state,
+        &name,
+        tag.into_inner(),
+        &precondition,
+        StatusCode::CREATED,
+    )
+    .await
+}
+
+#[utoipa::path(
+    context_path = "/tags",
+    request_body(content=Object, content_type="application/merge-patch+json", description="JSON Merge Patch of the tag fields to change"),
+    responses(
+        (status = 200, description = "Updated tag, a renamed tag keeps its old name as an alias", body = Tag),
+        (status = 400, description = "Missing tag, invalid patch, the parent sits below the tag, or renaming a system tag"),
+        (status = 401, description = "Invalid/Missing API Key"),
+        (status = 412, description = "Tag changed since the version in If-Match"),
+        (status = 422, description = "Invalid fields or missing parent, by field", body = ValidationErrors),
+        (status = 428, description = "Missing If-Match header"),
+        (status = 500, description = "Error Created by Query"),
+    ),
+    params(
+        ("tag", description = "Tag to change"),
+        ("If-Match" = String, Header, description = "ETag of the version being edited, or * for any version"),
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[patch("/{tag}", wrap = "RequireApiKey")]
+pub async fn patch_tag(
+    state: Data<AppState>,
+    path: Path<(String,)>,
+    tag_patch: Json<Value>,
+    req: HttpRequest,
+) -> impl Responder {
+    let (name,) = path.into_inner();
+    let precondition = match Precondition::from_request(&req) {
+        Ok(precondition) => precondition,
+        Err(res) => return res,
+    };
+    let current = match query_as::<_, Versioned<Tag>>("SELECT * FROM tags WHERE name = $1")
         .bind(&name)
-        .execute(&state.db)
+        .fetch_optional(Traced(&state.db))
+        .await
+    {
+        Ok(Some(current)) if precondition.matches(&current.updated_at) => current,
+        Ok(Some(_)) => return etag::precondition_failed(),
+        Ok(None) => return HttpResponse::BadRequest().body("Tag Does Not Exist"),
+        Err(e) => return internal_error("could not fetch tag", e),
+    };
+    let tag = match patch::apply(&current.resource, &tag_patch, &TAG_FIELDS) {
+        Ok(tag) => tag,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+    update_tag(&state, &name, tag, &precondition, StatusCode::OK).await
+}
+
+/// Replace the tag called `name` with `tag`, keeping the old name as an alias on renames, answering with `status`
+async fn update_tag(
+    state: &AppState,
+    name: &str,
+    mut tag: Tag,
+    precondition: &Precondition,
+    status: StatusCode,
+) -> HttpResponse {
+    if SYSTEM_TAGS.contains(&name) && tag.name != name {
+        return HttpResponse::BadRequest().body("System Tags Cannot Be Renamed");
+    }
+    if let Err(e) = resolve_parent(&state.db, &mut tag).await {
+        return internal_error("could not resolve parent tag", e);
+    }
+    match validate_tag(&state.db, &tag).await {
+        Ok(errors) if errors.is_empty() => {}
+        Ok(errors) => return errors.response(),
+        Err(e) => return internal_error("could not validate tag", e),
+    }
+    match check_tag(&state.db, &tag, name).await {
+        Ok(None) => {}
+        Ok(Some(reason)) => return HttpResponse::BadRequest().body(reason),
+        Err(e) => return internal_error("could not check tag", e),
+    }
+    let mut transaction = match state.db.begin().await {
+        Ok(transaction) => transaction,
+        Err(e) => return internal_error("could not begin transaction", e),
+    };
+    if let Err(e) = query(
+        "UPDATE game SET updated_at = now() WHERE id IN (SELECT game_id FROM game_tags WHERE tag_name = $1)",
+    )
+    .bind(name)
+    .execute(Traced(&mut transaction))
+    .await
+    {
+        let _ = transaction.rollback().await;
+        return internal_error("could not touch tagged games", e);
+    }
+    let (tag, updated_at) = match query_as::<_, Versioned<Tag>>(
+        "
+        UPDATE tags SET name = $1, description = $2, category = $3, parent = $4,
+            display_order = $5, color = $6, icon = $7, updated_at = now()
+        WHERE name = $8 AND ($9::timestamptz[] IS NULL OR updated_at = ANY($9))
+        RETURNING *
+        ",
+    )
+    .bind(&tag.name)
+    .bind(&tag.description)
+    .bind(tag.category)
+    .bind(&tag.parent)
+    .bind(tag.display_order)
+    .bind(&tag.color)
+    .bind(&tag.icon)
+    .bind(name)
+    .bind(precondition.versions())
+    .fetch_optional(Traced(&mut transaction))
+    .await
+    {
+        Ok(Some(Versioned {
+            resource: tag,
+            updated_at,
+        })) => (tag, updated_at),
+        Ok(None) => {
+            let _ = transaction.rollback().await;
+            return etag::precondition_failed();
+        }
+        Err(sqlx::Error::Database(e)) if e.code().as_deref() == Some("23503") => {
+            let _ = transaction.rollback().await;
+            return HttpResponse::BadRequest().body("Parent Tag Does Not Exist");
+        }
+        Err(e) => {
+            let _ = transaction.rollback().await;
+            return internal_error("could not update tag", e);
+        }
+    };
+    if tag.name != name {
+        // Keep the old name resolving to the renamed tag
+        if let Err(e) = query(
+            "
+            WITH freed AS (DELETE FROM tag_aliases WHERE alias = $2)
+            INSERT INTO tag_aliases (alias, tag_name) VALUES ($1, $2)
+            ON CONFLICT (alias) DO UPDATE SET tag_name = $2
+            ",
+        )
+        .bind(name)
+        .bind(&tag.name)
+        .execute(Traced(&mut transaction))
         .await
+        {
+            let _ = transaction.rollback().await;
+            return internal_error("could not save tag alias", e);
+        }
+    }
+    if let Err(e) = notify::publish(
+        &mut transaction,
+        CatalogEventType::TagUpdated,
+        None,
+        Some(&tag.name),
+    )
+    .await
     {
-        Ok(_) => HttpResponse::Created().json(Tag {
-            name: tag.name.clone(),
-            description: tag.description.clone(),
-        }),
-        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+        let _ = transaction.rollback().await;
+        return internal_error("could not publish catalog event", e);
+    }
+    if let Err(e) = transaction.commit().await {
+        return internal_error("could not commit tag", e);
     }
+    HttpResponse::build(status)
+        .insert_header(etag::etag(&updated_at))
+        .json(tag)
 }
 
 #[utoipa::path(
     context_path = "/tags",
     responses(
-        (status = 200, description = "Get games with tag", body = [Game]),
+        (status = 200, description = "Get games with the tag or any tag below it, except hidden games", body = [Game]),
+        (status = 400, description = "Missing tag"),
         (status = 500, description = "Error Created by Query"),
     )
 )]
 #[get("/{tag}/games")]
 pub async fn get_tag_games(state: Data<AppState>, path: Path<(String,)>) -> impl Responder {
     let (name,) = path.into_inner();
-    if query_as::<_, Tag>("SELECT * FROM tags WHERE name = $1")
+    match resolve_tag(&state.db, &name).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return HttpResponse::BadRequest().body("Tag Does Not Exist"),
+        Err(e) => return internal_error("could not resolve tag", e),
+    }
+    match query_as::<_, Game>(&format!(
+        "SELECT game.* FROM game LEFT JOIN game_tags ON game_tags.game_id = game.id WHERE game_tags.tag_name IN ({}) AND NOT {} GROUP BY game.id ORDER BY name ASC",
+        tag_and_descendants("$1"),
+        has_tag(HIDDEN_TAG)
+    ))
+    .bind(name)
+    .fetch_all(Traced(&state.db))
+    .await
+    {
+        Ok(games) => HttpResponse::Ok().json(games),
+        Err(e) => internal_error("could not fetch tag games", e),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/tags",
+    request_body(content=TagMerge, content_type="application/json", description="Tag to merge into"),
+    responses(
+        (status = 200, description = "Moved the games and child tags of the tag to the target and deleted it, its name now resolves to the target", body = Tag),
+        (status = 400, description = "Missing tag or target, merging a tag into itself, or merging away a system tag"),
+        (status = 401, description = "Invalid/Missing API Key"),
+        (status = 500, description = "Error Created by Query"),
+    ),
+    params(
+        ("tag", description = "Tag to merge away")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[post("/{tag}/merge", wrap = "RequireApiKey")]
+pub async fn merge_tag(
+    state: Data<AppState>,
+    path: Path<(String,)>,
+    merge: Json<TagMerge>,
+) -> impl Responder {
+    let (name,) = path.into_inner();
+    let name = match resolve_tag(&state.db, &name).await {
+        Ok(Some(name)) => name,
+        Ok(None) => return HttpResponse::BadRequest().body("Tag Does Not Exist"),
+        Err(e) => return internal_error("could not resolve tag", e),
+    };
+    if SYSTEM_TAGS.contains(&name.as_str()) {
+        return HttpResponse::BadRequest().body("System Tags Cannot Be Merged Away");
+    }
+    let target = match resolve_tag(&state.db, &merge.into).await {
+        Ok(Some(target)) => target,
+        Ok(None) => return HttpResponse::BadRequest().body("Target Tag Does Not Exist"),
+        Err(e) => return internal_error("could not resolve target tag", e),
+    };
+    if name == target {
+        return HttpResponse::BadRequest().body("Tag Cannot Be Merged Into Itself");
+    }
+    let mut transaction = match state.db.begin().await {
+        Ok(transaction) => transaction,
+        Err(e) => return internal_error("could not begin transaction", e),
+    };
+    for statement in [
+        "UPDATE game SET updated_at = now() WHERE id IN (SELECT game_id FROM game_tags WHERE tag_name = $1)",
+        "
+        INSERT INTO game_tags (game_id, tag_name)
+        SELECT game_id, $2 FROM game_tags WHERE tag_name = $1
+        ON CONFLICT DO NOTHING
+        ",
+        "UPDATE tags SET parent = $2 WHERE parent = $1 AND name <> $2",
+        "UPDATE tag_aliases SET tag_name = $2 WHERE tag_name = $1",
+    ] {
+        if let Err(e) = query(statement)
+            .bind(&name)
+            .bind(&target)
+            .execute(Traced(&mut transaction))
+            .await
+        {
+            let _ = transaction.rollback().await;
+            return internal_error("could not move tag onto target", e);
+        }
+    }
+    match query("DELETE FROM tags WHERE name = $1")
         .bind(&name)
-        .fetch_one(&state.db)
+        .execute(Traced(&mut transaction))
         .await
-        .is_err()
     {
-        return HttpResponse::BadRequest().body("Tag Does Not Exist");
+        Ok(result) if result.rows_affected() == 0 => {
+            let _ = transaction.rollback().await;
+            return HttpResponse::BadRequest().body("Tag Does Not Exist");
+        }
+        Ok(_) => {}
+        Err(e) => {
+            let _ = transaction.rollback().await;
+            return internal_error("could not delete merged tag", e);
+        }
     }
-    match query_as::<_, Game>("SELECT game.* FROM game LEFT JOIN game_tags ON game_tags.game_id = game.id LEFT JOIN tags ON tags.name = game_tags.tag_name WHERE game_tags.tag_name = $1 GROUP BY game.id ORDER BY name ASC")
-        .bind(name)
-        .fetch_all(&state.db)
+    if let Err(e) = query("INSERT INTO tag_aliases (alias, tag_name) VALUES ($1, $2)")
+        .bind(&name)
+        .bind(&target)
+        .execute(Traced(&mut transaction))
         .await
     {
-        Ok(games) => HttpResponse::Ok().json(games),
-        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+        let _ = transaction.rollback().await;
+        return internal_error("could not save tag alias", e);
+    }
+    for (event_type, tag_name) in [
+        (CatalogEventType::TagDeleted, &name),
+        (CatalogEventType::TagUpdated, &target),
+    ] {
+        if let Err(e) = notify::publish(&mut transaction, event_type, None, Some(tag_name)).await {
+            let _ = transaction.rollback().await;
+            return internal_error("could not publish catalog event", e);
+        }
+    }
+    if let Err(e) = transaction.commit().await {
+        return internal_error("could not commit tag merge", e);
+    }
+    match query_as::<_, Tag>("SELECT * FROM tags WHERE name = $1")
+        .bind(&target)
+        .fetch_one(Traced(&state.db))
+        .await
+    {
+        Ok(tag) => HttpResponse::Ok().json(tag),
+        Err(e) => internal_error("could not fetch target tag", e),
+    }
+}
+
+/// Attach or detach the tag from every game in `games` in one transaction
+async fn assign_games(
+    state: &AppState,
+    name: &str,
+    games: &[String],
+    attach: bool,
+) -> HttpResponse {
+    let name = match resolve_tag(&state.db, name).await {
+        Ok(Some(name)) => name,
+        Ok(None) => return HttpResponse::BadRequest().body("Tag Does Not Exist"),
+        Err(e) => return internal_error("could not resolve tag", e),
+    };
+    let mut transaction = match state.db.begin().await {
+        Ok(transaction) => transaction,
+        Err(e) => return internal_error("could not begin transaction", e),
+    };
+    let missing = match query_scalar::<_, String>(
+        "
+        SELECT DISTINCT ids.id FROM unnest($1::text[]) ids(id)
+        WHERE NOT EXISTS (SELECT 1 FROM game WHERE game.id = ids.id)
+        ",
+    )
+    .bind(games)
+    .fetch_all(Traced(&mut transaction))
+    .await
+    {
+        Ok(missing) => missing,
+        Err(e) => {
+            let _ = transaction.rollback().await;
+            return internal_error("could not find missing games", e);
+        }
+    };
+    if !missing.is_empty() {
+        let _ = transaction.rollback().await;
+        return HttpResponse::BadRequest().json(TagAssignment {
+            added: vec![],
+            removed: vec![],
+            missing,
+        });
     }
+    let statement = if attach {
+        "
+        INSERT INTO game_tags (game_id, tag_name) SELECT DISTINCT unnest($1::text[]), $2
+        ON CONFLICT DO NOTHING RETURNING game_id
+        "
+    } else {
+        "DELETE FROM game_tags WHERE game_id = ANY($1::text[]) AND tag_name = $2 RETURNING game_id"
+    };
+    let changed = match query_scalar::<_, String>(statement)
+        .bind(games)
+        .bind(&name)
+        .fetch_all(Traced(&mut transaction))
+        .await
+    {
+        Ok(changed) => changed,
+        Err(e) => {
+            let _ = transaction.rollback().await;
+            return internal_error("could not change game tags", e);
+        }
+    };
+    if let Err(e) = query("UPDATE game SET updated_at = now() WHERE id = ANY($1::text[])")
+        .bind(&changed)
+        .execute(Traced(&mut transaction))
+        .await
+    {
+        let _ = transaction.rollback().await;
+        return internal_error("could not touch tagged games", e);
+    }
+    for id in &changed {
+        if let Err(e) = notify::publish(
+            &mut transaction,
+            CatalogEventType::GameUpdated,
+            Some(id),
+            None,
+        )
+        .await
+        {
+            let _ = transaction.rollback().await;
+            return internal_error("could not publish catalog event", e);
+        }
+    }
+    if let Err(e) = transaction.commit().await {
+        return internal_error("could not commit game tags", e);
+    }
+    let (added, removed) = if attach {
+        (changed, vec![])
+    } else {
+        (vec![], changed)
+    };
+    HttpResponse::Ok().json(TagAssignment {
+        added,
+        removed,
+        missing: vec![],
+    })
+}
+
+#[utoipa::path(
+    context_path = "/tags",
+    request_body(content=GameIds, content_type="application/json", description="Games to tag"),
+    responses(
+        (status = 200, description = "Tagged every game, lists the games that did not have the tag yet", body = TagAssignment),
+        (status = 400, description = "Missing tag, or missing games which are listed and nothing is tagged", body = TagAssignment),
+        (status = 401, description = "Invalid/Missing API Key"),
+        (status = 500, description = "Error Created by Query"),
+    ),
+    params(
+        ("tag", description = "Tag to attach")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[post("/{tag}/games", wrap = "RequireApiKey")]
+pub async fn add_tag_games(
+    state: Data<AppState>,
+    path: Path<(String,)>,
+    games: Json<GameIds>,
+) -> impl Responder {
+    let (name,) = path.into_inner();
+    assign_games(&state, &name, &games.games, true).await
+}
+
+#[utoipa::path(
+    context_path = "/tags",
+    request_body(content=GameIds, content_type="application/json", description="Games to untag"),
+    responses(
+        (status = 200, description = "Untagged every game, lists the games that had the tag", body = TagAssignment),
+        (status = 400, description = "Missing tag, or missing games which are listed and nothing is untagged", body = TagAssignment),
+        (status = 401, description = "Invalid/Missing API Key"),
+        (status = 500, description = "Error Created by Query"),
+    ),
+    params(
+        ("tag", description = "Tag to detach")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[delete("/{tag}/games", wrap = "RequireApiKey")]
+pub async fn delete_tag_games(
+    state: Data<AppState>,
+    path: Path<(String,)>,
+    games: Json<GameIds>,
+) -> impl Responder {
+    let (name,) = path.into_inner();
+    assign_games(&state, &name, &games.games, false).await
 }
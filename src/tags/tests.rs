@@ -1,6 +1,13 @@
-use crate::models::Tag;
 #[cfg(test)]
-use crate::tests::{get_test_server, TEST_TAG_1, TEST_TAG_2, TEST_TAG_3, TEST_TAG_4, TEST_TAG_6};
+use crate::tests::{
+    get_test_server, TEST_GAME_B, TEST_GAME_L, TEST_GAME_M, TEST_TAG_1, TEST_TAG_2, TEST_TAG_3,
+    TEST_TAG_4, TEST_TAG_6, TEST_TAG_HIERARCHY,
+};
+use crate::{
+    games::routes::GameTagsPatch,
+    models::{Game, GameWithTags, Tag, TagAssignment, TagCategory, TagWithUsage},
+    tags::routes::{GameIds, TagMerge},
+};
 
 #[actix_web::test]
 async fn test_get_all_tags() {
@@ -36,7 +43,8 @@ async fn test_edit_tag() {
     edited_tag.description = "I changed the description!".to_string();
     let req = srv
         .put(format!("/tags/{}", TEST_TAG_2.name))
-        .insert_header(("frontend_api_key", "TESTING"));
+        .insert_header(("frontend_api_key", "TESTING"))
+        .insert_header(("If-Match", "*"));
     let mut res = req.send_json(&edited_tag).await.unwrap();
     println!(
         "{} | {}",
@@ -99,6 +107,7 @@ async fn test_add_tag() {
     let new_tag = Tag {
         name: "NEW_TAG".to_string(),
         description: "THIS IS A NEW TAG".to_string(),
+        ..Default::default()
     };
     let mut res = req.send_json(&new_tag).await.unwrap();
     println!(
@@ -142,3 +151,385 @@ async fn test_get_tag_games_some() {
     );
     assert!(res.status().is_success());
 }
+
+#[actix_web::test]
+async fn test_tag_hierarchy() {
+    let srv = get_test_server().await;
+    let action = Tag {
+        name: "HierAction".to_string(),
+        description: "Fast paced games".to_string(),
+        category: Some(TagCategory::Genre),
+        display_order: 1,
+        color: Some("#e11c52".to_string()),
+        ..Default::default()
+    };
+    let platformer = Tag {
+        name: "HierPlatformer".to_string(),
+        description: "Jump between platforms".to_string(),
+        category: Some(TagCategory::Genre),
+        parent: Some(action.name.clone()),
+        ..Default::default()
+    };
+    for tag in [&action, &platformer] {
+        let req = srv
+            .post("/api/tags/")
+            .insert_header(("frontend_api_key", "TESTING"));
+        let mut res = req.send_json(tag).await.unwrap();
+        assert_eq!(res.status().as_u16(), 201);
+        assert_eq!(res.json::<Tag>().await.unwrap(), *tag);
+    }
+    let mut leaf = TEST_TAG_HIERARCHY.clone();
+    leaf.parent = Some(platformer.name.clone());
+    let req = srv
+        .put(format!("/api/tags/{}", TEST_TAG_HIERARCHY.name))
+        .insert_header(("frontend_api_key", "TESTING"))
+        .insert_header(("If-Match", "*"));
+    assert!(req.send_json(&leaf).await.unwrap().status().is_success());
+
+    let req = srv.get(format!("/api/tags/{}/games", action.name));
+    let mut res = req.send().await.unwrap();
+    let games = res.json::<Vec<Game>>().await.unwrap();
+    assert!(games.iter().any(|game| game.id == TEST_GAME_M.id));
+    let req = srv.get(format!("/api/games/?tag={}", action.name));
+    let mut res = req.send().await.unwrap();
+    let games = res.json::<Vec<GameWithTags>>().await.unwrap();
+    assert!(games.iter().all(|game| game.id == TEST_GAME_M.id));
+    assert_eq!(games.len(), 1);
+
+    let req = srv.get("/api/tags/?category=genre");
+    let mut res = req.send().await.unwrap();
+    let tags = res.json::<Vec<Tag>>().await.unwrap();
+    assert!(tags
+        .iter()
+        .all(|tag| tag.category == Some(TagCategory::Genre)));
+    let position = |name: &str| tags.iter().position(|tag| tag.name == name).unwrap();
+    assert!(position(&platformer.name) < position(&action.name));
+
+    let mut cycle = action.clone();
+    cycle.parent = Some(TEST_TAG_HIERARCHY.name.clone());
+    let req = srv
+        .put(format!("/api/tags/{}", action.name))
+        .insert_header(("frontend_api_key", "TESTING"))
+        .insert_header(("If-Match", "*"));
+    assert_eq!(req.send_json(&cycle).await.unwrap().status().as_u16(), 400);
+    let mut bad_color = action.clone();
+    bad_color.color = Some("red".to_string());
+    let req = srv
+        .put(format!("/api/tags/{}", action.name))
+        .insert_header(("frontend_api_key", "TESTING"))
+        .insert_header(("If-Match", "*"));
+    assert_eq!(
+        req.send_json(&bad_color).await.unwrap().status().as_u16(),
+        422
+    );
+}
+
+async fn create_tag(srv: &actix_test::TestServer, name: &str) {
+    let req = srv
+        .post("/api/tags/")
+        .insert_header(("frontend_api_key", "TESTING"));
+    let res = req
+        .send_json(&Tag {
+            name: name.to_string(),
+            description: format!("{} Description", name),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+}
+
+#[actix_web::test]
+async fn test_rename_tag_alias() {
+    let srv = get_test_server().await;
+    create_tag(&srv, "AliasOld").await;
+    let req = srv
+        .put("/api/tags/AliasOld")
+        .insert_header(("frontend_api_key", "TESTING"))
+        .insert_header(("If-Match", "*"));
+    let res = req
+        .send_json(&Tag {
+            name: "AliasNew".to_string(),
+            description: "Renamed".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    let req = srv.get("/api/tags/AliasOld");
+    let mut res = req.send().await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(res.json::<Tag>().await.unwrap().name, "AliasNew");
+
+    // The old name is accepted wherever a tag is named
+    let req = srv
+        .post("/api/tags/")
+        .insert_header(("frontend_api_key", "TESTING"));
+    let mut res = req
+        .send_json(&Tag {
+            name: "AliasChild".to_string(),
+            description: "Below the renamed tag".to_string(),
+            parent: Some("AliasOld".to_string()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(res.status().as_u16(), 201);
+    assert_eq!(
+        res.json::<Tag>().await.unwrap().parent.as_deref(),
+        Some("AliasNew")
+    );
+}
+
+#[actix_web::test]
+async fn test_merge_tag() {
+    let srv = get_test_server().await;
+    create_tag(&srv, "MergeSrc").await;
+    create_tag(&srv, "MergeDst").await;
+    create_tag(&srv, "MergeUnused").await;
+    let game_g = "GGGGGGGG-GGGG-GGGG-GGGG-GGGGGGGGGGGG";
+    let req = srv
+        .put(format!("/api/games/{}", game_g))
+        .insert_header(("frontend_api_key", "TESTING"))
+        .insert_header(("If-Match", "*"));
+    let res = req
+        .send_json(&serde_json::json!({
+            "name": "TestGameG",
+            "description": "TestGameG Description",
+            "author": "skyz",
+            "tags": ["MergeSrc"],
+        }))
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+
+    let req = srv
+        .post("/api/tags/MergeSrc/merge")
+        .insert_header(("frontend_api_key", "TESTING"));
+    let res = req
+        .send_json(&TagMerge {
+            into: "MergeDst".to_string(),
+        })
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    let req = srv.get("/api/tags/MergeSrc/games");
+    let mut res = req.send().await.unwrap();
+    let games = res.json::<Vec<Game>>().await.unwrap();
+    assert!(games.iter().any(|game| game.id == game_g));
+    // The old name now resolves to the target it was merged into
+    let req = srv
+        .post("/api/tags/MergeSrc/merge")
+        .insert_header(("frontend_api_key", "TESTING"));
+    let res = req
+        .send_json(&TagMerge {
+            into: "MergeDst".to_string(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(res.status().as_u16(), 400);
+
+    let req = srv.get("/api/tags/");
+    let mut res = req.send().await.unwrap();
+    let tags = res.json::<Vec<TagWithUsage>>().await.unwrap();
+    assert!(!tags.iter().any(|tag| tag.tag.name == "MergeSrc"));
+    assert_eq!(
+        tags.iter()
+            .find(|tag| tag.tag.name == "MergeDst")
+            .unwrap()
+            .usage_count,
+        1
+    );
+    let req = srv.get("/api/tags/?unused=true");
+    let mut res = req.send().await.unwrap();
+    let unused = res.json::<Vec<TagWithUsage>>().await.unwrap();
+    assert!(unused.iter().all(|tag| tag.usage_count == 0));
+    assert!(unused.iter().any(|tag| tag.tag.name == "MergeUnused"));
+}
+
+#[actix_web::test]
+async fn test_system_tags_protected() {
+    let srv = get_test_server().await;
+    let req = srv
+        .delete("/api/tags/hidden")
+        .insert_header(("frontend_api_key", "TESTING"));
+    assert_eq!(req.send().await.unwrap().status().as_u16(), 400);
+    let req = srv
+        .put("/api/tags/featured")
+        .insert_header(("frontend_api_key", "TESTING"))
+        .insert_header(("If-Match", "*"));
+    let res = req
+        .send_json(&Tag {
+            name: "promoted".to_string(),
+            description: "Featured on the cabinet".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(res.status().as_u16(), 400);
+    let req = srv.get("/api/tags/authrequired");
+    assert!(req.send().await.unwrap().status().is_success());
+}
+
+#[actix_web::test]
+async fn test_bulk_tag_games() {
+    let srv = get_test_server().await;
+    create_tag(&srv, "BulkTag").await;
+    create_tag(&srv, "BulkOther").await;
+    let assign = |method: &'static str, games: Vec<String>| {
+        let req = srv
+            .request(method.parse().unwrap(), srv.url("/api/tags/BulkTag/games"))
+            .insert_header(("frontend_api_key", "TESTING"));
+        async move {
+            let mut res = req.send_json(&GameIds { games }).await.unwrap();
+            (
+                res.status().as_u16(),
+                res.json::<TagAssignment>().await.unwrap(),
+            )
+        }
+    };
+    let (status, result) = assign(
+        "POST",
+        vec![TEST_GAME_B.id.clone(), "MISSING-GAME".to_string()],
+    )
+    .await;
+    assert_eq!(status, 400);
+    assert_eq!(result.missing, vec!["MISSING-GAME".to_string()]);
+    let (status, result) =
+        assign("POST", vec![TEST_GAME_B.id.clone(), TEST_GAME_L.id.clone()]).await;
+    assert_eq!(status, 200);
+    assert_eq!(result.added.len(), 2);
+    let (_, result) = assign("DELETE", vec![TEST_GAME_B.id.clone()]).await;
+    assert_eq!(result.removed, vec![TEST_GAME_B.id.clone()]);
+    assert!(result.added.is_empty());
+
+    let patch = |patch: GameTagsPatch| {
+        let req = srv
+            .patch(format!("/api/games/{}/tags", TEST_GAME_L.id))
+            .insert_header(("frontend_api_key", "TESTING"));
+        async move {
+            let mut res = req.send_json(&patch).await.unwrap();
+            (
+                res.status().as_u16(),
+                res.json::<TagAssignment>().await.unwrap(),
+            )
+        }
+    };
+    let (status, result) = patch(GameTagsPatch {
+        add: vec![
+            "NoSuchTag".to_string(),
+            "BulkOther".to_string(),
+            "NoSuchTag".to_string(),
+        ],
+        remove: vec![],
+    })
+    .await;
+    assert_eq!(status, 400);
+    assert_eq!(result.missing, vec!["NoSuchTag".to_string()]);
+    let (status, result) = patch(GameTagsPatch {
+        add: vec!["BulkOther".to_string()],
+        remove: vec!["BulkTag".to_string()],
+    })
+    .await;
+    assert_eq!(status, 200);
+    assert_eq!(result.added, vec!["BulkOther".to_string()]);
+    assert_eq!(result.removed, vec!["BulkTag".to_string()]);
+    let req = srv.get(format!("/api/games/{}", TEST_GAME_L.id));
+    let mut res = req.send().await.unwrap();
+    let tags: Vec<String> = res
+        .json::<GameWithTags>()
+        .await
+        .unwrap()
+        .tags
+        .into_iter()
+        .map(|tag| tag.name)
+        .collect();
+    assert_eq!(tags, vec!["BulkOther".to_string()]);
+}
+
+#[actix_web::test]
+async fn test_patch_tag() {
+    let srv = get_test_server().await;
+    create_tag(&srv, "PatchOld").await;
+    let patch = |name: &'static str, body: serde_json::Value| {
+        let req = srv
+            .patch(format!("/api/tags/{}", name))
+            .insert_header(("frontend_api_key", "TESTING"))
+            .insert_header(("If-Match", "*"))
+            .content_type("application/merge-patch+json");
+        async move { req.send_body(body.to_string()).await.unwrap() }
+    };
+
+    let mut res = patch(
+        "PatchOld",
+        serde_json::json!({"name": "PatchNew", "color": "#b0197e"}),
+    )
+    .await;
+    assert_eq!(res.status().as_u16(), 200);
+    let tag = res.json::<Tag>().await.unwrap();
+    assert_eq!(tag.name, "PatchNew");
+    assert_eq!(tag.description, "PatchOld Description");
+    assert_eq!(tag.color.as_deref(), Some("#b0197e"));
+
+    let res = patch("PatchNew", serde_json::json!({"name": "P".repeat(33)})).await;
+    assert_eq!(res.status().as_u16(), 422);
+    let res = patch("PatchNew", serde_json::json!({"usage_count": 3})).await;
+    assert_eq!(res.status().as_u16(), 400);
+}
+
+#[actix_web::test]
+async fn test_tag_etag() {
+    let srv = get_test_server().await;
+    create_tag(&srv, "Versioned").await;
+    let patch = |etag: Option<&str>, body: serde_json::Value| {
+        let mut req = srv
+            .patch("/api/tags/Versioned")
+            .insert_header(("frontend_api_key", "TESTING"))
+            .content_type("application/merge-patch+json");
+        if let Some(etag) = etag {
+            req = req.insert_header(("If-Match", etag));
+        }
+        async move { req.send_body(body.to_string()).await.unwrap() }
+    };
+    let etag = |res: &awc::ClientResponse<_>| {
+        res.headers()
+            .get("ETag")
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string()
+    };
+
+    let req = srv.get("/api/tags/Versioned");
+    let res = req.send().await.unwrap();
+    let read = etag(&res);
+
+    let res = patch(None, serde_json::json!({"description": "Unversioned"})).await;
+    assert_eq!(res.status().as_u16(), 428);
+    let res = patch(Some(&read), serde_json::json!({"description": "First"})).await;
+    assert!(res.status().is_success());
+    let written = etag(&res);
+    assert_ne!(read, written);
+
+    // A second edit based on the same read would overwrite the first
+    let res = patch(Some(&read), serde_json::json!({"description": "Second"})).await;
+    assert_eq!(res.status().as_u16(), 412);
+    let req = srv
+        .put("/api/tags/Versioned")
+        .insert_header(("frontend_api_key", "TESTING"))
+        .insert_header(("If-Match", read.as_str()));
+    let res = req
+        .send_json(&Tag {
+            name: "Versioned".to_string(),
+            description: "Second".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(res.status().as_u16(), 412);
+
+    let req = srv.get("/api/tags/Versioned");
+    let mut res = req.send().await.unwrap();
+    assert_eq!(etag(&res), written);
+    assert_eq!(res.json::<Tag>().await.unwrap().description, "First");
+}
@@ -0,0 +1,81 @@
+use data_encoding::HEXLOWER;
+use uuid::Uuid;
+
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+tokio::task_local! {
+    /// Span the code running in this task belongs to
+    pub(crate) static CURRENT: TraceContext;
+}
+
+/// Ids of a span and the trace it is part of, as carried by W3C `traceparent` headers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceContext {
+    pub trace_id: [u8; 16],
+    pub span_id: [u8; 8],
+    pub sampled: bool,
+}
+
+fn random_span_id() -> [u8; 8] {
+    let mut span_id = [0; 8];
+    span_id.copy_from_slice(&Uuid::new_v4().as_bytes()[..8]);
+    span_id
+}
+
+impl TraceContext {
+    /// Context of a new trace
+    pub fn root() -> TraceContext {
+        TraceContext {
+            trace_id: *Uuid::new_v4().as_bytes(),
+            span_id: random_span_id(),
+            sampled: true,
+        }
+    }
+
+    /// Context of a new span under this one
+    pub fn child(&self) -> TraceContext {
+        TraceContext {
+            span_id: random_span_id(),
+            ..*self
+        }
+    }
+
+    /// Context of the span running in this task, if any
+    pub fn current() -> Option<TraceContext> {
+        CURRENT.try_with(|context| *context).ok()
+    }
+
+    /// Parse a version `00` `traceparent` header, as in
+    /// `00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01`
+    pub fn from_traceparent(header: &str) -> Option<TraceContext> {
+        let mut parts = header.trim().split('-');
+        let (version, trace_id, span_id, flags) =
+            (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+        if version != "00" || parts.next().is_some() {
+            return None;
+        }
+        let decode = |hex: &str, length: usize| {
+            Some(hex)
+                .filter(|hex| hex.len() == length * 2 && !hex.chars().any(char::is_uppercase))
+                .and_then(|hex| HEXLOWER.decode(hex.as_bytes()).ok())
+        };
+        // All zero ids are invalid
+        let id = |hex: &str, length: usize| {
+            decode(hex, length).filter(|bytes| bytes.iter().any(|byte| *byte != 0))
+        };
+        Some(TraceContext {
+            trace_id: id(trace_id, 16)?.try_into().ok()?,
+            span_id: id(span_id, 8)?.try_into().ok()?,
+            sampled: decode(flags, 1)?[0] & 1 == 1,
+        })
+    }
+
+    pub fn traceparent(&self) -> String {
+        format!(
+            "00-{}-{}-{:02x}",
+            HEXLOWER.encode(&self.trace_id),
+            HEXLOWER.encode(&self.span_id),
+            self.sampled as u8
+        )
+    }
+}
@@ -0,0 +1,155 @@
+use crate::telemetry::span::{SpanData, SpanKind};
+use actix_web::rt::{
+    self,
+    time::{timeout, Instant},
+};
+use awc::Client;
+use data_encoding::HEXLOWER;
+use lazy_static::lazy_static;
+use serde_json::{json, Value};
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, UNIX_EPOCH},
+};
+use tokio::sync::mpsc::{self, error::TrySendError, Receiver, Sender};
+
+const EXPORT_INTERVAL: Duration = Duration::from_secs(5);
+const EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_BATCH: usize = 512;
+/// Spans waiting to be exported, past which new spans are dropped rather than held in memory
+/// while the collector is slow or down
+const QUEUE_CAPACITY: usize = 4 * MAX_BATCH;
+
+lazy_static! {
+    static ref SENDER: Mutex<Option<Sender<SpanData>>> = Mutex::new(None);
+}
+static ENABLED: AtomicBool = AtomicBool::new(false);
+/// Spans dropped since the last export because the queue was full
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+
+/// Collector spans are sent to, only configured when `OTEL_EXPORTER_OTLP_ENDPOINT` is set
+#[derive(Debug, Clone, PartialEq)]
+pub struct TelemetryConfig {
+    /// Base URL of an OTLP/HTTP collector, spans are posted to `<endpoint>/v1/traces`
+    pub endpoint: String,
+    pub service_name: String,
+}
+
+/// Whether spans are being recorded
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Start sending spans to the collector in `config` from the background
+pub fn start(config: &TelemetryConfig) {
+    let (sender, receiver) = mpsc::channel(QUEUE_CAPACITY);
+    *SENDER.lock().unwrap() = Some(sender);
+    ENABLED.store(true, Ordering::Relaxed);
+    rt::spawn(run(config.clone(), receiver));
+}
+
+pub(crate) fn export(span: SpanData) {
+    if let Some(sender) = SENDER.lock().unwrap().as_ref() {
+        if let Err(TrySendError::Full(_)) = sender.try_send(span) {
+            DROPPED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+async fn run(config: TelemetryConfig, mut receiver: Receiver<SpanData>) {
+    let client = Client::builder().timeout(EXPORT_TIMEOUT).finish();
+    let url = format!("{}/v1/traces", config.endpoint.trim_end_matches('/'));
+    let mut batch = vec![];
+    let mut deadline = Instant::now() + EXPORT_INTERVAL;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let closed = match timeout(remaining, receiver.recv()).await {
+            Ok(Some(span)) => {
+                batch.push(span);
+                if batch.len() < MAX_BATCH {
+                    continue;
+                }
+                false
+            }
+            Ok(None) => true,
+            Err(_) => false,
+        };
+        if !batch.is_empty() {
+            let spans = std::mem::take(&mut batch);
+            match client
+                .post(&url)
+                .send_json(&otlp_json(&config.service_name, &spans))
+                .await
+            {
+                Ok(res) if res.status().is_success() => {}
+                Ok(res) => log::warn!(
+                    "Collector responded with {} to {} spans",
+                    res.status(),
+                    spans.len()
+                ),
+                Err(e) => log::warn!("Could not export {} spans: {}", spans.len(), e),
+            }
+        }
+        let dropped = DROPPED.swap(0, Ordering::Relaxed);
+        if dropped > 0 {
+            log::warn!("Dropped {} spans while the export queue was full", dropped);
+        }
+        if closed {
+            return;
+        }
+        deadline = Instant::now() + EXPORT_INTERVAL;
+    }
+}
+
+fn unix_nanos(time: &std::time::SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_nanos())
+        .to_string()
+}
+
+/// `spans` as an OTLP/HTTP JSON `ExportTraceServiceRequest`
+pub fn otlp_json(service_name: &str, spans: &[SpanData]) -> Value {
+    let attribute = |key: &str, value: &str| json!({"key": key, "value": {"stringValue": value}});
+    let spans = spans
+        .iter()
+        .map(|span| {
+            let mut otlp = json!({
+                "traceId": HEXLOWER.encode(&span.context.trace_id),
+                "spanId": HEXLOWER.encode(&span.context.span_id),
+                "name": span.name,
+                "kind": match span.kind {
+                    SpanKind::Internal => 1,
+                    SpanKind::Server => 2,
+                    SpanKind::Client => 3,
+                },
+                "startTimeUnixNano": unix_nanos(&span.start),
+                "endTimeUnixNano": unix_nanos(&span.end),
+                "attributes": span
+                    .attributes
+                    .iter()
+                    .map(|(key, value)| attribute(key, value))
+                    .collect::<Vec<_>>(),
+                "status": match &span.error {
+                    Some(error) => json!({"code": 2, "message": error}),
+                    None => json!({"code": 0}),
+                },
+            });
+            if let Some(parent_span_id) = span.parent_span_id {
+                otlp["parentSpanId"] = HEXLOWER.encode(&parent_span_id).into();
+            }
+            otlp
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "resourceSpans": [{
+            "resource": {"attributes": [attribute("service.name", service_name)]},
+            "scopeSpans": [{
+                "scope": {"name": env!("CARGO_PKG_NAME"), "version": env!("CARGO_PKG_VERSION")},
+                "spans": spans,
+            }],
+        }],
+    })
+}
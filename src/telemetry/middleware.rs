@@ -0,0 +1,90 @@
+use crate::telemetry::{
+    context::{TraceContext, TRACEPARENT_HEADER},
+    export,
+    span::Span,
+};
+use actix_web::{
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    HttpMessage,
+};
+use futures::future::LocalBoxFuture;
+use std::future::{self, Ready};
+
+/// Serves every request in a span, continuing the trace in an incoming `traceparent` header
+pub struct TraceRequests;
+
+impl<S, B> Transform<S, ServiceRequest> for TraceRequests
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Transform = TraceMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        future::ready(Ok(TraceMiddleware { service }))
+    }
+}
+
+pub struct TraceMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for TraceMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error>,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, actix_web::Error>>;
+
+    fn poll_ready(
+        &self,
+        ctx: &mut core::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if !export::enabled() {
+            return Box::pin(self.service.call(req));
+        }
+        let parent = req
+            .headers()
+            .get(TRACEPARENT_HEADER)
+            .and_then(|header| header.to_str().ok())
+            .and_then(TraceContext::from_traceparent);
+        let mut span = Span::server(req.method().to_string(), parent);
+        span.attribute("http.method", req.method());
+        span.attribute("http.target", req.path());
+        if let Some(context) = span.context() {
+            req.extensions_mut().insert(context);
+        }
+        let future = self.service.call(req);
+
+        Box::pin(async move {
+            let result = span.scope(future).await;
+            match &result {
+                Ok(response) => {
+                    if let Some(route) = response.request().match_pattern() {
+                        span.rename(format!("{} {}", response.request().method(), route));
+                        span.attribute("http.route", route);
+                    }
+                    span.attribute("http.status_code", response.status().as_u16());
+                    if response.status().is_server_error() {
+                        span.fail(response.status());
+                    }
+                }
+                Err(e) => span.fail(e),
+            }
+            span.end();
+            result
+        })
+    }
+}
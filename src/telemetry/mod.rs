@@ -0,0 +1,7 @@
+pub mod context;
+pub mod export;
+pub mod middleware;
+pub mod query;
+pub mod span;
+#[cfg(test)]
+pub mod tests;
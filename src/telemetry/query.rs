@@ -0,0 +1,144 @@
+use crate::telemetry::span::{Span, SpanKind};
+use futures::{
+    future::BoxFuture,
+    stream::{BoxStream, Stream, StreamExt},
+};
+use sqlx::{
+    postgres::{PgQueryResult, PgRow, PgStatement, PgTypeInfo},
+    Describe, Either, Execute, Executor, Postgres,
+};
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Runs queries through the executor it wraps, as in `Traced(&state.db)` or
+/// `Traced(&mut transaction)`, recording each one as a client span under the span running in
+/// this task. A query that fails fails its span. Outside of a trace queries are only run.
+#[derive(Debug)]
+pub struct Traced<E>(pub E);
+
+/// Span of running `sql`, named after its first keyword as in `SELECT`
+fn query_span(sql: &str) -> Span {
+    let name = sql.split_whitespace().next().unwrap_or("query");
+    let mut span = Span::child(name.to_uppercase(), SpanKind::Client);
+    if span.context().is_some() {
+        span.attribute("db.system", "postgresql");
+        span.attribute(
+            "db.statement",
+            sql.split_whitespace().collect::<Vec<_>>().join(" "),
+        );
+    }
+    span
+}
+
+type Results<'e> = BoxStream<'e, Result<Either<PgQueryResult, PgRow>, sqlx::Error>>;
+
+/// Results of a query, ending its span once they run out or are no longer polled
+struct TracedResults<'e> {
+    results: Results<'e>,
+    span: Option<Span>,
+    rows_affected: u64,
+    rows_returned: u64,
+}
+
+impl TracedResults<'_> {
+    fn end(&mut self) {
+        if let Some(mut span) = self.span.take() {
+            span.attribute("db.rows_affected", self.rows_affected);
+            span.attribute("db.rows_returned", self.rows_returned);
+            span.end();
+        }
+    }
+}
+
+impl Stream for TracedResults<'_> {
+    type Item = Result<Either<PgQueryResult, PgRow>, sqlx::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let polled = self.results.poll_next_unpin(cx);
+        match &polled {
+            Poll::Ready(Some(Ok(Either::Left(result)))) => {
+                self.rows_affected += result.rows_affected()
+            }
+            Poll::Ready(Some(Ok(Either::Right(_)))) => self.rows_returned += 1,
+            Poll::Ready(Some(Err(e))) => {
+                if let Some(span) = &mut self.span {
+                    span.fail(e);
+                }
+            }
+            Poll::Ready(None) => self.end(),
+            Poll::Pending => {}
+        }
+        polled
+    }
+}
+
+impl Drop for TracedResults<'_> {
+    fn drop(&mut self) {
+        self.end();
+    }
+}
+
+impl<'c, E> Executor<'c> for Traced<E>
+where
+    E: Executor<'c, Database = Postgres>,
+{
+    type Database = Postgres;
+
+    fn fetch_many<'e, 'q: 'e, Q>(self, query: Q) -> Results<'e>
+    where
+        'c: 'e,
+        Q: 'q + Execute<'q, Postgres>,
+    {
+        let span = query_span(query.sql());
+        Box::pin(TracedResults {
+            results: self.0.fetch_many(query),
+            span: Some(span),
+            rows_affected: 0,
+            rows_returned: 0,
+        })
+    }
+
+    fn fetch_optional<'e, 'q: 'e, Q>(
+        self,
+        query: Q,
+    ) -> BoxFuture<'e, Result<Option<PgRow>, sqlx::Error>>
+    where
+        'c: 'e,
+        Q: 'q + Execute<'q, Postgres>,
+    {
+        let mut span = query_span(query.sql());
+        let row = self.0.fetch_optional(query);
+        Box::pin(async move {
+            let row = row.await;
+            match &row {
+                Ok(row) => span.attribute("db.rows_returned", u8::from(row.is_some())),
+                Err(e) => span.fail(e),
+            }
+            span.end();
+            row
+        })
+    }
+
+    fn prepare_with<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+        parameters: &'e [PgTypeInfo],
+    ) -> BoxFuture<'e, Result<PgStatement<'q>, sqlx::Error>>
+    where
+        'c: 'e,
+    {
+        self.0.prepare_with(sql, parameters)
+    }
+
+    fn describe<'e, 'q: 'e>(
+        self,
+        sql: &'q str,
+    ) -> BoxFuture<'e, Result<Describe<Postgres>, sqlx::Error>>
+    where
+        'c: 'e,
+    {
+        self.0.describe(sql)
+    }
+}
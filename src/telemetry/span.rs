@@ -0,0 +1,128 @@
+use crate::telemetry::{
+    context::{TraceContext, CURRENT},
+    export,
+};
+use std::{fmt::Display, future::Future, time::SystemTime};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpanKind {
+    Internal,
+    Server,
+    Client,
+}
+
+/// A finished span, ready to be exported
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpanData {
+    pub name: String,
+    pub kind: SpanKind,
+    pub context: TraceContext,
+    pub parent_span_id: Option<[u8; 8]>,
+    pub start: SystemTime,
+    pub end: SystemTime,
+    pub attributes: Vec<(&'static str, String)>,
+    /// Why the operation failed, if it did
+    pub error: Option<String>,
+}
+
+/// An operation being timed, exported once ended. Spans are only recorded while a collector
+/// is configured and the trace is sampled, otherwise every method does nothing.
+#[derive(Debug)]
+pub struct Span(Option<SpanData>);
+
+impl Span {
+    fn start(
+        name: impl Into<String>,
+        kind: SpanKind,
+        context: TraceContext,
+        parent_span_id: Option<[u8; 8]>,
+    ) -> Span {
+        match export::enabled() && context.sampled {
+            true => Span(Some(SpanData {
+                name: name.into(),
+                kind,
+                context,
+                parent_span_id,
+                start: SystemTime::now(),
+                end: SystemTime::now(),
+                attributes: vec![],
+                error: None,
+            })),
+            false => Span(None),
+        }
+    }
+
+    /// Span for serving a request, continuing the trace of `parent` when the caller sent one
+    pub fn server(name: impl Into<String>, parent: Option<TraceContext>) -> Span {
+        match parent {
+            Some(parent) => {
+                Span::start(name, SpanKind::Server, parent.child(), Some(parent.span_id))
+            }
+            None => Span::start(name, SpanKind::Server, TraceContext::root(), None),
+        }
+    }
+
+    /// Span under the one running in this task, which does nothing outside of a trace
+    pub fn child(name: impl Into<String>, kind: SpanKind) -> Span {
+        match TraceContext::current() {
+            Some(parent) => Span::start(name, kind, parent.child(), Some(parent.span_id)),
+            None => Span(None),
+        }
+    }
+
+    pub fn context(&self) -> Option<TraceContext> {
+        self.0.as_ref().map(|span| span.context)
+    }
+
+    pub fn rename(&mut self, name: impl Into<String>) {
+        if let Some(span) = &mut self.0 {
+            span.name = name.into();
+        }
+    }
+
+    pub fn attribute(&mut self, key: &'static str, value: impl Display) {
+        if let Some(span) = &mut self.0 {
+            span.attributes.push((key, value.to_string()));
+        }
+    }
+
+    pub fn fail(&mut self, error: impl Display) {
+        if let Some(span) = &mut self.0 {
+            span.error = Some(error.to_string());
+        }
+    }
+
+    /// Run `operation` as part of this span, so spans it starts are nested under it
+    pub async fn scope<F: Future>(&self, operation: F) -> F::Output {
+        match self.context() {
+            Some(context) => CURRENT.scope(context, operation).await,
+            None => operation.await,
+        }
+    }
+
+    pub fn end(self) {
+        if let Some(mut span) = self.0 {
+            span.end = SystemTime::now();
+            export::export(span);
+        }
+    }
+}
+
+/// Time `operation` in a child span named `name`, failing the span if it returns an error
+pub async fn traced<T, E: Display>(
+    name: &'static str,
+    kind: SpanKind,
+    attributes: &[(&'static str, &str)],
+    operation: impl Future<Output = Result<T, E>>,
+) -> Result<T, E> {
+    let mut span = Span::child(name, kind);
+    for (key, value) in attributes {
+        span.attribute(key, value);
+    }
+    let result = span.scope(operation).await;
+    if let Err(e) = &result {
+        span.fail(e);
+    }
+    span.end();
+    result
+}
@@ -0,0 +1,92 @@
+#[cfg(test)]
+use crate::{
+    app::get_app_data,
+    config::Config,
+    telemetry::{
+        context::TraceContext,
+        export::otlp_json,
+        query::Traced,
+        span::{SpanData, SpanKind},
+    },
+};
+use sqlx::{query, query_scalar};
+use std::time::{Duration, SystemTime};
+
+const TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+#[test]
+fn test_traceparent() {
+    let context = TraceContext::from_traceparent(TRACEPARENT).unwrap();
+    assert!(context.sampled);
+    assert_eq!(context.traceparent(), TRACEPARENT);
+    let child = context.child();
+    assert_eq!(child.trace_id, context.trace_id);
+    assert_ne!(child.span_id, context.span_id);
+
+    let unsampled =
+        TraceContext::from_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-00")
+            .unwrap();
+    assert!(!unsampled.sampled);
+    for invalid in [
+        "",
+        "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+        "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+        "00-4bf92f3577b34da6a3ce929d0e0e47-00f067aa0ba902b7-01",
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+    ] {
+        assert_eq!(TraceContext::from_traceparent(invalid), None, "{}", invalid);
+    }
+}
+
+#[test]
+fn test_otlp_json() {
+    let context = TraceContext::from_traceparent(TRACEPARENT).unwrap();
+    let span = SpanData {
+        name: "s3.get_object".to_string(),
+        kind: SpanKind::Client,
+        context: context.child(),
+        parent_span_id: Some(context.span_id),
+        start: SystemTime::UNIX_EPOCH + Duration::from_millis(1500),
+        end: SystemTime::UNIX_EPOCH + Duration::from_millis(1750),
+        attributes: vec![("s3.key", "A/A.zip".to_string())],
+        error: Some("NoSuchKey".to_string()),
+    };
+    let body = otlp_json("devcade-api", &[span]);
+    let resource = &body["resourceSpans"][0];
+    assert_eq!(
+        resource["resource"]["attributes"][0]["value"]["stringValue"],
+        "devcade-api"
+    );
+    let span = &resource["scopeSpans"][0]["spans"][0];
+    assert_eq!(span["traceId"], "4bf92f3577b34da6a3ce929d0e0e4736");
+    assert_eq!(span["parentSpanId"], "00f067aa0ba902b7");
+    assert_eq!(span["kind"], 3);
+    assert_eq!(span["startTimeUnixNano"], "1500000000");
+    assert_eq!(span["endTimeUnixNano"], "1750000000");
+    assert_eq!(span["attributes"][0]["key"], "s3.key");
+    assert_eq!(span["status"]["code"], 2);
+    assert_eq!(span["status"]["message"], "NoSuchKey");
+}
+
+#[actix_web::test]
+async fn test_traced_query() {
+    let app_data = get_app_data(Config::load().unwrap()).await.unwrap();
+    let two = query_scalar::<_, i32>("SELECT 2")
+        .fetch_one(Traced(&app_data.db))
+        .await
+        .unwrap();
+    assert_eq!(two, 2);
+    let mut transaction = app_data.db.begin().await.unwrap();
+    let rows = query_scalar::<_, i32>("SELECT generate_series(1, 3)")
+        .fetch_all(Traced(&mut transaction))
+        .await
+        .unwrap();
+    assert_eq!(rows, vec![1, 2, 3]);
+    transaction.rollback().await.unwrap();
+    assert!(query("SELECT 1 / 0")
+        .execute(Traced(&app_data.db))
+        .await
+        .is_err());
+}
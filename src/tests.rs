@@ -1,12 +1,21 @@
 use crate::{
     app::{configure_app, get_app_data},
+    config::Config,
+    cors::{cors, origin_matches, CorsConfig},
+    logging::json,
     models::{Game, GameWithTags, Tag, User},
 };
 use actix_test::TestServer;
-use actix_web::App;
+use actix_web::{http::header, test, App};
 
 use chrono::NaiveDate;
+use env_logger::Target;
 use lazy_static::lazy_static;
+use serde_json::Value;
+use std::{
+    io::{self, Write},
+    sync::{Mutex, Once},
+};
 
 lazy_static! {
     pub static ref TEST_GAME_A: Game = make_test_game(
@@ -44,85 +53,167 @@ lazy_static! {
         NaiveDate::from_ymd_opt(2023, 3, 23).unwrap(),
         "TestGameE"
     );
+    /// Hidden until the webhook tests approve it
+    pub static ref TEST_GAME_H: Game = make_test_game(
+        "H",
+        "skyz",
+        "579e03f4fdad803a53602808ce2cfaead7c69344",
+        NaiveDate::from_ymd_opt(2023, 3, 23).unwrap(),
+        "TestGameH"
+    );
+    /// Reviewed by the review tests only
+    pub static ref TEST_GAME_I: Game = make_test_game(
+        "I",
+        "skyz",
+        "6f6e1f0733bc60463d32436d2c115382ec6a801f",
+        NaiveDate::from_ymd_opt(2023, 3, 23).unwrap(),
+        "TestGameI"
+    );
+    /// Shared by the contributor tests only
+    pub static ref TEST_GAME_J: Game = make_test_game(
+        "J",
+        "atom",
+        "a5e8a81726700bc1b408cb60366f232ada0e726b",
+        NaiveDate::from_ymd_opt(2023, 3, 23).unwrap(),
+        "TestGameJ"
+    );
+    /// Listed by the user tests only
+    pub static ref TEST_GAME_K: Game = make_test_game(
+        "K",
+        "joeneil",
+        "8b4290df8ecdd83dbd215fe745499c0f5e492e28",
+        NaiveDate::from_ymd_opt(2023, 3, 23).unwrap(),
+        "TestGameK"
+    );
+    /// Tagged by the bulk tag tests only
+    pub static ref TEST_GAME_L: Game = make_test_game(
+        "L",
+        "skyz",
+        "f942b92d813a16ab1ef322e8ad7b1a15d42390b5",
+        NaiveDate::from_ymd_opt(2023, 3, 23).unwrap(),
+        "TestGameL"
+    );
+    /// Tagged with `TEST_TAG_HIERARCHY` for the tag hierarchy tests only
+    pub static ref TEST_GAME_M: Game = make_test_game(
+        "M",
+        "skyz",
+        "0d54118dcfd7105ac57008a835998f5a08488368",
+        NaiveDate::from_ymd_opt(2023, 3, 23).unwrap(),
+        "TestGameM"
+    );
+    /// Hidden and featured by the system tag tests only
+    pub static ref TEST_GAME_N: Game = make_test_game(
+        "N",
+        "skyz",
+        "e5cc1088ea7326b246364e3fa40a97020f2662b9",
+        NaiveDate::from_ymd_opt(2023, 3, 23).unwrap(),
+        "TestGameN"
+    );
     pub static ref TEST_TAG_1: Tag = Tag {
         name: "TestTag1".to_string(),
         description: "TestTag1 Description".to_string(),
+        ..Default::default()
     };
     pub static ref TEST_TAG_2: Tag = Tag {
         name: "TestTag2".to_string(),
         description: "TestTag2 Description".to_string(),
+        ..Default::default()
     };
     pub static ref TEST_TAG_3: Tag = Tag {
         name: "TestTag3".to_string(),
         description: "TestTag3 Description".to_string(),
+        ..Default::default()
     };
     pub static ref TEST_TAG_4: Tag = Tag {
         name: "TestTag4".to_string(),
         description: "TestTag4 Description".to_string(),
+        ..Default::default()
     };
     pub static ref TEST_TAG_5: Tag = Tag {
         name: "TestTag5".to_string(),
         description: "TestTag5 Description".to_string(),
+        ..Default::default()
     };
     pub static ref TEST_TAG_6: Tag = Tag {
         name: "TestTag6".to_string(),
         description: "TestTag6 Description".to_string(),
+        ..Default::default()
     };
     pub static ref TEST_TAG_7: Tag = Tag {
         name: "TestTag7".to_string(),
         description: "TestTag7 Description".to_string(),
+        ..Default::default()
     };
     pub static ref TEST_TAG_8: Tag = Tag {
         name: "TestTag8".to_string(),
         description: "TestTag8 Description".to_string(),
+        ..Default::default()
     };
     pub static ref TEST_TAG_9: Tag = Tag {
         name: "TestTag9".to_string(),
         description: "TestTag9 Description".to_string(),
+        ..Default::default()
     };
     pub static ref TEST_TAG_10: Tag = Tag {
         name: "TestTag10".to_string(),
         description: "TestTag10 Description".to_string(),
+        ..Default::default()
     };
     pub static ref TEST_TAG_11: Tag = Tag {
         name: "TestTag11".to_string(),
         description: "TestTag11 Description".to_string(),
+        ..Default::default()
     };
     pub static ref TEST_TAG_12: Tag = Tag {
         name: "TestTag12".to_string(),
         description: "TestTag12 Description".to_string(),
+        ..Default::default()
     };
     pub static ref TEST_TAG_13: Tag = Tag {
         name: "TestTag13".to_string(),
         description: "TestTag13 Description".to_string(),
+        ..Default::default()
     };
     pub static ref TEST_TAG_14: Tag = Tag {
         name: "TestTag14".to_string(),
         description: "TestTag14 Description".to_string(),
+        ..Default::default()
     };
     pub static ref TEST_TAG_15: Tag = Tag {
         name: "TestTag15".to_string(),
         description: "TestTag15 Description".to_string(),
+        ..Default::default()
     };
     pub static ref TEST_TAG_16: Tag = Tag {
         name: "TestTag16".to_string(),
         description: "TestTag16 Description".to_string(),
+        ..Default::default()
     };
     pub static ref TEST_TAG_17: Tag = Tag {
         name: "TestTag17".to_string(),
         description: "TestTag17 Description".to_string(),
+        ..Default::default()
     };
     pub static ref TEST_TAG_18: Tag = Tag {
         name: "TestTag18".to_string(),
         description: "TestTag18 Description".to_string(),
+        ..Default::default()
     };
     pub static ref TEST_TAG_19: Tag = Tag {
         name: "TestTag19".to_string(),
         description: "TestTag19 Description".to_string(),
+        ..Default::default()
     };
     pub static ref TEST_TAG_20: Tag = Tag {
         name: "TestTag20".to_string(),
         description: "TestTag20 Description".to_string(),
+        ..Default::default()
+    };
+    /// Re-parented by the tag hierarchy tests only
+    pub static ref TEST_TAG_HIERARCHY: Tag = Tag {
+        name: "TestTagHierarchy".to_string(),
+        description: "TestTagHierarchy Description".to_string(),
+        ..Default::default()
     };
     pub static ref SKYZ_USER: User = User::from_csh("skyz", "Joe", "Abbate", true);
     pub static ref QEL_USER: User = User::from_csh("qel", "Jeremy", "Smart", false);
@@ -177,11 +268,22 @@ fn make_test_game(id_letter: &str, author: &str, hash: &str, date: NaiveDate, na
         name: name.to_string(),
         hash: hash.to_string(),
         description: format!("{} Description", name),
+        version: None,
+        entrypoint: None,
+        min_client_version: None,
+        min_players: None,
+        max_players: None,
+        controls: vec![],
+        session_minutes: None,
+        credits: vec![],
+        repo_url: None,
+        license: None,
+        content_warnings: vec![],
     }
 }
 
 pub async fn get_test_server() -> TestServer {
-    let app_data = get_app_data().await;
+    let app_data = get_app_data(Config::load().unwrap()).await.unwrap();
     actix_test::start(move || {
         App::new()
             .configure(configure_app)
@@ -189,6 +291,32 @@ pub async fn get_test_server() -> TestServer {
     })
 }
 
+/// Everything logged by the tests, which is written here once [`logged_lines`] is first called
+static LOGGED: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+
+struct Logged;
+
+impl Write for Logged {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        LOGGED.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The JSON lines logged since the logger was installed, installing it on the first call
+pub fn logged_lines() -> Vec<Value> {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| json::init_to(Target::Pipe(Box::new(Logged))));
+    String::from_utf8_lossy(&LOGGED.lock().unwrap())
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
 #[actix_web::test]
 async fn test_docs_reachable() {
     let srv = get_test_server().await;
@@ -214,3 +342,80 @@ async fn test_openapi_reachable() {
     );
     assert!(res.status().is_success());
 }
+
+#[actix_web::test]
+async fn test_config_debug_redacted() {
+    let config = Config::load().unwrap();
+    let logged = format!("{:?}", config);
+    assert!(!logged.contains(&config.frontend_api_key));
+    assert!(!logged.contains(&config.sql_uri));
+    assert!(logged.contains(&config.s3_games_bucket));
+}
+
+#[actix_web::test]
+async fn test_cors() {
+    let config = CorsConfig {
+        origins: vec!["https://*.csh.rit.edu".to_string()],
+        public_origins: vec!["https://example.com".to_string()],
+        allowed_headers: vec![],
+        exposed_headers: vec!["ETag".to_string()],
+        allow_credentials: true,
+        max_age: 3600,
+    };
+    assert!(config.problems().is_empty());
+    assert!(origin_matches(
+        "https://*.csh.rit.edu",
+        "https://devcade.csh.rit.edu"
+    ));
+    assert!(!origin_matches(
+        "https://*.csh.rit.edu",
+        "https://csh.rit.edu"
+    ));
+    assert!(!origin_matches(
+        "https://*.csh.rit.edu",
+        "https://evilcsh.rit.edu"
+    ));
+    assert!(!origin_matches(
+        "https://*.csh.rit.edu",
+        "http://devcade.csh.rit.edu"
+    ));
+
+    let app = test::init_service(App::new().wrap(cors(&config)).configure(configure_app)).await;
+    let preflight = |origin: &str, method: &str| {
+        test::TestRequest::default()
+            .method(actix_web::http::Method::OPTIONS)
+            .uri("/api/tags/")
+            .insert_header((header::ORIGIN, origin))
+            .insert_header((header::ACCESS_CONTROL_REQUEST_METHOD, method))
+            .insert_header((
+                header::ACCESS_CONTROL_REQUEST_HEADERS,
+                "frontend_api_key, if-match",
+            ))
+            .to_request()
+    };
+    let res = test::call_service(&app, preflight("https://devcade.csh.rit.edu", "PATCH")).await;
+    assert!(res.status().is_success());
+    assert_eq!(
+        res.headers()
+            .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+            .unwrap(),
+        "https://devcade.csh.rit.edu"
+    );
+    assert_eq!(
+        res.headers()
+            .get(header::ACCESS_CONTROL_ALLOW_CREDENTIALS)
+            .unwrap(),
+        "true"
+    );
+    let res = test::call_service(&app, preflight("https://example.com", "GET")).await;
+    assert!(res.status().is_success());
+    let res = test::call_service(&app, preflight("https://example.com", "PATCH")).await;
+    assert!(res.status().is_client_error());
+
+    let invalid = CorsConfig {
+        origins: vec!["*".to_string(), "devcade.csh.rit.edu".to_string()],
+        allowed_headers: vec!["bad header".to_string()],
+        ..config
+    };
+    assert_eq!(invalid.problems().len(), 3);
+}
@@ -0,0 +1,103 @@
+use ldap3::{drive, ldap_escape, LdapConnAsync, LdapConnSettings, LdapError, Scope, SearchEntry};
+use std::time::Duration;
+
+/// How long to wait on the directory for the connection and for each operation, so an
+/// unreachable server cannot hang a login
+const LDAP_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone)]
+pub struct LdapConfig {
+    pub uri: String,
+    /// Account to bind as before searching, binds anonymously when unset
+    pub bind_dn: Option<String>,
+    pub bind_password: String,
+    pub user_base: String,
+    pub group_base: String,
+    /// Members of any of these groups are made admins
+    pub admin_groups: Vec<String>,
+}
+
+/// What the directory knows about a CSH member
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirectoryEntry {
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub groups: Vec<String>,
+    pub admin: bool,
+}
+
+/// Look up a member in the configured directory. Returns `Ok(None)` when no directory is
+/// configured or the member is not in it.
+pub async fn lookup(
+    config: Option<&LdapConfig>,
+    username: &str,
+) -> Result<Option<DirectoryEntry>, LdapError> {
+    match config {
+        Some(config) => lookup_in(config, username).await,
+        None => Ok(None),
+    }
+}
+
+pub async fn lookup_in(
+    config: &LdapConfig,
+    username: &str,
+) -> Result<Option<DirectoryEntry>, LdapError> {
+    let (conn, mut ldap) = LdapConnAsync::with_settings(
+        LdapConnSettings::new().set_conn_timeout(LDAP_TIMEOUT),
+        &config.uri,
+    )
+    .await?;
+    drive!(conn);
+    if let Some(bind_dn) = &config.bind_dn {
+        ldap.with_timeout(LDAP_TIMEOUT)
+            .simple_bind(bind_dn, &config.bind_password)
+            .await?
+            .success()?;
+    }
+    let (users, _) = ldap
+        .with_timeout(LDAP_TIMEOUT)
+        .search(
+            &config.user_base,
+            Scope::Subtree,
+            &format!("(uid={})", ldap_escape(username)),
+            vec!["givenName", "sn"],
+        )
+        .await?
+        .success()?;
+    let user = match users.into_iter().next() {
+        Some(user) => SearchEntry::construct(user),
+        None => {
+            ldap.with_timeout(LDAP_TIMEOUT).unbind().await?;
+            return Ok(None);
+        }
+    };
+    let (groups, _) = ldap
+        .with_timeout(LDAP_TIMEOUT)
+        .search(
+            &config.group_base,
+            Scope::Subtree,
+            &format!("(member={})", ldap_escape(user.dn.as_str())),
+            vec!["cn"],
+        )
+        .await?
+        .success()?;
+    ldap.with_timeout(LDAP_TIMEOUT).unbind().await?;
+    let groups: Vec<String> = groups
+        .into_iter()
+        .filter_map(|group| {
+            SearchEntry::construct(group)
+                .attrs
+                .remove("cn")
+                .and_then(|cn| cn.into_iter().next())
+        })
+        .collect();
+    let first = |attr: &str| user.attrs.get(attr).and_then(|v| v.first()).cloned();
+    Ok(Some(DirectoryEntry {
+        first_name: first("givenName"),
+        last_name: first("sn"),
+        admin: groups
+            .iter()
+            .any(|group| config.admin_groups.contains(group)),
+        groups,
+    }))
+}
@@ -1,3 +1,4 @@
+pub mod ldap;
 pub mod routes;
 #[cfg(test)]
 pub mod tests;
@@ -1,13 +1,299 @@
 use crate::{
-    models::{AppState, User},
+    etag::{self, Precondition, Versioned},
+    events::notify,
+    games::routes::{delete_recursively, games_with_tags},
+    logging::middleware::internal_error,
+    models::{AppState, CatalogEventType, GameWithTags, SyncedUser, User, UserPage, UserType},
+    patch,
     security::RequireApiKey,
+    tags::routes::{has_tag, HIDDEN_TAG},
+    telemetry::query::Traced,
+    users::ldap::{self, DirectoryEntry},
+    validate::ValidationErrors,
 };
 use actix_web::{
-    get, post, put,
-    web::{Data, Json, Path},
-    HttpResponse, Responder,
+    delete, get,
+    http::StatusCode,
+    patch, post, put,
+    web::{Data, Json, Path, Query},
+    HttpRequest, HttpResponse, Responder,
 };
-use sqlx::{query, query_as};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{query, query_as, query_scalar, Pool, Postgres};
+use utoipa::{IntoParams, ToSchema};
+
+/// Fields of a user a JSON Merge Patch may change
+const USER_FIELDS: [&str; 6] = [
+    "user_type",
+    "first_name",
+    "last_name",
+    "picture",
+    "admin",
+    "email",
+];
+
+/// Identity claims a frontend received from CSH SSO or Google on login
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LoginClaims {
+    /// CSH username, or the Google account id
+    #[schema(example = "skyz")]
+    pub id: String,
+    #[schema(example = UserType::CSH)]
+    pub user_type: UserType,
+    /// Required unless the CSH directory knows the member
+    #[schema(example = "Joe")]
+    pub first_name: Option<String>,
+    #[schema(example = "Abbate")]
+    pub last_name: Option<String>,
+    /// Defaults to the CSH profile image for CSH members
+    #[schema(example = "IMAGE_URL")]
+    pub picture: Option<String>,
+    /// Required for Google logins, defaults to the CSH address for CSH members
+    #[schema(example = "skyz@csh.rit.edu")]
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct UserListQuery {
+    /// Case insensitive search through ids, names, and emails
+    search: Option<String>,
+    user_type: Option<UserType>,
+    /// Zero based page number, defaults to 0
+    page: Option<i64>,
+    /// Number of users per page, defaults to 20
+    per_page: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct DeleteUserQuery {
+    /// Hand the user's games over to this user instead of deleting them
+    reassign_to: Option<String>,
+}
+
+#[utoipa::path(
+    context_path = "/users",
+    params(UserListQuery),
+    responses(
+        (status = 200, description = "Page of users matching the search, sorted by id", body = UserPage),
+        (status = 400, description = "Invalid page"),
+        (status = 401, description = "Invalid/Missing API Key"),
+        (status = 500, description = "Error Created by Query"),
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/", wrap = "RequireApiKey")]
+pub async fn get_all_users(state: Data<AppState>, params: Query<UserListQuery>) -> impl Responder {
+    let page = params.page.unwrap_or(0);
+    let per_page = params.per_page.unwrap_or(20);
+    if page < 0 || !(1..=100).contains(&per_page) {
+        return HttpResponse::BadRequest().body("page must be positive and per_page within 1-100");
+    }
+    let offset = match page.checked_mul(per_page) {
+        Some(offset) => offset,
+        None => return HttpResponse::BadRequest().body("page is too large"),
+    };
+    // The search is matched literally, wildcards included
+    let pattern = params.search.as_ref().map(|search| {
+        format!(
+            "%{}%",
+            search
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        )
+    });
+    let filter = "
+        FROM users
+        WHERE ($1::varchar IS NULL
+                OR users.id ILIKE $1
+                OR users.first_name || ' ' || users.last_name ILIKE $1
+                OR users.email ILIKE $1)
+            AND ($2::usertype IS NULL OR users.user_type = $2)
+    ";
+    let total = match query_scalar::<_, i64>(&format!("SELECT COUNT(*) {}", filter))
+        .bind(&pattern)
+        .bind(&params.user_type)
+        .fetch_one(Traced(&state.db))
+        .await
+    {
+        Ok(total) => total,
+        Err(e) => return internal_error("could not count users", e),
+    };
+    match query_as::<_, User>(&format!(
+        "SELECT users.* {} ORDER BY users.id ASC LIMIT $3 OFFSET $4",
+        filter
+    ))
+    .bind(&pattern)
+    .bind(&params.user_type)
+    .bind(per_page)
+    .bind(offset)
+    .fetch_all(Traced(&state.db))
+    .await
+    {
+        Ok(users) => HttpResponse::Ok().json(UserPage {
+            users,
+            page,
+            per_page,
+            total,
+        }),
+        Err(e) => internal_error("could not fetch users", e),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/users",
+    params(
+        ("uid", description = "Unique id of user")
+    ),
+    responses(
+        (status = 200, description = "Games the user contributed to, except hidden games", body = [GameWithTags]),
+        (status = 500, description = "Error Created by Query"),
+    )
+)]
+#[get("/{uid}/games")]
+pub async fn get_user_games(state: Data<AppState>, path: Path<(String,)>) -> impl Responder {
+    let (uid,) = path.into_inner();
+    match query_as::<_, GameWithTags>(&games_with_tags(
+        &format!(
+            "game.id IN (SELECT game_id FROM game_contributors WHERE user_id = $1) AND NOT {}",
+            has_tag(HIDDEN_TAG)
+        ),
+        "name ASC",
+    ))
+    .bind(&uid)
+    .fetch_all(Traced(&state.db))
+    .await
+    {
+        Ok(games) => HttpResponse::Ok().json(games),
+        Err(e) => internal_error("could not fetch user games", e),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/users",
+    params(
+        ("uid", description = "Unique id of user"),
+        DeleteUserQuery,
+    ),
+    responses(
+        (status = 200, description = "Deleted user along with their games, or after reassigning them"),
+        (status = 400, description = "Missing user or reassignment target"),
+        (status = 401, description = "Invalid/Missing API Key"),
+        (status = 500, description = "Error Created by Deletion"),
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[delete("/{uid}", wrap = "RequireApiKey")]
+pub async fn delete_user(
+    state: Data<AppState>,
+    path: Path<(String,)>,
+    params: Query<DeleteUserQuery>,
+) -> impl Responder {
+    let (uid,) = path.into_inner();
+    for id in [Some(&uid), params.reassign_to.as_ref()]
+        .into_iter()
+        .flatten()
+    {
+        if query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+            .bind(id)
+            .fetch_one(Traced(&state.db))
+            .await
+            .is_err()
+        {
+            return HttpResponse::BadRequest().body("User Does Not Exist");
+        }
+    }
+    if params.reassign_to.as_deref() == Some(uid.as_str()) {
+        return HttpResponse::BadRequest().body("Cannot Reassign Games To The Deleted User");
+    }
+    let mut transaction = match state.db.begin().await {
+        Ok(transaction) => transaction,
+        Err(e) => return internal_error("could not begin transaction", e),
+    };
+    let games = match query_scalar::<_, String>("SELECT id FROM game WHERE author = $1 FOR UPDATE")
+        .bind(&uid)
+        .fetch_all(Traced(&mut transaction))
+        .await
+    {
+        Ok(games) => games,
+        Err(e) => {
+            let _ = transaction.rollback().await;
+            return internal_error("could not fetch user games", e);
+        }
+    };
+    if let Some(reassign_to) = &params.reassign_to {
+        let reassigned = query(
+            "
+            INSERT INTO game_contributors (game_id, user_id, role)
+            SELECT game_id, $2, 'owner' FROM game_contributors WHERE user_id = $1 AND role = 'owner'
+            ON CONFLICT (game_id, user_id) DO UPDATE SET role = 'owner'
+            ",
+        )
+        .bind(&uid)
+        .bind(reassign_to)
+        .execute(Traced(&mut transaction))
+        .await
+        .and(
+            query("UPDATE game SET author = $2, updated_at = now() WHERE author = $1")
+                .bind(&uid)
+                .bind(reassign_to)
+                .execute(Traced(&mut transaction))
+                .await,
+        );
+        if let Err(e) = reassigned {
+            let _ = transaction.rollback().await;
+            return internal_error("could not reassign user games", e);
+        }
+    } else {
+        for id in &games {
+            if let Err(e) = query(
+                "INSERT INTO game_tombstones VALUES ($1) ON CONFLICT (id) DO UPDATE SET deleted_at = now()",
+            )
+            .bind(id)
+            .execute(Traced(&mut transaction))
+            .await
+            {
+                let _ = transaction.rollback().await;
+                return internal_error("could not record game tombstone", e);
+            }
+        }
+    }
+    if let Err(e) = query("DELETE FROM users WHERE id = $1")
+        .bind(&uid)
+        .execute(Traced(&mut transaction))
+        .await
+    {
+        let _ = transaction.rollback().await;
+        return internal_error("could not delete user", e);
+    }
+    let event_type = match params.reassign_to {
+        Some(_) => CatalogEventType::GameUpdated,
+        None => CatalogEventType::GameDeleted,
+    };
+    for id in &games {
+        if let Err(e) = notify::publish(&mut transaction, event_type, Some(id), None).await {
+            let _ = transaction.rollback().await;
+            return internal_error("could not publish catalog event", e);
+        }
+    }
+    if let Err(e) = transaction.commit().await {
+        return internal_error("could not commit user deletion", e);
+    }
+    // Objects are only removed once the games are gone, so a failed delete leaves them playable
+    if params.reassign_to.is_none() {
+        for id in &games {
+            if let Err(e) = delete_recursively(&state, id).await {
+                return internal_error("could not delete game files", e);
+            }
+        }
+    }
+    HttpResponse::Ok().finish()
+}
 
 #[utoipa::path(
     context_path = "/users",
@@ -15,6 +301,7 @@ use sqlx::{query, query_as};
     responses(
         (status = 201, description = "Created new user"),
         (status = 401, description = "Invalid/Missing API Key"),
+        (status = 422, description = "Invalid fields, by field", body = ValidationErrors),
         (status = 500, description = "Error Created by Query"),
     ),
     security(
@@ -23,6 +310,10 @@ use sqlx::{query, query_as};
 )]
 #[post("/", wrap = "RequireApiKey")]
 pub async fn add_user(state: Data<AppState>, user: Json<User>) -> impl Responder {
+    let errors = ValidationErrors::of(&*user);
+    if !errors.is_empty() {
+        return errors.response();
+    }
     match query("INSERT INTO users VALUES ($1, $2, $3, $4, $5, $6, $7)")
         .bind(&user.id)
         .bind(&user.user_type)
@@ -31,7 +322,7 @@ pub async fn add_user(state: Data<AppState>, user: Json<User>) -> impl Responder
         .bind(&user.picture)
         .bind(user.admin)
         .bind(&user.email)
-        .execute(&state.db)
+        .execute(Traced(&state.db))
         .await
     {
         Ok(_) => HttpResponse::Created().json(User {
@@ -43,26 +334,28 @@ pub async fn add_user(state: Data<AppState>, user: Json<User>) -> impl Responder
             admin: user.admin,
             email: user.email.clone(),
         }),
-        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+        Err(e) => internal_error("could not add user", e),
     }
 }
 
 #[utoipa::path(
     context_path = "/users",
     responses(
-        (status = 200, description = "Get specified user", body = User),
+        (status = 200, description = "Get specified user, with its version in the ETag header", body = User),
         (status = 500, description = "Error Created by Query"),
     )
 )]
 #[get("/{uid}")]
 pub async fn get_user(state: Data<AppState>, path: Path<(String,)>) -> impl Responder {
     let (uid,) = path.into_inner();
-    match query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+    match query_as::<_, Versioned<User>>("SELECT * FROM users WHERE id = $1")
         .bind(uid)
-        .fetch_one(&state.db)
+        .fetch_one(Traced(&state.db))
         .await
     {
-        Ok(user) => HttpResponse::Ok().json(user),
+        Ok(user) => HttpResponse::Ok()
+            .insert_header(etag::etag(&user.updated_at))
+            .json(user.resource),
         Err(_) => HttpResponse::BadRequest().body("User Does Not Exist"),
     }
 }
@@ -71,10 +364,18 @@ pub async fn get_user(state: Data<AppState>, path: Path<(String,)>) -> impl Resp
     context_path = "/users",
     request_body(content=User, content_type="application/json", description="User Information"),
     responses(
-        (status = 201, description = "Updated user"),
+        (status = 201, description = "Updated user, with its new version in the ETag header", body = User),
+        (status = 400, description = "User Does Not Exist"),
         (status = 401, description = "Invalid/Missing API Key"),
+        (status = 412, description = "User changed since the version in If-Match"),
+        (status = 422, description = "Invalid fields, by field", body = ValidationErrors),
+        (status = 428, description = "Missing If-Match header"),
         (status = 500, description = "Error Created by Query"),
     ),
+    params(
+        ("uid", description = "Unique id of user"),
+        ("If-Match" = String, Header, description = "ETag of the version being edited, or * for any version"),
+    ),
     security(
         ("api_key" = [])
     )
@@ -84,35 +385,222 @@ pub async fn edit_user(
     state: Data<AppState>,
     path: Path<(String,)>,
     user: Json<User>,
+    req: HttpRequest,
 ) -> impl Responder {
     let (uid,) = path.into_inner();
-    if query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+    let errors = ValidationErrors::of(&*user);
+    if !errors.is_empty() {
+        return errors.response();
+    }
+    let precondition = match Precondition::from_request(&req) {
+        Ok(precondition) => precondition,
+        Err(res) => return res,
+    };
+    match query_as::<_, Versioned<User>>("SELECT * FROM users WHERE id = $1")
         .bind(&uid)
-        .fetch_one(&state.db)
+        .fetch_optional(Traced(&state.db))
         .await
-        .is_err()
     {
-        return HttpResponse::BadRequest().body("Tag Does Not Exist");
+        Ok(Some(current)) if precondition.matches(&current.updated_at) => {}
+        Ok(Some(_)) => return etag::precondition_failed(),
+        Ok(None) => return HttpResponse::BadRequest().body("User Does Not Exist"),
+        Err(e) => return internal_error("could not fetch user", e),
     }
-    match query("UPDATE users SET first_name = $1, last_name = $2, picture = $3, admin = $4, email = $5 WHERE id = $6")
-        .bind(&user.first_name)
-        .bind(&user.last_name)
-        .bind(&user.picture)
-        .bind(user.admin)
-        .bind(&user.email)
-        .bind(uid)
-        .execute(&state.db)
+    update_user(&state.db, &uid, &user, &precondition, StatusCode::CREATED).await
+}
+
+#[utoipa::path(
+    context_path = "/users",
+    request_body(content=Object, content_type="application/merge-patch+json", description="JSON Merge Patch of the user fields to change"),
+    responses(
+        (status = 200, description = "Updated user, with its new version in the ETag header", body = User),
+        (status = 400, description = "Missing user, or invalid patch or field"),
+        (status = 401, description = "Invalid/Missing API Key"),
+        (status = 412, description = "User changed since the version in If-Match"),
+        (status = 422, description = "Invalid fields, by field", body = ValidationErrors),
+        (status = 428, description = "Missing If-Match header"),
+        (status = 500, description = "Error Created by Query"),
+    ),
+    params(
+        ("uid", description = "Unique id of user"),
+        ("If-Match" = String, Header, description = "ETag of the version being edited, or * for any version"),
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[patch("/{uid}", wrap = "RequireApiKey")]
+pub async fn patch_user(
+    state: Data<AppState>,
+    path: Path<(String,)>,
+    user_patch: Json<Value>,
+    req: HttpRequest,
+) -> impl Responder {
+    let (uid,) = path.into_inner();
+    let precondition = match Precondition::from_request(&req) {
+        Ok(precondition) => precondition,
+        Err(res) => return res,
+    };
+    let current = match query_as::<_, Versioned<User>>("SELECT * FROM users WHERE id = $1")
+        .bind(&uid)
+        .fetch_optional(Traced(&state.db))
         .await
     {
-        Ok(_) => HttpResponse::Created().json(User {
-            id: user.id.clone(),
-            user_type: user.user_type.clone(),
-            first_name: user.first_name.clone(),
-            last_name: user.last_name.clone(),
-            picture: user.picture.clone(),
-            admin: user.admin,
-            email: user.email.clone(),
-        }),
-        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+        Ok(Some(current)) if precondition.matches(&current.updated_at) => current,
+        Ok(Some(_)) => return etag::precondition_failed(),
+        Ok(None) => return HttpResponse::BadRequest().body("User Does Not Exist"),
+        Err(e) => return internal_error("could not fetch user", e),
+    };
+    let user = match patch::apply(&current.resource, &user_patch, &USER_FIELDS) {
+        Ok(user) => user,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+    let errors = ValidationErrors::of(&user);
+    if !errors.is_empty() {
+        return errors.response();
+    }
+    update_user(&state.db, &uid, &user, &precondition, StatusCode::OK).await
+}
+
+/// Replace the user `uid` with `user` if it is still at a version the precondition allows, answering with `status`
+async fn update_user(
+    db: &Pool<Postgres>,
+    uid: &str,
+    user: &User,
+    precondition: &Precondition,
+    status: StatusCode,
+) -> HttpResponse {
+    match query_as::<_, Versioned<User>>(
+        "
+        UPDATE users SET user_type = $1, first_name = $2, last_name = $3, picture = $4, admin = $5,
+            email = $6, updated_at = now()
+        WHERE id = $7 AND ($8::timestamptz[] IS NULL OR updated_at = ANY($8))
+        RETURNING *
+        ",
+    )
+    .bind(&user.user_type)
+    .bind(&user.first_name)
+    .bind(&user.last_name)
+    .bind(&user.picture)
+    .bind(user.admin)
+    .bind(&user.email)
+    .bind(uid)
+    .bind(precondition.versions())
+    .fetch_optional(Traced(db))
+    .await
+    {
+        Ok(Some(user)) => HttpResponse::build(status)
+            .insert_header(etag::etag(&user.updated_at))
+            .json(user.resource),
+        Ok(None) => etag::precondition_failed(),
+        Err(e) => internal_error("could not update user", e),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/users",
+    request_body(content=LoginClaims, content_type="application/json", description="Identity claims from the login provider"),
+    responses(
+        (status = 200, description = "Updated existing user from the claims", body = SyncedUser),
+        (status = 201, description = "Created user from the claims", body = SyncedUser),
+        (status = 400, description = "Claims are missing a name or email"),
+        (status = 401, description = "Invalid/Missing API Key"),
+        (status = 422, description = "Invalid claims, by field", body = ValidationErrors),
+        (status = 500, description = "Error Created by Query"),
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[post("/login", wrap = "RequireApiKey")]
+pub async fn sync_user(state: Data<AppState>, claims: Json<LoginClaims>) -> impl Responder {
+    let claims = claims.into_inner();
+    let (mut user, entry) = match claims.user_type {
+        UserType::CSH => {
+            let entry = match ldap::lookup(state.config.ldap.as_ref(), &claims.id).await {
+                Ok(entry) => entry,
+                Err(e) => {
+                    log::warn!("LDAP lookup of {} failed: {}", claims.id, e);
+                    None
+                }
+            };
+            (User::from_csh(&claims.id, "", "", false), entry)
+        }
+        UserType::GOOGLE => match &claims.email {
+            Some(email) => (
+                User {
+                    id: claims.id.clone(),
+                    user_type: UserType::GOOGLE,
+                    first_name: String::new(),
+                    last_name: String::new(),
+                    picture: String::new(),
+                    admin: false,
+                    email: email.clone(),
+                },
+                None,
+            ),
+            None => return HttpResponse::BadRequest().body("Google Logins Require An Email"),
+        },
+    };
+    // The directory is authoritative for members it knows, claims fill in everything else
+    match (&entry, &claims.first_name, &claims.last_name) {
+        (
+            Some(DirectoryEntry {
+                first_name: Some(first_name),
+                last_name: Some(last_name),
+                ..
+            }),
+            _,
+            _,
+        )
+        | (_, Some(first_name), Some(last_name)) => {
+            user.first_name = first_name.clone();
+            user.last_name = last_name.clone();
+        }
+        _ => return HttpResponse::BadRequest().body("Missing first_name Or last_name"),
+    }
+    if let Some(picture) = claims.picture {
+        user.picture = picture;
+    }
+    if let Some(email) = claims.email {
+        user.email = email;
+    }
+    let errors = ValidationErrors::of(&user);
+    if !errors.is_empty() {
+        return errors.response();
+    }
+    // Without a directory entry, admin is only changed by editing the user
+    let admin = entry.as_ref().map(|entry| entry.admin);
+    // A login without a picture keeps the one already stored
+    let picture = Some(user.picture.as_str()).filter(|picture| !picture.is_empty());
+    let groups = entry.map(|entry| entry.groups).unwrap_or_default();
+    match query_as::<_, (User, bool)>(
+        "
+        INSERT INTO users AS u (id, user_type, first_name, last_name, picture, admin, email)
+        VALUES ($1, $2, $3, $4, COALESCE($5, ''), COALESCE($6, false), $7)
+        ON CONFLICT (id) DO UPDATE SET user_type = $2, first_name = $3, last_name = $4,
+            picture = COALESCE($5, u.picture), admin = COALESCE($6, u.admin), email = $7, updated_at = now()
+        RETURNING u, (xmax = 0)
+        ",
+    )
+    .bind(&user.id)
+    .bind(&user.user_type)
+    .bind(&user.first_name)
+    .bind(&user.last_name)
+    .bind(picture)
+    .bind(admin)
+    .bind(&user.email)
+    .fetch_one(Traced(&state.db))
+    .await
+    {
+        Ok((user, created)) => {
+            let synced = SyncedUser { user, groups };
+            if created {
+                HttpResponse::Created().json(synced)
+            } else {
+                HttpResponse::Ok().json(synced)
+            }
+        }
+        Err(e) => internal_error("could not sync user", e),
     }
 }
@@ -1,5 +1,33 @@
 #[cfg(test)]
-use crate::tests::{get_test_server, MCDADE_USER, MTFT_USER};
+use crate::tests::{get_test_server, MCDADE_USER, MTFT_USER, TEST_GAME_E, TEST_GAME_K};
+use crate::{
+    contributors::routes::ContributorData,
+    models::{ContributorRole, GameWithTags, SyncedUser, User, UserPage, UserType},
+    security::USER_HEADER,
+    users::routes::LoginClaims,
+    validate::ValidationErrors,
+};
+
+async fn sync(
+    srv: &actix_test::TestServer,
+    claims: &LoginClaims,
+) -> awc::ClientResponse<actix_web::dev::Decompress<actix_web::dev::Payload>> {
+    let req = srv
+        .post("/api/users/login")
+        .insert_header(("frontend_api_key", "TESTING"));
+    req.send_json(claims).await.unwrap()
+}
+
+async fn create_user(srv: &actix_test::TestServer, uid: &str) {
+    let req = srv
+        .post("/api/users/")
+        .insert_header(("frontend_api_key", "TESTING"));
+    let res = req
+        .send_json(&User::from_csh(uid, "Delete", "Me", false))
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+}
 
 #[actix_web::test]
 async fn test_get_user() {
@@ -47,9 +75,10 @@ async fn test_edit_user() {
     let srv = get_test_server().await;
     let req = srv
         .put(format!("/users/{}", MTFT_USER.id))
-        .insert_header(("frontend_api_key", "TESTING"));
+        .insert_header(("frontend_api_key", "TESTING"))
+        .insert_header(("If-Match", "*"));
     let mut edited_user = MTFT_USER.clone();
-    edited_user.picture = "CHANGE PICTURE".to_string();
+    edited_user.picture = "https://profiles.csh.rit.edu/image/changed".to_string();
     let mut res = req.send_json(&edited_user).await.unwrap();
     println!(
         "{} | {}",
@@ -73,3 +102,251 @@ async fn test_edit_user_unauthorized() {
     );
     assert_eq!(res.status().as_u16(), 401);
 }
+
+#[actix_web::test]
+async fn test_get_all_users() {
+    let srv = get_test_server().await;
+    let req = srv
+        .get("/api/users/")
+        .insert_header(("frontend_api_key", "TESTING"))
+        .query(&[("search", "SKY"), ("per_page", "5")])
+        .unwrap();
+    let mut res = req.send().await.unwrap();
+    assert!(res.status().is_success());
+    let page = res.json::<UserPage>().await.unwrap();
+    assert!(page.users.iter().any(|user| user.id == "skyz"));
+    assert_eq!(page.per_page, 5);
+
+    let req = srv
+        .get("/api/users/")
+        .insert_header(("frontend_api_key", "TESTING"))
+        .query(&[("search", "%")])
+        .unwrap();
+    let mut res = req.send().await.unwrap();
+    let page = res.json::<UserPage>().await.unwrap();
+    assert_eq!(page.total, 0);
+
+    let req = srv
+        .get("/api/users/?user_type=CSH")
+        .insert_header(("frontend_api_key", "TESTING"));
+    let mut res = req.send().await.unwrap();
+    let page = res.json::<UserPage>().await.unwrap();
+    assert!(page.total > 0);
+    assert!(page
+        .users
+        .iter()
+        .all(|user| user.user_type == UserType::CSH));
+}
+
+#[actix_web::test]
+async fn test_get_all_users_unauthorized() {
+    let srv = get_test_server().await;
+    let res = srv.get("/api/users/").send().await.unwrap();
+    assert_eq!(res.status().as_u16(), 401);
+}
+
+#[actix_web::test]
+async fn test_get_all_users_page_overflow() {
+    let srv = get_test_server().await;
+    let req = srv
+        .get("/api/users/")
+        .insert_header(("frontend_api_key", "TESTING"))
+        .query(&[("page", i64::MAX), ("per_page", 100)])
+        .unwrap();
+    let res = req.send().await.unwrap();
+    assert_eq!(res.status().as_u16(), 400);
+}
+
+#[actix_web::test]
+async fn test_get_user_games() {
+    let srv = get_test_server().await;
+    let req = srv.get(format!("/api/users/{}/games", TEST_GAME_K.author));
+    let mut res = req.send().await.unwrap();
+    assert!(res.status().is_success());
+    let games = res.json::<Vec<GameWithTags>>().await.unwrap();
+    assert!(games.iter().any(|game| game.id == TEST_GAME_K.id));
+}
+
+#[actix_web::test]
+async fn test_delete_user_reassign() {
+    let srv = get_test_server().await;
+    create_user(&srv, "reassignme").await;
+    let req = srv
+        .put(format!(
+            "/api/games/{}/contributors/reassignme",
+            TEST_GAME_E.id
+        ))
+        .insert_header(("frontend_api_key", "TESTING"))
+        .insert_header((USER_HEADER, TEST_GAME_E.author.as_str()));
+    let res = req
+        .send_json(&ContributorData {
+            role: ContributorRole::Owner,
+        })
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+
+    let req = srv
+        .delete("/api/users/reassignme?reassign_to=ella")
+        .insert_header(("frontend_api_key", "TESTING"));
+    let res = req.send().await.unwrap();
+    assert!(res.status().is_success());
+    let req = srv.get("/api/users/ella/games");
+    let mut res = req.send().await.unwrap();
+    let games = res.json::<Vec<GameWithTags>>().await.unwrap();
+    assert!(games.iter().any(|game| game.id == TEST_GAME_E.id));
+}
+
+#[actix_web::test]
+async fn test_delete_user() {
+    let srv = get_test_server().await;
+    create_user(&srv, "deleteme").await;
+    let req = srv
+        .delete("/api/users/deleteme")
+        .insert_header(("frontend_api_key", "TESTING"));
+    let res = req.send().await.unwrap();
+    assert!(res.status().is_success());
+    let req = srv
+        .delete("/api/users/deleteme")
+        .insert_header(("frontend_api_key", "TESTING"));
+    let res = req.send().await.unwrap();
+    assert_eq!(res.status().as_u16(), 400);
+}
+
+#[actix_web::test]
+async fn test_sync_user() {
+    let srv = get_test_server().await;
+    let mut claims = LoginClaims {
+        id: "synccsh".to_string(),
+        user_type: UserType::CSH,
+        first_name: Some("Sync".to_string()),
+        last_name: Some("Member".to_string()),
+        picture: None,
+        email: None,
+    };
+    let mut res = sync(&srv, &claims).await;
+    assert_eq!(res.status().as_u16(), 201);
+    let synced = res.json::<SyncedUser>().await.unwrap();
+    assert_eq!(
+        synced.user,
+        User::from_csh("synccsh", "Sync", "Member", false)
+    );
+
+    claims.first_name = Some("Renamed".to_string());
+    let mut res = sync(&srv, &claims).await;
+    assert_eq!(res.status().as_u16(), 200);
+    assert_eq!(
+        res.json::<SyncedUser>().await.unwrap().user.first_name,
+        "Renamed"
+    );
+
+    let mut claims = LoginClaims {
+        id: "109876543210".to_string(),
+        user_type: UserType::GOOGLE,
+        first_name: Some("Goo".to_string()),
+        last_name: Some("Gle".to_string()),
+        picture: Some("https://example.com/me.png".to_string()),
+        email: None,
+    };
+    assert_eq!(sync(&srv, &claims).await.status().as_u16(), 400);
+
+    // A later login without a picture keeps the stored one
+    claims.email = Some("goo@example.com".to_string());
+    assert_eq!(sync(&srv, &claims).await.status().as_u16(), 201);
+    claims.picture = None;
+    let mut res = sync(&srv, &claims).await;
+    assert_eq!(res.status().as_u16(), 200);
+    assert_eq!(
+        res.json::<SyncedUser>().await.unwrap().user.picture,
+        "https://example.com/me.png"
+    );
+}
+
+#[actix_web::test]
+async fn test_sync_user_ldap() {
+    // Only runs against the LDAP stand-in of the test compose file
+    if std::env::var("LDAP_URI").is_err() {
+        return;
+    }
+    let srv = get_test_server().await;
+    let claims = LoginClaims {
+        id: "ldapsync".to_string(),
+        user_type: UserType::CSH,
+        first_name: None,
+        last_name: None,
+        picture: None,
+        email: None,
+    };
+    let mut res = sync(&srv, &claims).await;
+    assert!(res.status().is_success());
+    let synced = res.json::<SyncedUser>().await.unwrap();
+    assert_eq!(
+        synced.user,
+        User::from_csh("ldapsync", "Lydia", "Dap", true)
+    );
+    assert!(synced.groups.contains(&"member".to_string()));
+}
+
+#[actix_web::test]
+async fn test_edit_user_type() {
+    let srv = get_test_server().await;
+    create_user(&srv, "switchtype").await;
+    let mut user = User::from_csh("switchtype", "Delete", "Me", false);
+    user.user_type = UserType::GOOGLE;
+    let req = srv
+        .put("/api/users/switchtype")
+        .insert_header(("frontend_api_key", "TESTING"))
+        .insert_header(("If-Match", "*"));
+    let res = req.send_json(&user).await.unwrap();
+    assert!(res.status().is_success());
+    let req = srv.get("/api/users/switchtype");
+    let mut res = req.send().await.unwrap();
+    assert_eq!(
+        res.json::<User>().await.unwrap().user_type,
+        UserType::GOOGLE
+    );
+}
+
+#[actix_web::test]
+async fn test_patch_user() {
+    let srv = get_test_server().await;
+    create_user(&srv, "patchme").await;
+    let patch = |body: serde_json::Value| {
+        let req = srv
+            .patch("/api/users/patchme")
+            .insert_header(("frontend_api_key", "TESTING"))
+            .insert_header(("If-Match", "*"))
+            .content_type("application/merge-patch+json");
+        async move { req.send_body(body.to_string()).await.unwrap() }
+    };
+
+    let mut res = patch(serde_json::json!({"first_name": "Patched"})).await;
+    assert_eq!(res.status().as_u16(), 200);
+    let user = res.json::<User>().await.unwrap();
+    assert_eq!(user.first_name, "Patched");
+    assert_eq!(user.last_name, "Me");
+
+    let res = patch(serde_json::json!({"id": "someone"})).await;
+    assert_eq!(res.status().as_u16(), 400);
+    let res = patch(serde_json::json!({"last_name": "M".repeat(33)})).await;
+    assert_eq!(res.status().as_u16(), 422);
+}
+
+#[actix_web::test]
+async fn test_add_user_invalid() {
+    let srv = get_test_server().await;
+    let mut user = User::from_csh("invalid", "", "Me", false);
+    user.picture = "not a url".to_string();
+    user.email = "invalid".to_string();
+    let req = srv
+        .post("/api/users/")
+        .insert_header(("frontend_api_key", "TESTING"));
+    let mut res = req.send_json(&user).await.unwrap();
+    assert_eq!(res.status().as_u16(), 422);
+    let errors = res.json::<ValidationErrors>().await.unwrap().errors;
+    let fields: Vec<&str> = errors.keys().map(String::as_str).collect();
+    assert_eq!(fields, vec!["email", "first_name", "picture"]);
+
+    let req = srv.get("/api/users/invalid");
+    assert_eq!(req.send().await.unwrap().status().as_u16(), 400);
+}
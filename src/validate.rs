@@ -0,0 +1,132 @@
+use crate::telemetry::query::Traced;
+use actix_web::HttpResponse;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sqlx::{query_scalar, Pool, Postgres};
+use std::collections::BTreeMap;
+use url::Url;
+use utoipa::ToSchema;
+
+lazy_static! {
+    static ref EMAIL: Regex = Regex::new(r"^[^@\s]+@[^@\s]+\.[^@\s]+$").unwrap();
+}
+
+/// Problems found with the fields of a request body, returned as `422 Unprocessable Entity`
+#[derive(Debug, Default, Clone, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct ValidationErrors {
+    /// Messages for every invalid field, by field name
+    #[schema(value_type = Object, example = json!({"name": ["must be between 1 and 128 characters"]}))]
+    pub errors: BTreeMap<String, Vec<String>>,
+}
+
+/// Checks of a request body that need nothing but the body itself
+pub trait Validate {
+    fn validate(&self, errors: &mut ValidationErrors);
+}
+
+impl ValidationErrors {
+    /// Problems `value` has on its own, before references to other rows are checked
+    pub fn of(value: &impl Validate) -> ValidationErrors {
+        let mut errors = ValidationErrors::default();
+        value.validate(&mut errors);
+        errors
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    pub fn add(&mut self, field: &str, message: impl Into<String>) {
+        self.errors
+            .entry(field.to_string())
+            .or_default()
+            .push(message.into());
+    }
+
+    /// Require between `min` and `max` characters, not counting surrounding whitespace
+    pub fn length(&mut self, field: &str, value: &str, min: usize, max: usize) {
+        let length = value.trim().chars().count();
+        if length < min || length > max {
+            self.add(
+                field,
+                format!("must be between {} and {} characters", min, max),
+            );
+        }
+    }
+
+    /// Require every item of a list to be between `min` and `max` characters
+    pub fn each_length(&mut self, field: &str, values: &[String], min: usize, max: usize) {
+        for value in values {
+            let length = value.trim().chars().count();
+            if length < min || length > max {
+                self.add(
+                    field,
+                    format!("{:?} must be between {} and {} characters", value, min, max),
+                );
+            }
+        }
+    }
+
+    pub fn pattern(&mut self, field: &str, value: &str, pattern: &Regex, expected: &str) {
+        if !pattern.is_match(value) {
+            self.add(field, format!("must be {}", expected));
+        }
+    }
+
+    pub fn email(&mut self, field: &str, value: &str) {
+        self.pattern(field, value, &EMAIL, "an email address");
+    }
+
+    /// Require an absolute http(s) URL
+    pub fn url(&mut self, field: &str, value: &str) {
+        match Url::parse(value) {
+            Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {}
+            _ => self.add(field, "must be an http or https URL"),
+        }
+    }
+
+    /// Record an error on `field` unless the user `uid` exists
+    pub async fn user_exists(
+        &mut self,
+        db: &Pool<Postgres>,
+        field: &str,
+        uid: &str,
+    ) -> Result<(), sqlx::Error> {
+        if !query_scalar::<_, bool>("SELECT EXISTS (SELECT 1 FROM users WHERE id = $1)")
+            .bind(uid)
+            .fetch_one(Traced(db))
+            .await?
+        {
+            self.add(field, format!("user {} does not exist", uid));
+        }
+        Ok(())
+    }
+
+    /// Record an error on `field` for each of the `tags` that does not exist, either as a tag or
+    /// as the old name of one
+    pub async fn tags_exist(
+        &mut self,
+        db: &Pool<Postgres>,
+        field: &str,
+        tags: &[String],
+    ) -> Result<(), sqlx::Error> {
+        let known = query_scalar::<_, String>(
+            "
+            SELECT name FROM tags WHERE name = ANY($1)
+            UNION SELECT alias FROM tag_aliases WHERE alias = ANY($1)
+            ",
+        )
+        .bind(tags)
+        .fetch_all(Traced(db))
+        .await?;
+        for tag in tags.iter().filter(|tag| !known.contains(tag)) {
+            self.add(field, format!("tag {} does not exist", tag));
+        }
+        Ok(())
+    }
+
+    pub fn response(&self) -> HttpResponse {
+        HttpResponse::UnprocessableEntity().json(self)
+    }
+}
@@ -0,0 +1,192 @@
+use crate::{
+    models::{CatalogEvent, WebhookDeliveryStatus},
+    security::{sign, SecretKey, SIGNATURE_HEADER},
+};
+use actix_web::rt::time::{sleep, timeout};
+use awc::Client;
+use serde::{Deserialize, Serialize};
+use sqlx::{query, query_as, FromRow, Pool, Postgres};
+use std::time::Duration;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+
+pub const EVENT_HEADER: &str = "X-Devcade-Event";
+pub const DELIVERY_HEADER: &str = "X-Devcade-Delivery";
+
+const MAX_ATTEMPTS: i32 = 8;
+const BASE_BACKOFF_SECS: i64 = 30;
+const BATCH_SIZE: i64 = 16;
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct WebhookPayload {
+    pub delivery_id: i64,
+    pub event: CatalogEvent,
+}
+
+#[derive(FromRow)]
+struct ClaimedDelivery {
+    id: i64,
+    event_id: i64,
+    attempts: i32,
+    url: String,
+    /// Encrypted with [`SecretKey::seal`]
+    secret: String,
+}
+
+/// Seconds to wait before retrying a delivery that has failed `attempts` times
+pub fn backoff(attempts: i32) -> i64 {
+    BASE_BACKOFF_SECS << (attempts - 1).clamp(0, 16)
+}
+
+/// Deliver queued webhooks until the process exits. Deliveries are claimed with
+/// `SKIP LOCKED`, so every replica can run a worker without sending duplicates.
+pub async fn run(db: Pool<Postgres>, key: Option<SecretKey>, mut events: Receiver<CatalogEvent>) {
+    let client = Client::builder().timeout(DELIVERY_TIMEOUT).finish();
+    loop {
+        loop {
+            match deliver_pending(&db, &client, key.as_ref()).await {
+                Ok(claimed) if claimed == BATCH_SIZE => continue,
+                Ok(_) => break,
+                Err(e) => {
+                    log::warn!("Could not claim webhook deliveries: {}", e);
+                    break;
+                }
+            }
+        }
+        // Wake early when a new event may have queued deliveries
+        if let Ok(Err(RecvError::Closed)) = timeout(POLL_INTERVAL, events.recv()).await {
+            sleep(POLL_INTERVAL).await;
+        }
+    }
+}
+
+async fn deliver_pending(
+    db: &Pool<Postgres>,
+    client: &Client,
+    key: Option<&SecretKey>,
+) -> Result<i64, sqlx::Error> {
+    // Push next_attempt_at forward while the attempt is in flight so a crashed worker's
+    // claim is picked up again later
+    let claimed = query_as::<_, ClaimedDelivery>(
+        "
+        WITH claimed AS (
+            UPDATE webhook_deliveries
+            SET attempts = attempts + 1, next_attempt_at = now() + interval '5 minutes'
+            WHERE id IN (
+                SELECT id FROM webhook_deliveries
+                WHERE status = 'pending' AND next_attempt_at <= now()
+                ORDER BY next_attempt_at ASC
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, webhook_id, event_id, attempts
+        )
+        SELECT claimed.id, claimed.event_id, claimed.attempts, webhooks.url, webhooks.secret
+        FROM claimed
+        JOIN webhooks ON webhooks.id = claimed.webhook_id
+        ",
+    )
+    .bind(BATCH_SIZE)
+    .fetch_all(db)
+    .await?;
+    let count = claimed.len() as i64;
+    for delivery in claimed {
+        // The claim already schedules a retry, so one failing delivery leaves the rest going
+        if let Err(e) = deliver(db, client, key, &delivery).await {
+            log::warn!("Could not record webhook delivery {}: {}", delivery.id, e);
+        }
+    }
+    Ok(count)
+}
+
+async fn deliver(
+    db: &Pool<Postgres>,
+    client: &Client,
+    key: Option<&SecretKey>,
+    delivery: &ClaimedDelivery,
+) -> Result<(), sqlx::Error> {
+    let event = query_as::<_, CatalogEvent>("SELECT * FROM catalog_events WHERE id = $1")
+        .bind(delivery.event_id)
+        .fetch_one(db)
+        .await?;
+    let (status_code, error) = match key.map(|key| key.open(&delivery.secret)) {
+        Some(Some(secret)) => send(client, delivery, &secret, event).await,
+        Some(None) => (
+            None,
+            Some("Could not decrypt the webhook secret".to_string()),
+        ),
+        None => (
+            None,
+            Some("WEBHOOK_SECRET_KEY is not configured".to_string()),
+        ),
+    };
+    let delivered = matches!(status_code, Some(code) if (200..300).contains(&code));
+    if delivered {
+        query(
+            "
+            UPDATE webhook_deliveries
+            SET status = 'delivered', delivered_at = now(), last_status_code = $2, last_error = NULL
+            WHERE id = $1
+            ",
+        )
+        .bind(delivery.id)
+        .bind(status_code)
+        .execute(db)
+        .await?;
+    } else {
+        let status = if delivery.attempts >= MAX_ATTEMPTS {
+            WebhookDeliveryStatus::Failed
+        } else {
+            WebhookDeliveryStatus::Pending
+        };
+        query(
+            "
+            UPDATE webhook_deliveries
+            SET status = $2, next_attempt_at = now() + make_interval(secs => $3),
+                last_status_code = $4, last_error = $5
+            WHERE id = $1
+            ",
+        )
+        .bind(delivery.id)
+        .bind(status)
+        .bind(backoff(delivery.attempts) as f64)
+        .bind(status_code)
+        .bind(error)
+        .execute(db)
+        .await?;
+    }
+    Ok(())
+}
+
+async fn send(
+    client: &Client,
+    delivery: &ClaimedDelivery,
+    secret: &str,
+    event: CatalogEvent,
+) -> (Option<i32>, Option<String>) {
+    let event_type = event.event_type.name();
+    let body = match serde_json::to_vec(&WebhookPayload {
+        delivery_id: delivery.id,
+        event,
+    }) {
+        Ok(body) => body,
+        Err(e) => return (None, Some(e.to_string())),
+    };
+    match client
+        .post(&delivery.url)
+        .content_type("application/json")
+        .insert_header((SIGNATURE_HEADER, sign(secret, &body)))
+        .insert_header((EVENT_HEADER, event_type))
+        .insert_header((DELIVERY_HEADER, delivery.id.to_string()))
+        .send_body(body)
+        .await
+    {
+        Ok(res) if res.status().is_success() => (Some(res.status().as_u16() as i32), None),
+        Ok(res) => (
+            Some(res.status().as_u16() as i32),
+            Some(format!("Receiver responded with {}", res.status())),
+        ),
+        Err(e) => (None, Some(e.to_string())),
+    }
+}
@@ -0,0 +1,4 @@
+pub mod delivery;
+pub mod routes;
+#[cfg(test)]
+pub mod tests;
@@ -0,0 +1,242 @@
+use crate::{
+    logging::middleware::internal_error,
+    models::{AppState, CatalogEventType, Webhook, WebhookDelivery},
+    security::RequireApiKey,
+    telemetry::query::Traced,
+};
+use actix_web::{
+    delete, get, post, put,
+    web::{Data, Json, Path},
+    HttpResponse, Responder,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{query, query_as};
+use utoipa::ToSchema;
+
+fn default_active() -> bool {
+    true
+}
+
+/// `secret` encrypted for storage, or the response to give when it can't be
+fn seal_secret(state: &AppState, secret: &str) -> Result<String, HttpResponse> {
+    match &state.config.webhook_secret_key {
+        Some(key) => key
+            .seal(secret)
+            .map_err(|e| internal_error("could not encrypt webhook secret", e)),
+        None => {
+            Err(HttpResponse::ServiceUnavailable().body("WEBHOOK_SECRET_KEY is not configured"))
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct WebhookData {
+    #[schema(example = "https://hooks.example.com/devcade")]
+    pub url: String,
+    /// Key for the HMAC-SHA256 signature sent in `X-Devcade-Signature`, stored encrypted and
+    /// never returned
+    #[schema(example = "correct-horse-battery-staple")]
+    pub secret: String,
+    #[schema(example = json!([CatalogEventType::GameCreated, CatalogEventType::GameDeleted]))]
+    pub event_types: Vec<CatalogEventType>,
+    #[serde(default = "default_active")]
+    #[schema(example = true)]
+    pub active: bool,
+}
+
+#[utoipa::path(
+    context_path = "/webhooks",
+    responses(
+        (status = 200, description = "List all webhooks", body = [Webhook]),
+        (status = 401, description = "Invalid/Missing API Key"),
+        (status = 500, description = "Error Created by Query"),
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/", wrap = "RequireApiKey")]
+pub async fn get_all_webhooks(state: Data<AppState>) -> impl Responder {
+    match query_as::<_, Webhook>("SELECT * FROM webhooks ORDER BY id ASC")
+        .fetch_all(Traced(&state.db))
+        .await
+    {
+        Ok(webhooks) => HttpResponse::Ok().json(webhooks),
+        Err(e) => internal_error("could not fetch webhooks", e),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/webhooks",
+    request_body(content=WebhookData, content_type="application/json", description="Webhook Information"),
+    responses(
+        (status = 201, description = "Created new webhook", body = Webhook),
+        (status = 401, description = "Invalid/Missing API Key"),
+        (status = 500, description = "Error Created by Query"),
+        (status = 503, description = "No key is configured to encrypt webhook secrets with"),
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[post("/", wrap = "RequireApiKey")]
+pub async fn add_webhook(state: Data<AppState>, webhook: Json<WebhookData>) -> impl Responder {
+    let secret = match seal_secret(&state, &webhook.secret) {
+        Ok(secret) => secret,
+        Err(res) => return res,
+    };
+    match query_as::<_, Webhook>(
+        "INSERT INTO webhooks (url, secret, event_types, active) VALUES ($1, $2, $3, $4) RETURNING *",
+    )
+    .bind(&webhook.url)
+    .bind(&secret)
+    .bind(&webhook.event_types)
+    .bind(webhook.active)
+    .fetch_one(Traced(&state.db))
+    .await
+    {
+        Ok(webhook) => HttpResponse::Created().json(webhook),
+        Err(e) => internal_error("could not add webhook", e),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/webhooks",
+    responses(
+        (status = 200, description = "Get specified webhook", body = Webhook),
+        (status = 400, description = "Missing webhook"),
+        (status = 401, description = "Invalid/Missing API Key"),
+    ),
+    params(
+        ("id", description = "Unique id of webhook")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/{id}", wrap = "RequireApiKey")]
+pub async fn get_webhook(state: Data<AppState>, path: Path<(i32,)>) -> impl Responder {
+    let (id,) = path.into_inner();
+    match query_as::<_, Webhook>("SELECT * FROM webhooks WHERE id = $1")
+        .bind(id)
+        .fetch_one(Traced(&state.db))
+        .await
+    {
+        Ok(webhook) => HttpResponse::Ok().json(webhook),
+        Err(_) => HttpResponse::BadRequest().body("Webhook Does Not Exist"),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/webhooks",
+    request_body(content=WebhookData, content_type="application/json", description="Webhook Information"),
+    responses(
+        (status = 200, description = "Updated webhook", body = Webhook),
+        (status = 400, description = "Missing webhook"),
+        (status = 401, description = "Invalid/Missing API Key"),
+        (status = 500, description = "Error Created by Query"),
+        (status = 503, description = "No key is configured to encrypt webhook secrets with"),
+    ),
+    params(
+        ("id", description = "Unique id of webhook")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[put("/{id}", wrap = "RequireApiKey")]
+pub async fn edit_webhook(
+    state: Data<AppState>,
+    path: Path<(i32,)>,
+    webhook: Json<WebhookData>,
+) -> impl Responder {
+    let (id,) = path.into_inner();
+    let secret = match seal_secret(&state, &webhook.secret) {
+        Ok(secret) => secret,
+        Err(res) => return res,
+    };
+    match query_as::<_, Webhook>(
+        "UPDATE webhooks SET url = $1, secret = $2, event_types = $3, active = $4 WHERE id = $5 RETURNING *",
+    )
+    .bind(&webhook.url)
+    .bind(&secret)
+    .bind(&webhook.event_types)
+    .bind(webhook.active)
+    .bind(id)
+    .fetch_optional(Traced(&state.db))
+    .await
+    {
+        Ok(Some(webhook)) => HttpResponse::Ok().json(webhook),
+        Ok(None) => HttpResponse::BadRequest().body("Webhook Does Not Exist"),
+        Err(e) => internal_error("could not edit webhook", e),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/webhooks",
+    responses(
+        (status = 200, description = "Delete webhook and its delivery log"),
+        (status = 400, description = "Missing webhook"),
+        (status = 401, description = "Invalid/Missing API Key"),
+        (status = 500, description = "Error Created by Deletion"),
+    ),
+    params(
+        ("id", description = "Unique id of webhook")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[delete("/{id}", wrap = "RequireApiKey")]
+pub async fn delete_webhook(state: Data<AppState>, path: Path<(i32,)>) -> impl Responder {
+    let (id,) = path.into_inner();
+    match query("DELETE FROM webhooks WHERE id = $1")
+        .bind(id)
+        .execute(Traced(&state.db))
+        .await
+    {
+        Ok(result) if result.rows_affected() == 0 => {
+            HttpResponse::BadRequest().body("Webhook Does Not Exist")
+        }
+        Ok(_) => HttpResponse::Ok().finish(),
+        Err(e) => internal_error("could not delete webhook", e),
+    }
+}
+
+#[utoipa::path(
+    context_path = "/webhooks",
+    responses(
+        (status = 200, description = "Delivery log of webhook, newest first", body = [WebhookDelivery]),
+        (status = 400, description = "Missing webhook"),
+        (status = 401, description = "Invalid/Missing API Key"),
+        (status = 500, description = "Error Created by Query"),
+    ),
+    params(
+        ("id", description = "Unique id of webhook")
+    ),
+    security(
+        ("api_key" = [])
+    )
+)]
+#[get("/{id}/deliveries", wrap = "RequireApiKey")]
+pub async fn get_webhook_deliveries(state: Data<AppState>, path: Path<(i32,)>) -> impl Responder {
+    let (id,) = path.into_inner();
+    if query_as::<_, Webhook>("SELECT * FROM webhooks WHERE id = $1")
+        .bind(id)
+        .fetch_one(Traced(&state.db))
+        .await
+        .is_err()
+    {
+        return HttpResponse::BadRequest().body("Webhook Does Not Exist");
+    }
+    match query_as::<_, WebhookDelivery>(
+        "SELECT * FROM webhook_deliveries WHERE webhook_id = $1 ORDER BY id DESC",
+    )
+    .bind(id)
+    .fetch_all(Traced(&state.db))
+    .await
+    {
+        Ok(deliveries) => HttpResponse::Ok().json(deliveries),
+        Err(e) => internal_error("could not fetch webhook deliveries", e),
+    }
+}
@@ -0,0 +1,293 @@
+#[cfg(test)]
+use crate::tests::{get_test_server, TEST_GAME_H};
+use crate::{
+    app::get_app_data,
+    config::Config,
+    games::routes::GameTagsPatch,
+    models::{CatalogEventType, Tag, Webhook, WebhookDelivery, WebhookDeliveryStatus},
+    security::{sign, SecretKey, SIGNATURE_HEADER},
+    webhooks::{
+        delivery::{backoff, WebhookPayload, EVENT_HEADER},
+        routes::WebhookData,
+    },
+};
+use actix_web::{
+    rt::time::sleep,
+    web::{self, Bytes, Data},
+    App, HttpRequest, HttpResponse,
+};
+use sqlx::query_scalar;
+use std::{sync::Mutex, time::Duration};
+
+struct Received {
+    status: u16,
+    requests: Mutex<Vec<(String, String, Bytes)>>,
+}
+
+async fn receive(req: HttpRequest, body: Bytes, received: Data<Received>) -> HttpResponse {
+    let header = |name: &str| {
+        req.headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string()
+    };
+    received
+        .requests
+        .lock()
+        .unwrap()
+        .push((header(SIGNATURE_HEADER), header(EVENT_HEADER), body));
+    HttpResponse::build(actix_web::http::StatusCode::from_u16(received.status).unwrap()).finish()
+}
+
+fn start_receiver(status: u16) -> (actix_test::TestServer, Data<Received>) {
+    let received = Data::new(Received {
+        status,
+        requests: Mutex::new(vec![]),
+    });
+    let app_data = received.clone();
+    let srv = actix_test::start(move || {
+        App::new()
+            .app_data(app_data.clone())
+            .route("/hook", web::post().to(receive))
+    });
+    (srv, received)
+}
+
+async fn create_webhook(
+    srv: &actix_test::TestServer,
+    url: String,
+    secret: &str,
+    event_types: Vec<CatalogEventType>,
+) -> Webhook {
+    let req = srv
+        .post("/api/webhooks/")
+        .insert_header(("frontend_api_key", "TESTING"));
+    let mut res = req
+        .send_json(&WebhookData {
+            url,
+            secret: secret.to_string(),
+            event_types,
+            active: true,
+        })
+        .await
+        .unwrap();
+    assert_eq!(res.status().as_u16(), 201);
+    res.json::<Webhook>().await.unwrap()
+}
+
+async fn create_tag(srv: &actix_test::TestServer, name: &str) {
+    let req = srv
+        .post("/api/tags/")
+        .insert_header(("frontend_api_key", "TESTING"));
+    let res = req
+        .send_json(&Tag {
+            name: name.to_string(),
+            description: "Tag created to trigger a webhook".to_string(),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+}
+
+#[test]
+fn test_webhook_backoff() {
+    assert_eq!(backoff(1), 30);
+    assert_eq!(backoff(2), 60);
+    assert_eq!(backoff(4), 240);
+}
+
+#[test]
+fn test_secret_key() {
+    let key = "00112233445566778899aabbccddeeff00112233445566778899aabbccddeeff"
+        .parse::<SecretKey>()
+        .unwrap();
+    let sealed = key.seal("WEBHOOK_SECRET").unwrap();
+    assert!(!sealed.contains("WEBHOOK_SECRET"));
+    assert_ne!(sealed, key.seal("WEBHOOK_SECRET").unwrap());
+    assert_eq!(key.open(&sealed).as_deref(), Some("WEBHOOK_SECRET"));
+    let other = "ffeeddccbbaa99887766554433221100ffeeddccbbaa99887766554433221100"
+        .parse::<SecretKey>()
+        .unwrap();
+    assert_eq!(other.open(&sealed), None);
+    assert!("0011".parse::<SecretKey>().is_err());
+}
+
+#[actix_web::test]
+async fn test_get_all_webhooks_unauthorized() {
+    let srv = get_test_server().await;
+    let req = srv.get("/api/webhooks/");
+    let res = req.send().await.unwrap();
+    assert_eq!(res.status().as_u16(), 401);
+}
+
+#[actix_web::test]
+async fn test_webhook_delivered_and_signed() {
+    let srv = get_test_server().await;
+    let (receiver, received) = start_receiver(200);
+    let webhook = create_webhook(
+        &srv,
+        receiver.url("/hook"),
+        "WEBHOOK_SECRET",
+        vec![CatalogEventType::TagCreated],
+    )
+    .await;
+    create_tag(&srv, "WEBHOOK_SIGNED_TAG").await;
+    let mut payload = None;
+    for _ in 0..100 {
+        let requests = received.requests.lock().unwrap().clone();
+        payload = requests.into_iter().find_map(|(signature, event, body)| {
+            let delivered = serde_json::from_slice::<WebhookPayload>(&body).ok()?;
+            (delivered.event.tag_name.as_deref() == Some("WEBHOOK_SIGNED_TAG"))
+                .then_some((signature, event, body))
+        });
+        if payload.is_some() {
+            break;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+    let (signature, event, body) = payload.expect("webhook was not delivered");
+    assert_eq!(event, "tag_created");
+    assert_eq!(signature, sign("WEBHOOK_SECRET", &body));
+
+    let mut deliveries = vec![];
+    for _ in 0..50 {
+        let req = srv
+            .get(format!("/api/webhooks/{}/deliveries", webhook.id))
+            .insert_header(("frontend_api_key", "TESTING"));
+        let mut res = req.send().await.unwrap();
+        assert!(res.status().is_success());
+        deliveries = res.json::<Vec<WebhookDelivery>>().await.unwrap();
+        if deliveries
+            .iter()
+            .any(|delivery| delivery.status == WebhookDeliveryStatus::Delivered)
+        {
+            break;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+    assert!(deliveries.iter().any(|delivery| {
+        delivery.status == WebhookDeliveryStatus::Delivered
+            && delivery.last_status_code == Some(200)
+    }));
+
+    let req = srv
+        .delete(format!("/api/webhooks/{}", webhook.id))
+        .insert_header(("frontend_api_key", "TESTING"));
+    let res = req.send().await.unwrap();
+    assert!(res.status().is_success());
+}
+
+#[actix_web::test]
+async fn test_webhook_failure_scheduled_for_retry() {
+    let srv = get_test_server().await;
+    let (receiver, _) = start_receiver(500);
+    let webhook = create_webhook(
+        &srv,
+        receiver.url("/hook"),
+        "WEBHOOK_SECRET",
+        vec![CatalogEventType::TagCreated],
+    )
+    .await;
+    create_tag(&srv, "WEBHOOK_FAILING_TAG").await;
+    let mut deliveries = vec![];
+    for _ in 0..100 {
+        let req = srv
+            .get(format!("/api/webhooks/{}/deliveries", webhook.id))
+            .insert_header(("frontend_api_key", "TESTING"));
+        let mut res = req.send().await.unwrap();
+        deliveries = res.json::<Vec<WebhookDelivery>>().await.unwrap();
+        if deliveries
+            .iter()
+            .any(|delivery| delivery.last_status_code.is_some())
+        {
+            break;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+    let delivery = deliveries
+        .iter()
+        .find(|delivery| delivery.last_status_code.is_some())
+        .expect("webhook delivery was not attempted");
+    assert_eq!(delivery.status, WebhookDeliveryStatus::Pending);
+    assert_eq!(delivery.last_status_code, Some(500));
+    assert_eq!(delivery.attempts, 1);
+    assert!(delivery.next_attempt_at > delivery.created_at);
+
+    let req = srv
+        .delete(format!("/api/webhooks/{}", webhook.id))
+        .insert_header(("frontend_api_key", "TESTING"));
+    let res = req.send().await.unwrap();
+    assert!(res.status().is_success());
+}
+
+#[actix_web::test]
+async fn test_webhook_secret_encrypted() {
+    let srv = get_test_server().await;
+    let webhook = create_webhook(
+        &srv,
+        "https://hooks.example.com/devcade".to_string(),
+        "WEBHOOK_STORED_SECRET",
+        vec![CatalogEventType::GameDeleted],
+    )
+    .await;
+    let state = get_app_data(Config::load().unwrap()).await.unwrap();
+    let stored = query_scalar::<_, String>("SELECT secret FROM webhooks WHERE id = $1")
+        .bind(webhook.id)
+        .fetch_one(&state.db)
+        .await
+        .unwrap();
+    assert!(!stored.contains("WEBHOOK_STORED_SECRET"));
+    let key = state.config.webhook_secret_key.as_ref().unwrap();
+    assert_eq!(key.open(&stored).as_deref(), Some("WEBHOOK_STORED_SECRET"));
+
+    let req = srv
+        .delete(format!("/api/webhooks/{}", webhook.id))
+        .insert_header(("frontend_api_key", "TESTING"));
+    let res = req.send().await.unwrap();
+    assert!(res.status().is_success());
+}
+
+#[actix_web::test]
+async fn test_webhook_game_approved() {
+    let srv = get_test_server().await;
+    let (receiver, received) = start_receiver(200);
+    let webhook = create_webhook(
+        &srv,
+        receiver.url("/hook"),
+        "WEBHOOK_SECRET",
+        vec![CatalogEventType::GameApproved],
+    )
+    .await;
+    let req = srv
+        .patch(format!("/api/games/{}/tags", TEST_GAME_H.id))
+        .insert_header(("frontend_api_key", "TESTING"));
+    let res = req
+        .send_json(&GameTagsPatch {
+            add: vec![],
+            remove: vec!["hidden".to_string()],
+        })
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    let mut approved = None;
+    for _ in 0..100 {
+        let requests = received.requests.lock().unwrap().clone();
+        approved = requests.into_iter().find_map(|(_, event, body)| {
+            let delivered = serde_json::from_slice::<WebhookPayload>(&body).ok()?;
+            (delivered.event.game_id.as_deref() == Some(TEST_GAME_H.id.as_str())).then_some(event)
+        });
+        if approved.is_some() {
+            break;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(approved.as_deref(), Some("game_approved"));
+
+    let req = srv
+        .delete(format!("/api/webhooks/{}", webhook.id))
+        .insert_header(("frontend_api_key", "TESTING"));
+    let res = req.send().await.unwrap();
+    assert!(res.status().is_success());
+}
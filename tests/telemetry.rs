@@ -0,0 +1,102 @@
+//! Exporting turns on tracing for the whole process, so it is tested in a binary of its own
+use actix_web::{
+    rt::time::timeout,
+    web::{self, Json},
+    App, HttpResponse,
+};
+use devcade_api_rs::{
+    app::{configure_app, get_app_data},
+    config::Config,
+    telemetry::{
+        context::{TraceContext, TRACEPARENT_HEADER},
+        export::{self, TelemetryConfig},
+        middleware::TraceRequests,
+        query::Traced,
+        span::Span,
+    },
+};
+use serde_json::Value;
+use sqlx::query;
+use std::time::Duration;
+
+const TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+const FAILED_TRACEPARENT: &str = "00-0af7651916cd43dd8448eb211c80319c-b7ad6b7169203331-01";
+
+#[actix_web::test]
+async fn test_export_request_spans() {
+    let (sender, mut exported) = tokio::sync::mpsc::unbounded_channel::<Value>();
+    let collector = actix_test::start(move || {
+        let sender = sender.clone();
+        App::new().route(
+            "/v1/traces",
+            web::post().to(move |body: Json<Value>| {
+                let _ = sender.send(body.into_inner());
+                async { HttpResponse::Ok().finish() }
+            }),
+        )
+    });
+    export::start(&TelemetryConfig {
+        endpoint: collector.url(""),
+        service_name: "devcade-api-test".to_string(),
+    });
+    let app_data = get_app_data(Config::load().unwrap()).await.unwrap();
+    let span = Span::server(
+        "failing query",
+        TraceContext::from_traceparent(FAILED_TRACEPARENT),
+    );
+    let failed = span
+        .scope(query("SELECT 1 / 0").execute(Traced(&app_data.db)))
+        .await;
+    assert!(failed.is_err());
+    span.end();
+    let srv = actix_test::start(move || {
+        App::new()
+            .wrap(TraceRequests)
+            .configure(configure_app)
+            .app_data(app_data.clone())
+    });
+    let res = srv
+        .get("/api/tags/")
+        .insert_header((TRACEPARENT_HEADER, TRACEPARENT))
+        .send()
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+
+    // Other tests may be tracing too, so only spans of these traces are kept
+    let (mut spans, mut failed_spans) = (vec![], vec![]);
+    while let Ok(Some(body)) = timeout(Duration::from_secs(15), exported.recv()).await {
+        for span in body["resourceSpans"][0]["scopeSpans"][0]["spans"]
+            .as_array()
+            .unwrap()
+        {
+            match span["traceId"].as_str() {
+                Some("4bf92f3577b34da6a3ce929d0e0e4736") => spans.push(span.clone()),
+                Some("0af7651916cd43dd8448eb211c80319c") => failed_spans.push(span.clone()),
+                _ => {}
+            }
+        }
+        if spans.len() >= 2 && failed_spans.len() >= 2 {
+            break;
+        }
+    }
+    let server = spans
+        .iter()
+        .find(|span| span["name"] == "GET /api/tags/")
+        .expect("request span exported");
+    assert_eq!(server["parentSpanId"], "00f067aa0ba902b7");
+    assert_eq!(server["kind"], 2);
+    let query = spans
+        .iter()
+        .find(|span| span["kind"] == 3)
+        .expect("query span exported");
+    assert_eq!(query["parentSpanId"], server["spanId"]);
+    assert_eq!(query["name"], "SELECT");
+
+    let failed = failed_spans
+        .iter()
+        .find(|span| span["kind"] == 3)
+        .expect("failed query span exported");
+    assert_eq!(failed["name"], "SELECT");
+    assert_eq!(failed["status"]["code"], 2);
+}